@@ -0,0 +1,73 @@
+use chunk_engine::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const CHUNKS: u64 = 1 << 16;
+const WINDOW: u64 = 4096;
+const STEP: u64 = 1024;
+
+// overlapping chunk sets: each window shares three quarters of its chunks with the previous one.
+fn windows() -> impl Iterator<Item = u64> {
+    (0..CHUNKS - WINDOW)
+        .step_by(STEP as usize)
+        .flat_map(|start| start..start + WINDOW)
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let config = MetaStoreConfig {
+        rocksdb: RocksDBConfig {
+            path: dir.path().into(),
+            create: true,
+            ..Default::default()
+        },
+        prefix_len: 4,
+        ..Default::default()
+    };
+    let meta_store = MetaStore::open(&config).unwrap();
+    let chunk_meta = ChunkMeta {
+        len: 4096,
+        ..Default::default()
+    };
+    for batch in 0..CHUNKS / 1024 {
+        let mut write_batch = RocksDB::new_write_batch();
+        for i in 0..1024 {
+            let chunk_id = (batch * 1024 + i).to_be_bytes();
+            meta_store
+                .add_chunk_mut(&chunk_id, &chunk_meta, &mut write_batch)
+                .unwrap();
+        }
+        meta_store.write(write_batch, false).unwrap();
+    }
+
+    c.bench_function("uncached", |b| {
+        b.iter(|| {
+            for i in windows() {
+                assert!(meta_store
+                    .get_chunk_meta(&i.to_be_bytes())
+                    .unwrap()
+                    .is_some());
+            }
+        })
+    });
+
+    for capacity in [1024, WINDOW as usize] {
+        c.bench_with_input(
+            BenchmarkId::new("cached", capacity),
+            &capacity,
+            |b, &capacity| {
+                b.iter(|| {
+                    let cache = MetaCache::new(capacity);
+                    for i in windows() {
+                        assert!(cache
+                            .get_chunk_meta(&meta_store, &i.to_be_bytes())
+                            .unwrap()
+                            .is_some());
+                    }
+                })
+            },
+        );
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,75 @@
+use chunk_engine::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use derse::Deserialize;
+use std::path::Path;
+
+fn meta_store_config(path: &Path, create: bool) -> MetaStoreConfig {
+    MetaStoreConfig {
+        rocksdb: RocksDBConfig {
+            path: path.into(),
+            create,
+            mode: if create {
+                DbOpenMode::ReadWrite
+            } else {
+                DbOpenMode::ReadOnly
+            },
+            ..Default::default()
+        },
+        prefix_len: 4,
+        ..Default::default()
+    }
+}
+
+fn populate(path: &Path, count: u64) {
+    let meta_store = MetaStore::open(&meta_store_config(path, true)).unwrap();
+    let chunk_meta = ChunkMeta {
+        len: 4096,
+        ..Default::default()
+    };
+    for batch in 0..count / 1024 {
+        let mut write_batch = RocksDB::new_write_batch();
+        for i in 0..1024 {
+            let chunk_id = (batch * 1024 + i).to_be_bytes();
+            meta_store
+                .add_chunk_mut(&chunk_id, &chunk_meta, &mut write_batch)
+                .unwrap();
+        }
+        meta_store.write(write_batch, false).unwrap();
+    }
+}
+
+// the same full scan of chunk metas the chunk viewer summary does.
+fn scan(meta_store: &MetaStore) -> u64 {
+    let mut it = meta_store.iterator();
+    it.seek(MetaKey::chunk_meta_key_prefix()).unwrap();
+    let mut total = 0u64;
+    while it.valid() && it.key().unwrap()[0] == MetaKey::CHUNK_META_KEY_PREFIX {
+        total += ChunkMeta::deserialize(it.value().unwrap()).unwrap().len as u64;
+        it.next();
+    }
+    total
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let count: u64 = 1 << 18;
+    populate(dir.path(), count);
+
+    let default_config = meta_store_config(dir.path(), false);
+    let mut tuned_config = meta_store_config(dir.path(), false);
+    tuned_config.rocksdb.tuning = RocksDBTuning {
+        block_cache_size: Some(Size::mebibyte(8)),
+        bloom_filter: false,
+        readahead_size: Size::mebibyte(16),
+    };
+
+    for (name, config) in [("default", default_config), ("tuned", tuned_config)] {
+        let meta_store = MetaStore::open(&config).unwrap();
+        c.bench_with_input(BenchmarkId::new(name, count), &count, |b, &c| {
+            b.iter(|| assert_eq!(scan(&meta_store), c * 4096))
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
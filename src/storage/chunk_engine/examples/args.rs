@@ -1,46 +1,947 @@
-use clap::Parser;
-use std::path::PathBuf;
+use chunk_engine::{ChunkChecksum, DbOpenMode, Error, MetaKey, Result, RocksDBConfig, RocksDBTuning, Size};
+use clap::{builder::RangedU64ValueParser, error::ErrorKind, ArgAction, ArgGroup, Command, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use clap_complete::Shell;
+use std::{
+    ffi::OsString,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use super::chunk_lister::DEFAULT_PRECISION;
+use super::utils::parse_size_string;
+
+/// Environment variable naming the store to inspect when no path is given
+pub const PATH_ENV_VAR: &str = "CHUNK_VIEWER_PATH";
+
+/// Output format for reports
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable tables
+    #[default]
+    Table,
+    /// A single JSON document
+    Json,
+    /// One JSON object per line, e.g. one per chunk of a --list-size page
+    JsonLines,
+    /// Comma-separated values with a header line, only for --list-size and --list-all
+    Csv,
+    /// Tab-separated values with a header line, only for --list-size and --list-all
+    Tsv,
+}
+
+impl OutputFormat {
+    /// Field delimiter of the CSV and TSV formats, `None` for the others
+    pub fn delimiter(self) -> Option<char> {
+        match self {
+            OutputFormat::Csv => Some(','),
+            OutputFormat::Tsv => Some('\t'),
+            OutputFormat::Table | OutputFormat::Json | OutputFormat::JsonLines => None,
+        }
+    }
+}
+
+/// Output format for chunk content
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContentFormat {
+    /// Hex dump with offsets and an ASCII column
+    #[default]
+    Hex,
+    /// The raw bytes
+    Binary,
+    /// The bytes as UTF-8, invalid sequences replaced
+    Text,
+    /// A C array declaration
+    CArray,
+    /// A Rust array declaration
+    RustArray,
+}
+
+/// Checksum algorithm of --verify
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChecksumAlgo {
+    /// CRC32C, as written by the engine
+    #[default]
+    Crc32c,
+    /// CRC32 (IEEE)
+    Crc32,
+    /// XXH64 with seed 0, truncated to the low 32 bits
+    #[value(name = "xxhash64")]
+    XxHash64,
+}
+
+impl From<ChecksumAlgo> for ChunkChecksum {
+    fn from(algo: ChecksumAlgo) -> Self {
+        match algo {
+            ChecksumAlgo::Crc32c => ChunkChecksum::Crc32c,
+            ChecksumAlgo::Crc32 => ChunkChecksum::Crc32,
+            ChecksumAlgo::XxHash64 => ChunkChecksum::XxHash64,
+        }
+    }
+}
+
+/// Rendering style of chunk tables
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableStyle {
+    /// Space padded fixed-width columns, stable for scripting
+    #[default]
+    Plain,
+    /// Unicode box-drawing borders with columns sized to the data
+    Box,
+}
+
+/// When to color table output
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and NO_COLOR is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// How the tool opens RocksDB for reading
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OpenMode {
+    /// Open a snapshot of a store that no process is writing to
+    #[default]
+    ReadOnly,
+    /// Follow a live primary without blocking it (requires --secondary-path)
+    Secondary,
+}
 
 /// A chunk viewer tool for analyzing RocksDB chunk metadata.
 #[derive(Parser, Debug, Clone)]
-#[command(version, about, long_about = None)]
+#[command(name = "chunk_viewer", version, about, long_about = None)]
+#[command(group(ArgGroup::new("dry_run_target").args(["defragment", "migrate_to", "release_reservations", "rebucket", "delete_from_manifest"])))]
+#[command(group(ArgGroup::new("chunk_content").args(["read_chunk", "diff_chunks"])))]
+#[command(group(ArgGroup::new("detailed_listing").args(["list_size", "list_all"])))]
+#[command(group(ArgGroup::new("live").args(["watch", "follow"])))]
 pub struct Args {
-    /// Path to rocksdb.
-    pub path: PathBuf,
+    /// Path to rocksdb (default: $CHUNK_VIEWER_PATH)
+    pub path: Option<PathBuf>,
+    
+    /// Read default values for the other options from this TOML file, keys are the option names with underscores (e.g., page_size = 50)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    
+    /// Print a completion script for SHELL (bash, zsh, fish, powershell) to stdout and exit
+    #[arg(long, value_name = "SHELL", hide = true, exclusive = true)]
+    pub generate_completions: Option<Shell>,
+    
+    /// How to open RocksDB: read-only, secondary (default: read-only)
+    #[arg(long, value_enum, default_value_t = OpenMode::ReadOnly)]
+    pub mode: OpenMode,
+    
+    /// Directory for the secondary instance's own files, must differ from the store path
+    #[arg(long, value_name = "DIR", required_if_eq("mode", "secondary"))]
+    pub secondary_path: Option<PathBuf>,
+    
+    /// RocksDB block cache size, "0" disables the cache (e.g., "8MB"; default: RocksDB's own)
+    #[arg(long, value_name = "SIZE")]
+    pub block_cache_size: Option<String>,
+    
+    /// Skip loading bloom filters, they only speed up point lookups and not full scans
+    #[arg(long)]
+    pub no_bloom_filter: bool,
+    
+    /// Read-ahead size used by RocksDB iterators (e.g., "16MB")
+    #[arg(long, value_name = "SIZE", default_value = "4MB")]
+    pub readahead_size: String,
+    
+    /// Keep up to N chunk metas in memory so repeated lookups of the same chunk skip RocksDB, "0" disables the cache
+    #[arg(long, value_name = "N", default_value_t = 1024)]
+    pub meta_cache_size: usize,
+    
+    /// Give up with a timeout error if opening the store takes longer than SECS, e.g. on a hung network filesystem
+    #[arg(long, value_name = "SECS", value_parser = RangedU64ValueParser::<u64>::new().range(1..))]
+    pub open_timeout: Option<u64>,
+    
+    /// First byte of the chunk meta keys, for stores written with another key layout, in decimal or hex like "0x01"
+    #[arg(long, value_name = "BYTE", default_value_t = MetaKey::CHUNK_META_KEY_PREFIX, value_parser = parse_key_prefix)]
+    pub chunk_meta_prefix: u8,
+    
+    /// Print diagnostics such as meta cache hits and misses to stderr
+    #[arg(long, short)]
+    pub verbose: bool,
+    
+    /// Print only the answer: the content for --read-chunk, yes or no per chunk for --lookup-chunks and the numbers for --bucket-stats; notices go to stderr
+    #[arg(long, short, conflicts_with = "verbose")]
+    pub quiet: bool,
+    
+    /// Print wall-clock time, RocksDB keys scanned and looked up, and chunk data reads to stderr when the command completes
+    #[arg(long)]
+    pub timing: bool,
+    
+    /// Directory holding the chunk data files (default: the parent of the RocksDB path)
+    #[arg(long, value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
     
     /// List detailed information for chunks of specific size bucket (e.g., "64KB", "8MB", "1GB" or raw bytes)
     #[arg(long, value_name = "SIZE")]
     pub list_size: Option<String>,
     
+    /// List detailed information for the chunks of every size bucket in turn, with totals per bucket and for all of them
+    #[arg(long)]
+    pub list_all: bool,
+    
+    /// With --list-all, show at most N chunks of each size bucket (default: all of them)
+    #[arg(long, value_name = "N", requires = "list_all", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub limit_per_bucket: Option<usize>,
+    
+    /// Show only the chunk count and byte totals of a size bucket, in one pass and without paging (e.g., "4MB")
+    #[arg(long, value_name = "SIZE")]
+    pub bucket_stats: Option<String>,
+    
+    /// Show p50, p90, p99 and max chunk length of a size bucket (e.g., "1MB"), or of all chunks without SIZE
+    #[arg(long, value_name = "SIZE", num_args = 0..=1)]
+    pub length_percentiles: Option<Option<String>>,
+    
+    /// With --length-percentiles, compute exact values by buffering every length instead of estimating them
+    #[arg(long, requires = "length_percentiles")]
+    pub exact: bool,
+    
+    /// Show allocated, reserved and used slots of every size bucket, flagging buckets with too many reserved slots
+    #[arg(long)]
+    pub reservation_audit: bool,
+    
+    /// With --reservation-audit, flag buckets where reserved slots exceed this percentage of allocated slots (default: 50)
+    #[arg(long, value_name = "PERCENT", default_value_t = 50.0, requires = "reservation_audit")]
+    pub reserved_threshold: f64,
+    
+    /// Show how tightly the allocator packs the groups of every size bucket, and overall
+    #[arg(long)]
+    pub allocator_efficiency: bool,
+    
+    /// Show chunk count and average utilization per chain version, lowest utilization first
+    #[arg(long)]
+    pub chain_utilization: bool,
+    
+    /// Show a row of shaded cells per size bucket, its chunks from least to most utilized, to spot wasted space at a glance
+    #[arg(long)]
+    pub heatmap: bool,
+    
+    /// With --heatmap, shade with ASCII characters instead of block glyphs
+    #[arg(long, requires = "heatmap")]
+    pub ascii: bool,
+    
+    /// Count the chunks of each size bucket whose length fits in the next smaller bucket, and the bytes moving them would reclaim
+    #[arg(long)]
+    pub rebucket_candidates: bool,
+    
+    /// List every allocator group of a size bucket with its used and free slots (e.g., "1MB")
+    #[arg(long, value_name = "SIZE")]
+    pub list_groups: Option<String>,
+    
+    /// Dump the allocator state of a size bucket as JSON, every group with its slot bitmap and the counters, for reproducing allocator bugs (e.g., "1MB")
+    #[arg(long, value_name = "SIZE")]
+    pub dump_allocator: Option<String>,
+    
+    /// With --list-size, only list chunks in the group with this index (see --list-groups)
+    #[arg(long, value_name = "INDEX", requires = "list_size")]
+    pub group: Option<u32>,
+    
+    /// With --list-size, only list chunks with no data
+    #[arg(long, requires = "list_size")]
+    pub empty_only: bool,
+    
+    /// With --list-size, only list chunks whose length equals the bucket size
+    #[arg(long, requires = "list_size", conflicts_with = "empty_only")]
+    pub full_only: bool,
+    
+    /// With --list-size, only list chunks whose last write was never committed
+    #[arg(long, requires = "list_size")]
+    pub uncommitted_only: bool,
+    
+    /// With --list-size, print only the totals of the chunks the filters select, without listing them
+    #[arg(long, requires = "list_size", conflicts_with_all = ["page", "find_page", "interactive"])]
+    pub stats_only: bool,
+    
+    /// Round --list-size up to the next real size bucket instead of rejecting it
+    #[arg(long, requires = "list_size")]
+    pub nearest_bucket: bool,
+    
+    /// Leave size buckets with fewer than N used chunks out of the summary tables (default: 0, show all)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub min_chunks: u64,
+    
+    /// Decimal places of utilization percentages and sizes in the detailed listings, from 0 to 4 (default: 2)
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_PRECISION, value_parser = RangedU64ValueParser::<usize>::new().range(0..=4))]
+    pub precision: usize,
+    
     /// Number of chunks to display per page (default: 20)
-    #[arg(long, default_value = "20")]
+    #[arg(long, default_value = "20", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
     pub page_size: usize,
     
     /// Page number to display (default: 1)
-    #[arg(long, default_value = "1")]
+    #[arg(long, default_value = "1", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
     pub page: usize,
     
+    /// With --list-size, show the page of the listing that holds this chunk instead of --page
+    #[arg(long, value_name = "CHUNK_ID", requires = "list_size", conflicts_with = "page")]
+    pub find_page: Option<String>,
+    
+    /// With --list-size, show the first N chunks in sorted order instead of a page
+    #[arg(long, value_name = "N", requires = "list_size", conflicts_with_all = ["page", "find_page", "interactive"], value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub first_n: Option<usize>,
+    
+    /// With --list-size, show the last N chunks in sorted order instead of a page
+    #[arg(long, value_name = "N", requires = "list_size", conflicts_with_all = ["page", "find_page", "interactive", "first_n"], value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub last_n: Option<usize>,
+    
+    /// Browse the detailed listing page by page with n/p/q keys (requires a TTY)
+    #[arg(long)]
+    pub interactive: bool,
+    
     /// Show only summary statistics (default behavior)
     #[arg(long)]
     pub summary_only: bool,
     
+    /// Output format for reports: table, json, json-lines (default: table)
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output_format: OutputFormat,
+    
+    /// Refresh the summary periodically until interrupted with Ctrl-C
+    #[arg(long)]
+    pub watch: bool,
+    
+    /// Write the metadata of every chunk to a Parquet file with a fixed schema (needs the parquet feature)
+    #[arg(long, value_name = "FILE")]
+    pub export_parquet: Option<PathBuf>,
+    
+    /// Print each chunk a running primary creates as it appears, like tail -f (requires --mode secondary)
+    #[arg(long)]
+    pub follow: bool,
+    
+    /// Seconds between refreshes in --watch mode, or between polls in --follow mode, at least 1 (default: 5)
+    #[arg(long, value_name = "SECS", default_value = "5", requires = "live", value_parser = RangedU64ValueParser::<u64>::new().range(1..))]
+    pub interval: u64,
+    
     /// Show short chunk IDs (first 16 hex chars) for compact display
     #[arg(long)]
     pub short_ids: bool,
     
+    /// Follow the utilization in the --list-size and --list-all tables with a bar such as [####------]
+    #[arg(long, requires = "detailed_listing")]
+    pub util_bars: bool,
+    
+    /// Add a checksum column to the --list-size and --list-all tables, JSON output always includes it
+    #[arg(long, requires = "detailed_listing")]
+    pub show_checksum: bool,
+    
+    /// Color utilization, uncommitted chunks and warnings in tables: auto, always, never (default: auto, only on a terminal)
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+    
+    /// Table style for --list-size and --list-all: plain, box (default: plain)
+    #[arg(long, value_enum, default_value_t = TableStyle::Plain)]
+    pub table_style: TableStyle,
+    
     /// Read and display content of a specific chunk by ID (hex format)
     #[arg(long, value_name = "CHUNK_ID")]
     pub read_chunk: Option<String>,
     
-    /// Output format for chunk content: hex, binary, text (default: hex)
-    #[arg(long, default_value = "hex")]
-    pub content_format: String,
+    /// With --read-chunk, read the chunk as of this chunk version, fails if the chunk has moved past it as only the latest version is kept
+    #[arg(long, value_name = "V", requires = "read_chunk")]
+    pub at_version: Option<u32>,
+    
+    /// Compare the chunk given by --read-chunk byte-for-byte against a local file
+    #[arg(long, value_name = "PATH", requires = "read_chunk")]
+    pub compare_file: Option<String>,
+    
+    /// Look up the chunks whose full IDs (hex format) are listed one per line in FILE, "-" for stdin, and print their metadata
+    #[arg(long, value_name = "FILE")]
+    pub lookup_chunks: Option<String>,
+    
+    /// With --lookup-chunks, scan the chunk keys once first so IDs that cannot exist skip their RocksDB lookup
+    #[arg(long, requires = "lookup_chunks")]
+    pub prebuild_index: bool,
+    
+    /// Show the serialized metadata of a chunk as a hex dump next to its decoded fields (hex format)
+    #[arg(long, value_name = "CHUNK_ID")]
+    pub dump_meta: Option<String>,
+    
+    /// Only summarize, list or verify chunks with an ID from this one on (hex format)
+    #[arg(long, value_name = "HEX")]
+    pub id_from: Option<String>,
+    
+    /// Only summarize, list or verify chunks with an ID below this one (hex format)
+    #[arg(long, value_name = "HEX")]
+    pub id_to: Option<String>,
+    
+    /// Expected chunk ID length in bytes, IDs of another length that are not found are rejected (default: only warn below 16)
+    #[arg(long, value_name = "BYTES", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub id_len: Option<usize>,
+    
+    /// Diff two chunks byte-for-byte by ID (hex format)
+    #[arg(long, num_args = 2, value_names = ["ID_A", "ID_B"])]
+    pub diff_chunks: Option<Vec<String>>,
+    
+    /// With --diff-chunks, show at most this many differing regions
+    #[arg(long, value_name = "N", default_value = "10", requires = "diff_chunks", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub max_diffs: usize,
+    
+    /// Output format for chunk content: hex, binary, text, c-array, rust-array, in any case (default: hex)
+    #[arg(long, value_enum, ignore_case = true, default_value_t = ContentFormat::Hex)]
+    pub content_format: ContentFormat,
     
     /// Output chunk content to file instead of stdout
     #[arg(long, value_name = "FILE")]
     pub output_file: Option<String>,
     
+    /// Compress --output-file or --corrupt-manifest with gzip as it is written
+    #[arg(long)]
+    pub gzip: bool,
+    
+    /// Create missing parent directories of --output-file or --corrupt-manifest
+    #[arg(long)]
+    pub mkdir_parents: bool,
+    
     /// Show text preview (first 256 bytes as text) along with hex/binary
     #[arg(long)]
     pub show_preview: bool,
-}
\ No newline at end of file
+    
+    /// With --read-chunk, show the entropy, zero byte count and byte value summary of the content
+    #[arg(long, requires = "read_chunk")]
+    pub content_stats: bool,
+    
+    /// Refuse to read chunks whose last write was never committed, their data may be partial (override with --force)
+    #[arg(long, requires = "chunk_content")]
+    pub committed_only_reads: bool,
+    
+    /// Map chunk data into memory instead of reading it into a buffer, so large chunks are paged in as they are output
+    #[arg(long, requires = "chunk_content")]
+    pub mmap: bool,
+    
+    /// Read every chunk and verify its data against the stored checksum
+    #[arg(long)]
+    pub verify: bool,
+    
+    /// Check every chunk's length against its capacity and read only its first and last byte, a fast check for truncation
+    #[arg(long, conflicts_with = "verify")]
+    pub verify_lengths: bool,
+    
+    /// Count the chunks holding data whose stored checksum is 0 per size bucket, usually a checksum that was never computed
+    #[arg(long, conflicts_with_all = ["verify", "verify_lengths"])]
+    pub zero_checksum: bool,
+    
+    /// With --zero-checksum, read the data of every zero checksum chunk to tell data that really hashes to 0
+    #[arg(long, requires = "zero_checksum")]
+    pub recompute: bool,
+    
+    /// With --zero-checksum, list every zero checksum chunk as well
+    #[arg(long, requires = "zero_checksum")]
+    pub show_chunks: bool,
+    
+    /// Checksum algorithm used by --verify (default: crc32c, as written by the engine)
+    #[arg(long, value_enum, ignore_case = true, default_value_t = ChecksumAlgo::Crc32c)]
+    pub checksum_algo: ChecksumAlgo,
+    
+    /// Number of threads --verify splits the chunk ID space across (default: 1)
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "verify")]
+    pub threads: usize,
+    
+    /// With --verify, write every corrupt chunk to FILE as JSON lines
+    #[arg(long, value_name = "FILE", requires = "verify")]
+    pub corrupt_manifest: Option<String>,
+    
+    /// With --verify, save progress to FILE periodically and resume from it if it exists
+    #[arg(long, value_name = "FILE", requires = "verify")]
+    pub checkpoint: Option<PathBuf>,
+    
+    /// Check that the chunk meta keys are strictly increasing, so no two decode to the same chunk ID
+    #[arg(long)]
+    pub check_key_order: bool,
+    
+    /// Count the chunks of every distinct chunk ID prefix the meta store groups keys by and report their spread and hotspots
+    #[arg(long)]
+    pub prefix_distribution: bool,
+    
+    /// With --prefix-distribution, how many of the hottest prefixes to list (default: 10)
+    #[arg(long, value_name = "N", default_value_t = 10, requires = "prefix_distribution")]
+    pub top: usize,
+    
+    /// Print every raw chunk meta key in hex with the chunk ID decoded from it and the length of its value
+    #[arg(long)]
+    pub raw_keys: bool,
+    
+    /// With --raw-keys, stop after N keys
+    #[arg(long, value_name = "N", requires = "raw_keys")]
+    pub limit: Option<usize>,
+    
+    /// Print RocksDB properties such as key estimates, SST sizes and block cache usage
+    #[arg(long)]
+    pub rocksdb_stats: bool,
+    
+    /// Print this RocksDB property instead of the default set, can be repeated (e.g., "rocksdb.stats")
+    #[arg(long, value_name = "NAME")]
+    pub rocksdb_property: Vec<String>,
+    
+    /// Write N chunks of random data (dev mode, opens the engine read-write)
+    #[arg(long, value_name = "N", requires = "size")]
+    pub write_random: Option<usize>,
+    
+    /// Size of each chunk written by --write-random (e.g., "64KB", "1MB" or raw bytes)
+    #[arg(long, value_name = "SIZE")]
+    pub size: Option<String>,
+    
+    /// Move chunks out of sparse groups of a size bucket and free them (opens the engine read-write)
+    #[arg(long, value_name = "SIZE")]
+    pub defragment: Option<String>,
+    
+    /// Release slots of a size bucket that are taken but held by no chunk meta, e.g. after a crash (opens the engine read-write, needs --force)
+    #[arg(long, value_name = "SIZE", conflicts_with_all = ["defragment", "migrate_to"])]
+    pub release_reservations: Option<String>,
+    
+    /// Move the chunks of a size bucket whose length fits in the next smaller bucket down to it (opens the engine read-write)
+    #[arg(long, value_name = "SIZE", conflicts_with_all = ["defragment", "migrate_to", "release_reservations"])]
+    pub rebucket: Option<String>,
+    
+    /// Delete the chunks listed in a --corrupt-manifest file, all in one atomic write (opens the engine read-write, needs --force)
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["defragment", "migrate_to", "release_reservations", "rebucket"])]
+    pub delete_from_manifest: Option<String>,
+    
+    /// Confirm a destructive operation such as --release-reservations or --delete-from-manifest, or read uncommitted chunks despite --committed-only-reads
+    #[arg(long)]
+    pub force: bool,
+    
+    /// Compare the chunk metadata of this store with the store at OTHER: chunks only in one of them, and chunks whose length, checksum or versions differ
+    #[arg(long, value_name = "OTHER")]
+    pub diff_store: Option<PathBuf>,
+    
+    /// Copy chunks from this store into the store at DEST, keeping their versions (opens DEST read-write)
+    #[arg(long, value_name = "DEST", conflicts_with = "defragment")]
+    pub migrate_to: Option<PathBuf>,
+    
+    /// With --migrate-to, only copy chunks whose ID starts with these hex digits (e.g., "01ab")
+    #[arg(long, value_name = "HEX", requires = "migrate_to")]
+    pub id_prefix: Option<String>,
+    
+    /// With --defragment, --migrate-to, --release-reservations, --rebucket or --delete-from-manifest, only report what would be done without writing anything
+    #[arg(long, requires = "dry_run_target")]
+    pub dry_run: bool,
+}
+
+impl Args {
+    /// Parse the command line, taking defaults from the --config file if one is given: flags override the file, which overrides the built-in defaults
+    pub fn try_parse_with_config() -> clap::error::Result<Self> {
+        Self::try_parse_from_sources(std::env::args_os(), std::env::var_os(PATH_ENV_VAR))
+    }
+
+    /// Parse `args`, falling back to `env_path`, the value of CHUNK_VIEWER_PATH, when neither they nor the config file give a store path
+    pub fn try_parse_from_sources<I, T>(args: I, env_path: Option<OsString>) -> clap::error::Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString>,
+    {
+        let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let mut command = Self::command();
+        
+        // The file has to be read before clap parses the rest, as its values become the defaults
+        if let Some(path) = config_path(&args) {
+            let text = std::fs::read_to_string(&path)
+                .map_err(|e| command.error(ErrorKind::Io, format!("Failed to read config file {}: {}", path.display(), e)))?;
+            let config: toml::Table = text.parse()
+                .map_err(|e| command.error(ErrorKind::InvalidValue, format!("Invalid config file {}: {}", path.display(), e)))?;
+            let unknown_keys;
+            (command, unknown_keys) = apply_config(command, &config)?;
+            for key in unknown_keys {
+                eprintln!("Warning: ignoring unknown key '{}' in config file {}", key, path.display());
+            }
+        }
+        
+        let mut matches = command.try_get_matches_from_mut(args)?;
+        let mut this = Self::from_arg_matches_mut(&mut matches)?;
+        
+        if this.output_format.delimiter().is_some() && this.list_size.is_none() && !this.list_all {
+            return Err(command.error(ErrorKind::ArgumentConflict,
+                "--output-format csv and tsv are only supported by --list-size and --list-all"));
+        }
+        
+        if this.output_format.delimiter().is_some() && this.stats_only {
+            return Err(command.error(ErrorKind::ArgumentConflict,
+                "--stats-only prints no rows, use --output-format table, json or json-lines"));
+        }
+        
+        // A live primary may rewrite or reuse the slot of a mapped chunk under the reader
+        if this.mmap && this.mode == OpenMode::Secondary {
+            return Err(command.error(ErrorKind::ArgumentConflict,
+                "--mmap needs a store no process is writing to, read chunks without it in --mode secondary"));
+        }
+        
+        // Chunk metadata and headers share stdout with the content, so compressing stdout would mix text into the stream
+        if this.gzip && this.output_file.is_none() && this.corrupt_manifest.is_none() {
+            return Err(command.error(ErrorKind::MissingRequiredArgument,
+                "--gzip compresses --output-file or --corrupt-manifest, pass one of them, or pipe stdout through gzip instead"));
+        }
+        
+        if this.mkdir_parents && this.output_file.is_none() && this.corrupt_manifest.is_none() {
+            return Err(command.error(ErrorKind::MissingRequiredArgument,
+                "--mkdir-parents creates the directories of --output-file or --corrupt-manifest, pass one of them"));
+        }
+        
+        if this.path.is_none() && this.generate_completions.is_none() {
+            match env_path.filter(|path| !path.is_empty()) {
+                Some(path) => this.path = Some(path.into()),
+                None => return Err(command.error(ErrorKind::MissingRequiredArgument,
+                    format!("no store given, pass <PATH> or set {}", PATH_ENV_VAR))),
+            }
+        }
+        Ok(this)
+    }
+
+    /// Path of the store to inspect, only missing with --generate-completions
+    pub fn store_path(&self) -> &Path {
+        self.path.as_deref().expect("the store path is required")
+    }
+
+    /// Write the completion script for `shell`, generated from the flags declared here so it never goes stale
+    pub fn write_completions(shell: Shell, out: &mut dyn Write) {
+        clap_complete::generate(shell, &mut Self::command(), "chunk_viewer", out);
+    }
+
+    /// RocksDB config for the metadata store at `path`
+    pub fn rocksdb_config(&self) -> Result<RocksDBConfig> {
+        let block_cache_size = match &self.block_cache_size {
+            Some(size) => Some(Size::from(parse_size_string(size)?)),
+            None => None,
+        };
+        
+        Ok(RocksDBConfig {
+            path: self.store_path().into(),
+            create: false,
+            mode: self.db_open_mode(),
+            tuning: RocksDBTuning {
+                block_cache_size,
+                bloom_filter: !self.no_bloom_filter,
+                readahead_size: Size::from(parse_size_string(&self.readahead_size)?),
+            },
+        })
+    }
+    
+    /// Reject --read-chunk output that would come out garbled, and return a warning for output that is merely unwise
+    pub fn check_content_output(&self, stdout_is_tty: bool) -> Result<Option<String>> {
+        if self.content_format != ContentFormat::Binary || self.output_file.is_some() {
+            return Ok(None);
+        }
+        if self.show_preview {
+            return Err(Error::InvalidArg("--show-preview would interleave its text with the binary content on stdout, write the content with --output-file instead".to_string()));
+        }
+        if stdout_is_tty {
+            return Ok(Some("writing binary content to a terminal, redirect stdout or pass --output-file".to_string()));
+        }
+        Ok(None)
+    }
+    
+    /// RocksDB open mode for the read paths of the tool
+    pub fn db_open_mode(&self) -> DbOpenMode {
+        match (self.mode, &self.secondary_path) {
+            (OpenMode::Secondary, Some(wal_dir)) => DbOpenMode::Secondary { wal_dir: wal_dir.clone() },
+            _ => DbOpenMode::ReadOnly,
+        }
+    }
+}
+
+/// A key prefix byte, in decimal or in hex after "0x"
+fn parse_key_prefix(value: &str) -> std::result::Result<u8, String> {
+    let parsed = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => value.parse::<u8>(),
+    };
+    parsed.map_err(|_| format!("{} is not a single byte, use 0 to 255 or 0x00 to 0xff", value))
+}
+
+/// The value of --config on the command line, found without clap as it must be read before parsing
+fn config_path(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1).map(|arg| arg.to_str());
+    while let Some(arg) = args.next() {
+        match arg {
+            Some("--") => break,
+            Some("--config") => return args.next().flatten().map(PathBuf::from),
+            Some(arg) if arg.starts_with("--config=") => return Some(PathBuf::from(&arg["--config=".len()..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Turn every key of the config file that names an option into that option's default, returns the keys that name none
+fn apply_config(mut command: Command, config: &toml::Table) -> clap::error::Result<(Command, Vec<String>)> {
+    let mut unknown_keys = vec![];
+    for (key, value) in config {
+        let known = command.get_arguments().any(|arg| {
+            arg.get_id() == key.as_str() && key != "config" && !matches!(arg.get_action(), ArgAction::Help | ArgAction::Version)
+        });
+        if !known {
+            unknown_keys.push(key.clone());
+            continue;
+        }
+        
+        let values = match value {
+            toml::Value::Array(values) => values.iter().map(config_value).collect::<Option<Vec<_>>>(),
+            value => config_value(value).map(|value| vec![value]),
+        };
+        let Some(values) = values else {
+            return Err(command.error(ErrorKind::InvalidValue, format!("Config key '{}' must be a string, number, boolean or an array of them", key)));
+        };
+        command = command.mut_arg(key, |arg| arg.default_values(values));
+    }
+    Ok((command, unknown_keys))
+}
+
+fn config_value(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(value) => Some(value.clone()),
+        toml::Value::Integer(value) => Some(value.to_string()),
+        toml::Value::Float(value) => Some(value.to_string()),
+        toml::Value::Boolean(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_completions() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut script = Vec::new();
+            Args::write_completions(shell, &mut script);
+            let script = String::from_utf8(script).unwrap();
+            assert!(script.contains("chunk_viewer"), "{:?}", shell);
+            assert!(script.contains("list-size"), "{:?}", shell);
+        }
+        
+        // The positional store path is not needed to generate a script
+        let args = Args::try_parse_from(["chunk_viewer", "--generate-completions", "zsh"]).unwrap();
+        assert_eq!(args.generate_completions, Some(Shell::Zsh));
+    }
+
+    #[test]
+    fn test_path_from_env() {
+        let env_path = || Some(OsString::from("/env/meta"));
+        let args = Args::try_parse_from_sources(["chunk_viewer"], env_path()).unwrap();
+        assert_eq!(args.store_path(), Path::new("/env/meta"));
+        
+        // An explicit path wins over the environment
+        let args = Args::try_parse_from_sources(["chunk_viewer", "/cli/meta"], env_path()).unwrap();
+        assert_eq!(args.store_path(), Path::new("/cli/meta"));
+        
+        for env_path in [None, Some(OsString::new())] {
+            let error = Args::try_parse_from_sources(["chunk_viewer"], env_path).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::MissingRequiredArgument);
+            assert!(error.to_string().contains(PATH_ENV_VAR));
+        }
+        
+        let args = Args::try_parse_from_sources(["chunk_viewer", "--generate-completions", "bash"], None).unwrap();
+        assert!(args.path.is_none());
+    }
+
+    #[test]
+    fn test_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("chunk_viewer.toml");
+        std::fs::write(&config, r#"
+            path = "/data/meta"
+            page_size = 50
+            output_format = "json-lines"
+            short_ids = true
+            rocksdb_property = ["rocksdb.stats", "rocksdb.num-snapshots"]
+            no_such_option = 1
+        "#).unwrap();
+        let config = config.to_str().unwrap();
+        
+        // Values from the file stand in for absent flags
+        let args = Args::try_parse_from_sources(["chunk_viewer", "--config", config], None).unwrap();
+        assert_eq!(args.store_path(), Path::new("/data/meta"));
+        assert_eq!(args.page_size, 50);
+        assert_eq!(args.output_format, OutputFormat::JsonLines);
+        assert!(args.short_ids);
+        assert_eq!(args.rocksdb_property, ["rocksdb.stats", "rocksdb.num-snapshots"]);
+        assert_eq!(args.page, 1);
+        
+        // Flags given on the command line win
+        let config_arg = format!("--config={}", config);
+        let args = Args::try_parse_from_sources(["chunk_viewer", "/other/meta", &config_arg, "--page-size", "10", "--output-format", "table"], None).unwrap();
+        assert_eq!(args.store_path(), Path::new("/other/meta"));
+        assert_eq!(args.page_size, 10);
+        assert_eq!(args.output_format, OutputFormat::Table);
+        
+        let (_, unknown_keys) = apply_config(Args::command(), &r#"no_such_option = 1
+help = true
+page = 2"#.parse().unwrap()).unwrap();
+        assert_eq!(unknown_keys, ["help", "no_such_option"]);
+        
+        // Config values go through the same validation as flags
+        std::fs::write(dir.path().join("bad.toml"), "page_size = 0").unwrap();
+        let bad = dir.path().join("bad.toml");
+        assert!(Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--config", bad.to_str().unwrap()], None).is_err());
+    }
+
+    #[test]
+    fn test_content_format() {
+        let args = Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--read-chunk", "1234", "--content-format", "rust-array"], None).unwrap();
+        assert_eq!(args.content_format, ContentFormat::RustArray);
+        let args = Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--read-chunk", "1234"], None).unwrap();
+        assert_eq!(args.content_format, ContentFormat::Hex);
+        
+        // An unknown format fails while parsing, before any store is opened or chunk read, and lists the valid ones
+        let error = Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--read-chunk", "1234", "--content-format", "yaml"], None).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidValue);
+        let message = error.to_string();
+        for format in ["hex", "binary", "text", "c-array", "rust-array"] {
+            assert!(message.contains(format), "{}", message);
+        }
+        
+        // The case of the format does not matter, also for the binary preview check
+        for format in ["HEX", "Hex", "hex"] {
+            let args = Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--read-chunk", "1234", "--content-format", format], None).unwrap();
+            assert_eq!(args.content_format, ContentFormat::Hex);
+        }
+        for format in ["BINARY", "Binary", "binary"] {
+            let args = Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--read-chunk", "1234", "--content-format", format, "--show-preview"], None).unwrap();
+            assert_eq!(args.content_format, ContentFormat::Binary);
+            assert!(matches!(args.check_content_output(false), Err(Error::InvalidArg(_))));
+        }
+    }
+
+    #[test]
+    fn test_checksum_algo() {
+        let args = Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--verify"], None).unwrap();
+        assert_eq!(ChunkChecksum::from(args.checksum_algo), ChunkChecksum::ENGINE);
+        for (value, algo) in [("crc32", ChunkChecksum::Crc32), ("XXHASH64", ChunkChecksum::XxHash64), ("Crc32c", ChunkChecksum::Crc32c)] {
+            let args = Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--verify", "--checksum-algo", value], None).unwrap();
+            assert_eq!(ChunkChecksum::from(args.checksum_algo), algo);
+        }
+        
+        // Every algorithm maps to the engine's name for it, an unknown one fails while parsing and lists them
+        for algo in ChecksumAlgo::value_variants() {
+            assert_eq!(algo.to_possible_value().unwrap().get_name(), ChunkChecksum::from(*algo).name());
+        }
+        let error = Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--verify", "--checksum-algo", "md5"], None).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidValue);
+        let message = error.to_string();
+        for algo in ["crc32c", "crc32", "xxhash64"] {
+            assert!(message.contains(algo), "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_content_output() {
+        let parse = |extra: &[&str]| Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--read-chunk", "1234"].iter().chain(extra), None).unwrap();
+        
+        // A preview on stdout would corrupt the binary stream, whether or not stdout is a terminal
+        let args = parse(&["--content-format", "binary", "--show-preview"]);
+        assert!(matches!(args.check_content_output(false), Err(Error::InvalidArg(_))));
+        assert!(matches!(args.check_content_output(true), Err(Error::InvalidArg(_))));
+        
+        // Binary on a terminal is allowed with a warning, a pipe or a file is fine
+        let args = parse(&["--content-format", "binary"]);
+        assert!(args.check_content_output(true).unwrap().is_some());
+        assert!(args.check_content_output(false).unwrap().is_none());
+        let args = parse(&["--content-format", "binary", "--show-preview", "--output-file", "/tmp/chunk.bin"]);
+        assert!(args.check_content_output(true).unwrap().is_none());
+        let args = parse(&["--show-preview"]);
+        assert!(args.check_content_output(true).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_gzip_and_mkdir_parents_need_output() {
+        let parse = |extra: &[&str]| Args::try_parse_from_sources(["chunk_viewer", "/data/meta"].iter().chain(extra), None);
+        
+        let err = parse(&["--read-chunk", "1234", "--gzip"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+        assert!(parse(&["--read-chunk", "1234", "--gzip", "--output-file", "/tmp/chunk.hex.gz"]).unwrap().gzip);
+        assert!(parse(&["--verify", "--gzip", "--corrupt-manifest", "/tmp/corrupt.jsonl.gz"]).unwrap().gzip);
+        
+        assert_eq!(parse(&["--read-chunk", "1234", "--mkdir-parents"]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+        assert!(parse(&["--read-chunk", "1234", "--mkdir-parents", "--output-file", "/tmp/dumps/chunk.hex"]).unwrap().mkdir_parents);
+    }
+
+    #[test]
+    fn test_first_and_last_n() {
+        let parse = |extra: &[&str]| Args::try_parse_from_sources(["chunk_viewer", "/data/meta"].iter().chain(extra), None);
+        
+        assert_eq!(parse(&["--list-size", "1MB", "--first-n", "10"]).unwrap().first_n, Some(10));
+        assert_eq!(parse(&["--list-size", "1MB", "--last-n", "10"]).unwrap().last_n, Some(10));
+        for extra in [&["--first-n", "10", "--last-n", "10"][..], &["--first-n", "10", "--page", "2"], &["--last-n", "10", "--interactive"]] {
+            let err = parse(&[&["--list-size", "1MB"][..], extra].concat()).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ArgumentConflict, "{:?}", extra);
+        }
+        assert_eq!(parse(&["--first-n", "10"]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+        assert_eq!(parse(&["--list-size", "1MB", "--last-n", "0"]).unwrap_err().kind(), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn test_quiet() {
+        let parse = |extra: &[&str]| Args::try_parse_from_sources(["chunk_viewer", "/data/meta"].iter().chain(extra), None);
+        
+        assert!(parse(&["--lookup-chunks", "ids.txt", "-q"]).unwrap().quiet);
+        assert!(!parse(&["--lookup-chunks", "ids.txt"]).unwrap().quiet);
+        assert_eq!(parse(&["--read-chunk", "1234", "--quiet", "--verbose"]).unwrap_err().kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn test_chunk_meta_prefix() {
+        let parse = |extra: &[&str]| Args::try_parse_from_sources(["chunk_viewer", "/data/meta"].iter().chain(extra), None);
+        
+        assert_eq!(parse(&[]).unwrap().chunk_meta_prefix, MetaKey::CHUNK_META_KEY_PREFIX);
+        assert_eq!(parse(&["--chunk-meta-prefix", "10"]).unwrap().chunk_meta_prefix, 10);
+        assert_eq!(parse(&["--chunk-meta-prefix", "0xff"]).unwrap().chunk_meta_prefix, 0xff);
+        for value in ["256", "0x100", "x"] {
+            let err = parse(&["--chunk-meta-prefix", value]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ValueValidation, "{}", value);
+            assert!(err.to_string().contains("is not a single byte"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_delimited_output_format() {
+        let args = Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--list-size", "1MB", "--output-format", "tsv"], None).unwrap();
+        assert_eq!(args.output_format.delimiter(), Some('\t'));
+        let args = Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--list-all", "--output-format", "csv"], None).unwrap();
+        assert_eq!(args.output_format.delimiter(), Some(','));
+
+        // Only the listings have rows to delimit
+        let error = Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--output-format", "tsv"], None).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn test_interval() {
+        let parse = |extra: &[&str]| Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--watch"].iter().chain(extra), None);
+
+        assert_eq!(parse(&[]).unwrap().interval, 5);
+        assert_eq!(parse(&["--interval", "1"]).unwrap().interval, 1);
+        // A zero interval would poll the store in a busy loop
+        assert_eq!(parse(&["--interval", "0"]).unwrap_err().kind(), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn test_max_diffs() {
+        let parse = |extra: &[&str]| Args::try_parse_from_sources(["chunk_viewer", "/data/meta"].iter().chain(extra), None);
+
+        assert_eq!(parse(&["--diff-chunks", "aa", "bb"]).unwrap().max_diffs, 10);
+        assert_eq!(parse(&["--diff-chunks", "aa", "bb", "--max-diffs", "3"]).unwrap().max_diffs, 3);
+        assert_eq!(parse(&["--diff-chunks", "aa", "bb", "--max-diffs", "0"]).unwrap_err().kind(), ErrorKind::ValueValidation);
+        assert_eq!(parse(&["--max-diffs", "3"]).unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+    }
+    #[test]
+    fn test_mmap_mode() {
+        let parse = |extra: &[&str]| Args::try_parse_from_sources(["chunk_viewer", "/data/meta", "--read-chunk", "aa", "--mmap"].iter().chain(extra), None);
+
+        assert!(parse(&[]).unwrap().mmap);
+        // A live primary may rewrite a chunk while it is mapped
+        assert_eq!(parse(&["--mode", "secondary", "--secondary-path", "/tmp/secondary"]).unwrap_err().kind(), ErrorKind::ArgumentConflict);
+    }
+}
@@ -1,248 +1,2045 @@
 use chunk_engine::*;
 use derse::Deserialize;
+use serde::Serialize;
 use std::{
+    cmp::Ordering,
     collections::{BTreeMap, HashMap},
+    io::Write,
+    ops::Range,
     sync::Arc,
 };
 
-use super::utils::format_size;
+use super::args::{OutputFormat, TableStyle};
+use super::exit_status::ExitStatus;
+use super::percentiles::{LengthCollector, RunningStats};
+use super::table::{render_box_table_with, Align, Column};
+use super::terminal::{clear_screen, paint, read_key, Color};
+use super::utils::{bucket_size, format_checksum, format_hex_chunk_id, format_size, format_size_with, to_json, to_json_line, IdRange, SizeFormat};
 
 pub struct ChunkLister {
     meta_store: MetaStore,
+    /// Highlight utilization, uncommitted chunks and warnings with ANSI colors in table output
+    color: bool,
+    /// Decimal places of utilization percentages and sizes in the detailed listings
+    precision: usize,
+    /// Chunk IDs the summary and listings are bounded to
+    id_range: IdRange,
+    /// Buckets with fewer used chunks are left out of the summary tables
+    min_chunks: u64,
+    /// Follow the utilization in the detailed listings with a bar, see `util_bar`
+    util_bars: bool,
+    /// Print only the numbers of `--bucket-stats`, see `--quiet`
+    quiet: bool,
+}
+
+/// Decimal places of utilization and sizes unless --precision says otherwise
+pub const DEFAULT_PRECISION: usize = 2;
+
+/// Utilization below this percentage is shown in red with --color
+const LOW_UTILIZATION: f64 = 50.0;
+/// Utilization at or above this percentage is shown in green with --color
+const HIGH_UTILIZATION: f64 = 90.0;
+
+/// Cells of a `--heatmap` row, each one an equal share of the bucket's chunks
+const HEATMAP_CELLS: usize = 20;
+/// Heatmap shades for utilization below 25%, 50%, 75% and up to 100%, with `--ascii` stand-ins
+const HEATMAP_BLOCKS: [char; 4] = ['░', '▒', '▓', '█'];
+const HEATMAP_ASCII: [char; 4] = ['.', ':', '#', '@'];
+
+/// Characters between the brackets of a `--util-bars` bar, each one a tenth of the slot
+const UTIL_BAR_WIDTH: usize = 10;
+
+/// Allocation statistics of one size bucket
+#[derive(Debug, Serialize)]
+pub struct BucketSummary {
+    pub chunk_size: u64,
+    pub used_chunks: u64,
+    pub reserved_chunks: u64,
+    pub full_groups: usize,
+    pub active_groups: usize,
+    /// Free slots over total slots across active groups, in [0, 1]
+    pub fragmentation: f64,
+    /// Chunks with no data that still hold a slot
+    pub empty_chunks: u64,
+    /// Chunks filling their whole slot, the next append moves them to a larger bucket
+    pub full_chunks: u64,
+    /// Mean of the chunks' utilization of their slots, in percent
+    pub avg_utilization: f64,
+    /// Standard deviation of the chunks' utilization in percent, high when packing is inconsistent
+    pub utilization_stddev: f64,
+    pub committed_chunks: u64,
+    /// Chunks whose last write was never committed, many of them point to interrupted writes needing recovery
+    pub uncommitted_chunks: u64,
+    /// Slots taken in the group bits that no chunk meta holds, see `--release-reservations`
+    pub stale_positions: u64,
+    /// Chunk metas beyond the slots taken for them, e.g. two chunks sharing one slot
+    pub excess_chunks: u64,
+    /// Chunks holding data with a stored checksum of 0, usually never computed, see `--zero-checksum`
+    pub zero_checksum_chunks: u64,
+}
+
+/// The allocation statistics of every size bucket added up
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct SummaryTotal {
+    pub used_chunks: u64,
+    pub reserved_chunks: u64,
+    pub full_groups: usize,
+    pub active_groups: usize,
+    /// Bytes of the slots the used chunks hold, each chunk counted at the size of its bucket
+    pub used_bytes: u64,
+}
+
+impl SummaryTotal {
+    fn add(&mut self, bucket: &BucketSummary) {
+        self.used_chunks += bucket.used_chunks;
+        self.reserved_chunks += bucket.reserved_chunks;
+        self.full_groups += bucket.full_groups;
+        self.active_groups += bucket.active_groups;
+        self.used_bytes += bucket.used_chunks * bucket.chunk_size;
+    }
+}
+
+/// Allocation statistics of the whole store, ordered by chunk size
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub buckets: Vec<BucketSummary>,
+    /// All buckets together, including those `--min-chunks` hides
+    pub total: SummaryTotal,
+    /// Chunks per checksum algorithm, more than one means the store is migrating between checksum schemes
+    pub checksum_algorithms: BTreeMap<&'static str, u64>,
+    /// No chunk metadata was found, usually a wrong path rather than a store that was never written
+    pub empty: bool,
+    /// Every bucket has as many slots taken as chunk metas. Only checked when every chunk was scanned
+    pub consistent: bool,
+}
+
+impl Summary {
+    /// Stale positions and excess chunk metas are a mismatch between the group bits and the metadata
+    pub fn exit_status(&self) -> ExitStatus {
+        if self.consistent { ExitStatus::Ok } else { ExitStatus::Mismatch }
+    }
+}
+
+/// Counted for chunks whose metadata does not say which algorithm produced their checksum
+const UNKNOWN_CHECKSUM_ALGORITHM: &str = "unknown/uniform";
+
+/// The algorithm that produced the checksum of `chunk_meta`. Chunk metas do not record it yet, so every
+/// chunk counts as unknown until they do; `--verify` detects the algorithm from the data instead
+fn checksum_algorithm(_chunk_meta: &ChunkMeta) -> &'static str {
+    UNKNOWN_CHECKSUM_ALGORITHM
+}
+
+/// Shown when the summary finds no chunks at all
+const EMPTY_STORE_WARNING: &str = "WARNING: store contains no chunks — wrong path or empty store?";
+
+/// Occupancy of one allocator group
+#[derive(Debug, Serialize)]
+struct GroupInfo {
+    index: u32,
+    group: u32,
+    cluster: u8,
+    used: u32,
+    free: u32,
+    full: bool,
+}
+
+/// An allocator group with the bitmap of its taken slots, slot i is bit i % 8 of byte i / 8
+#[derive(Debug, Serialize)]
+struct GroupBitmap {
+    index: u32,
+    used: u32,
+    bitmap: String,
+}
+
+/// The full `ChunkAllocator` state of one size bucket as `--dump-allocator` writes it, groups are given by index
+#[derive(Debug, Serialize)]
+struct AllocatorDump {
+    chunk_size: u64,
+    allocated_chunks: u64,
+    reserved_chunks: u64,
+    next_group: u32,
+    full_groups: Vec<u32>,
+    active_groups: Vec<GroupBitmap>,
+    frozen_groups: Vec<GroupBitmap>,
+    empty_groups: Vec<u32>,
+    unallocated_groups: Vec<u32>,
+}
+
+/// Totals of one size bucket, see `MetaStore::bucket_stats`
+#[derive(Debug, Serialize)]
+struct BucketTotals {
+    chunk_size: u64,
+    count: u64,
+    total_len: u64,
+    total_allocated: u64,
+}
+
+/// Reserved against allocated slots of one size bucket, see `--reservation-audit`
+#[derive(Debug, Serialize)]
+struct BucketReservations {
+    chunk_size: u64,
+    allocated_chunks: u64,
+    reserved_chunks: u64,
+    used_chunks: u64,
+    stale_positions: u64,
+    /// Reserved over allocated slots, in percent
+    reserved_percent: f64,
+    over_threshold: bool,
+}
+
+/// How tightly the groups of one size bucket are packed, see `--allocator-efficiency`
+#[derive(Debug, PartialEq, Serialize)]
+struct BucketPacking {
+    chunk_size: u64,
+    full_groups: usize,
+    active_groups: usize,
+    /// Chunk slots of all full and active groups
+    capacity: u64,
+    /// Slots taken by chunks
+    occupied: u64,
+    /// Occupied over capacity slots, in percent
+    efficiency: f64,
+}
+
+/// Packing of every size bucket with groups, and of all of them weighted by the bytes of their slots
+#[derive(Debug, PartialEq, Serialize)]
+struct AllocatorEfficiency {
+    buckets: Vec<BucketPacking>,
+    capacity_bytes: u64,
+    occupied_bytes: u64,
+    /// Occupied over capacity bytes, in percent
+    efficiency: f64,
+}
+
+/// One chunk meta key as RocksDB stores it, see `--raw-keys`
+#[derive(Debug, PartialEq, Serialize)]
+struct RawKey {
+    key: String,
+    /// The chunk ID `MetaKey::parse_chunk_meta_key` decodes from the key
+    chunk_id: String,
+    value_len: usize,
+}
+
+/// How a chunk meta key breaks the strict ordering of the scan, see `--check-key-order`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum KeyOrderViolationKind {
+    /// The key equals the previous one, so two keys decode to the same chunk ID
+    Duplicate,
+    /// The key sorts before the previous one
+    OutOfOrder,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct KeyOrderViolation {
+    kind: KeyOrderViolationKind,
+    previous_key: String,
+    key: String,
+}
+
+/// Outcome of `--check-key-order`, every violation found in one pass
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct KeyOrderReport {
+    checked_keys: u64,
+    violations: Vec<KeyOrderViolation>,
+}
+
+impl KeyOrderReport {
+    pub fn exit_status(&self) -> ExitStatus {
+        if self.violations.is_empty() { ExitStatus::Ok } else { ExitStatus::Corruption }
+    }
+}
+
+/// Check that every key is strictly greater than the one before it. The chunk ID is the key inverted
+/// byte by byte, so two keys decode to the same chunk ID only if they are equal; while the keys are
+/// ordered such keys are adjacent, and once they are not the disorder itself is reported.
+fn check_key_order<K: AsRef<[u8]>>(keys: impl IntoIterator<Item = K>) -> KeyOrderReport {
+    let mut report = KeyOrderReport::default();
+    let mut previous: Option<K> = None;
+    for key in keys {
+        report.checked_keys += 1;
+        if let Some(previous) = &previous {
+            let kind = match key.as_ref().cmp(previous.as_ref()) {
+                std::cmp::Ordering::Greater => None,
+                std::cmp::Ordering::Equal => Some(KeyOrderViolationKind::Duplicate),
+                std::cmp::Ordering::Less => Some(KeyOrderViolationKind::OutOfOrder),
+            };
+            if let Some(kind) = kind {
+                report.violations.push(KeyOrderViolation {
+                    kind,
+                    previous_key: format_hex_chunk_id(previous.as_ref()),
+                    key: format_hex_chunk_id(key.as_ref()),
+                });
+            }
+        }
+        previous = Some(key);
+    }
+    report
+}
+
+/// Chunks written under one chain version, see `--chain-utilization`
+#[derive(Debug, PartialEq, Serialize)]
+struct ChainUtilization {
+    chain_ver: u32,
+    chunks: u64,
+    total_len: u64,
+    total_allocated: u64,
+    /// Total length over total allocated size, in percent
+    avg_utilization: f64,
+    /// Chunks using less than `LOW_UTILIZATION` percent of their slot
+    low_utilization_chunks: u64,
+}
+
+/// Chunks whose IDs share one prefix, see `--prefix-distribution`
+#[derive(Debug, PartialEq, Serialize)]
+struct PrefixCount {
+    prefix: String,
+    chunks: u64,
+}
+
+/// How evenly the chunks spread over the distinct ID prefixes the meta store groups keys by, see `--prefix-distribution`
+#[derive(Debug, PartialEq, Serialize)]
+struct PrefixDistribution {
+    prefix_len: usize,
+    chunks: u64,
+    /// Distinct prefixes holding at least one chunk
+    prefixes: u64,
+    min: u64,
+    max: u64,
+    mean: f64,
+    stddev: f64,
+    /// The prefixes holding the most chunks, most first
+    hottest: Vec<PrefixCount>,
+}
+
+/// Utilization percentiles of one size bucket, see `--heatmap`
+#[derive(Debug, PartialEq, Serialize)]
+struct HeatmapRow {
+    chunk_size: u64,
+    chunks: u64,
+    /// Total length over total allocated size, in percent
+    avg_utilization: f64,
+    /// Utilization in whole percent at evenly spaced percentiles, least utilized chunks first, empty without chunks
+    cells: Vec<u8>,
+}
+
+/// Chunks of one size bucket that would fit in the next smaller bucket, see `--rebucket-candidates`
+#[derive(Debug, PartialEq, Serialize)]
+struct RebucketCandidates {
+    chunk_size: u64,
+    target_size: u64,
+    chunks: u64,
+    candidates: u64,
+    /// Slot bytes freed by moving every candidate down one bucket
+    reclaimable_bytes: u64,
+}
+
+/// Chunk count and byte totals of a detailed listing, see `--list-all`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+struct ListingTotals {
+    chunks: u64,
+    total_len: u64,
+    total_allocated: u64,
+}
+
+impl ListingTotals {
+    fn add(&mut self, other: ListingTotals) {
+        self.chunks += other.chunks;
+        self.total_len += other.total_len;
+        self.total_allocated += other.total_allocated;
+    }
+}
+
+/// One size bucket of `--list-all`, its totals cover every chunk even when `rows` is limited
+#[derive(Debug, Serialize)]
+struct BucketListing {
+    chunk_size: u64,
+    totals: ListingTotals,
+    rows: Vec<ChunkRow>,
+}
+
+/// Which chunks of a size bucket the detailed listing shows
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ChunkFilter {
+    /// Only chunks in the group with this index, see `--list-groups`
+    pub group: Option<u32>,
+    /// Only chunks with no data
+    pub empty_only: bool,
+    /// Only chunks filling their whole slot
+    pub full_only: bool,
+    /// Only chunks whose last write was never committed
+    pub uncommitted_only: bool,
+}
+
+impl ChunkFilter {
+    fn matches(&self, chunk_meta: &ChunkMeta) -> bool {
+        self.group.is_none_or(|group| chunk_meta.pos.group_index() == group)
+            && (!self.empty_only || chunk_meta.len == 0)
+            && (!self.full_only || chunk_meta.len as u64 == chunk_meta.pos.chunk_size().0)
+            && (!self.uncommitted_only || chunk_meta.uncommitted)
+    }
+}
+
+/// Which chunks of the sorted detailed listing are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListingSlice {
+    /// One page of `page_size` chunks, pages count from 1
+    Page { page_size: usize, page: usize },
+    /// The first N chunks, see `--first-n`
+    First(usize),
+    /// The last N chunks, see `--last-n`
+    Last(usize),
+}
+
+/// All chunks of one size bucket, sorted by chunk ID, with their totals
+struct DetailedListing {
+    target_size: u32,
+    filter: ChunkFilter,
+    chunks_info: Vec<(Bytes, ChunkMeta)>,
+    total_actual_size: u64,
+    total_allocated_size: u64,
+    /// Utilization of the chunks in percent, gathered during the scan
+    utilization: RunningStats,
+}
+
+/// One chunk of the detailed listing, with the table columns as plain values
+#[derive(Debug, Serialize)]
+struct ChunkRow {
+    index: usize,
+    chunk_id: String,
+    alloc_size: u64,
+    actual_len: u32,
+    utilization: f64,
+    chain_ver: u32,
+    chunk_ver: u32,
+    checksum: u32,
+    uncommitted: bool,
+}
+
+/// The detailed listing as `--output-format json` prints it, the chunks of the slice together with the page numbers
+/// `display_pagination_info` shows, so a script can tell whether to fetch another page. `--first-n` and `--last-n`
+/// have no page numbers, those fields are left out
+#[derive(Debug, Serialize)]
+struct ListingPage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_pages: Option<usize>,
+    total_chunks: usize,
+    chunks: Vec<ChunkRow>,
+}
+
+impl ChunkRow {
+    /// Column names of the CSV and TSV output, the same as the JSON field names
+    const DELIMITED_HEADER: [&'static str; 9] =
+        ["index", "chunk_id", "alloc_size", "actual_len", "utilization", "chain_ver", "chunk_ver", "checksum", "uncommitted"];
+
+    /// The table cells of the row, the checksum only with `show_checksum`, sizes and utilization with `precision` decimals
+    fn cells(self, short_ids: bool, show_checksum: bool, precision: usize) -> Vec<String> {
+        let chunk_id_display = if short_ids && self.chunk_id.len() > 16 {
+            format!("{}...", &self.chunk_id[..16])
+        } else {
+            self.chunk_id
+        };
+
+        let mut cells = vec![
+            self.index.to_string(),
+            chunk_id_display,
+            format_size_to(self.alloc_size, precision),
+            format_size_to(self.actual_len as u64, precision),
+            format!("{:.*}", precision, self.utilization),
+            self.chain_ver.to_string(),
+            self.chunk_ver.to_string(),
+        ];
+        if show_checksum {
+            cells.push(format_checksum(self.checksum));
+        }
+        cells.push(if self.uncommitted { "Yes" } else { "No" }.to_string());
+        cells
+    }
+
+    /// The row as plain values joined by `delimiter`, in the order of `DELIMITED_HEADER`. Chunk IDs are hex and the
+    /// other values numbers or booleans, so no value holds a delimiter and none is quoted
+    fn delimited(&self, delimiter: char) -> String {
+        [
+            self.index.to_string(),
+            self.chunk_id.clone(),
+            self.alloc_size.to_string(),
+            self.actual_len.to_string(),
+            format!("{:.2}", self.utilization),
+            self.chain_ver.to_string(),
+            self.chunk_ver.to_string(),
+            format_checksum(self.checksum),
+            self.uncommitted.to_string(),
+        ].join(&delimiter.to_string())
+    }
+
+    /// Highlights of the cells returned by `cells`, for --color
+    fn colors(&self, show_checksum: bool) -> Vec<Option<Color>> {
+        let mut colors = vec![None; if show_checksum { 9 } else { 8 }];
+        colors[4] = if self.utilization < LOW_UTILIZATION {
+            Some(Color::Red)
+        } else if self.utilization >= HIGH_UTILIZATION {
+            Some(Color::Green)
+        } else {
+            None
+        };
+        if self.uncommitted {
+            *colors.last_mut().unwrap() = Some(Color::Yellow);
+        }
+        colors
+    }
+}
+
+/// The heatmap table, one row per size bucket followed by the legend
+fn render_heatmap(rows: &[HeatmapRow], ascii: bool) -> String {
+    let shades = if ascii { HEATMAP_ASCII } else { HEATMAP_BLOCKS };
+    let mut out = String::new();
+    out += "=== Utilization Heatmap ===\n";
+    out += &format!("{:>12} {:>10} {:>9}  {}\n", "Bucket", "Chunks", "Avg Util", "Least to most utilized chunks");
+    for row in rows {
+        let cells: String = if row.cells.is_empty() {
+            " ".repeat(HEATMAP_CELLS)
+        } else {
+            row.cells.iter().map(|&utilization| shades[(utilization as usize / 25).min(shades.len() - 1)]).collect()
+        };
+        let avg_utilization = if row.chunks == 0 { "-".to_string() } else { format!("{:.2}%", row.avg_utilization) };
+        out += &format!("{:>12} {:>10} {:>9}  |{}|\n", format_size(row.chunk_size), row.chunks, avg_utilization, cells);
+    }
+    out += &format!("\nLegend: {} <25%  {} <50%  {} <75%  {} >=75%\n", shades[0], shades[1], shades[2], shades[3]);
+    out
+}
+
+/// `format_size` with `precision` decimals
+fn format_size_to(bytes: u64, precision: usize) -> String {
+    format_size_with(bytes, SizeFormat { precision: Some(precision), ..Default::default() })
+}
+
+/// Header of the detailed listing: the bucket, the filters applied and the totals of the chunks they let through,
+/// with the standard deviation of their utilization in percent
+fn render_listing_totals(target_size: u32, filter: ChunkFilter, totals: ListingTotals, utilization_stddev: f64, precision: usize) -> String {
+    let mut out = String::new();
+    out += "=== Detailed Chunk Information ===\n";
+    out += &format!("Size bucket: {} ({})\n", format_size_to(target_size as u64, precision), target_size);
+    if let Some(group) = filter.group {
+        out += &format!("Group: {}\n", group);
+    }
+    if filter.empty_only {
+        out += "Only zero-length chunks\n";
+    }
+    if filter.full_only {
+        out += "Only chunks at capacity\n";
+    }
+    if filter.uncommitted_only {
+        out += "Only uncommitted chunks\n";
+    }
+    out += &format!("Total chunks: {}\n", totals.chunks);
+    out += &format!("Total actual size: {} ({})\n", format_size_to(totals.total_len, precision), totals.total_len);
+    out += &format!("Total allocated size: {} ({})\n", format_size_to(totals.total_allocated, precision), totals.total_allocated);
+    if totals.total_allocated > 0 {
+        out += &format!("Average utilization: {:.*}%\n", precision, (totals.total_len as f64 / totals.total_allocated as f64) * 100.0);
+        out += &format!("Utilization stddev: {:.*}%\n", precision, utilization_stddev);
+    }
+    out
+}
+
+/// Print a header and one line per row, for `--output-format csv` or `tsv`
+fn print_delimited(rows: &[ChunkRow], output_format: OutputFormat) {
+    let delimiter = output_format.delimiter().expect("a delimited output format");
+    println!("{}", ChunkRow::DELIMITED_HEADER.join(&delimiter.to_string()));
+    for row in rows {
+        println!("{}", row.delimited(delimiter));
+    }
+}
+
+impl DetailedListing {
+    fn total_pages(&self, page_size: usize) -> usize {
+        self.chunks_info.len().div_ceil(page_size)
+    }
+
+    /// Index range of the chunks on one page, an error for a page past the end rather than an empty page
+    fn page_range(&self, page_size: usize, page: usize) -> Result<Range<usize>> {
+        if page_size == 0 {
+            return Err(Error::InvalidArg("--page-size must be at least 1".into()));
+        }
+        let start_idx = page.checked_sub(1)
+            .ok_or_else(|| Error::InvalidArg("--page starts at 1".into()))?
+            .checked_mul(page_size)
+            .ok_or_else(|| Error::InvalidArg(format!("--page {} with --page-size {} is out of range", page, page_size)))?;
+
+        // Page 1 of an empty listing is still valid, it just shows no chunks
+        if start_idx >= self.chunks_info.len() && page > 1 {
+            return Err(Error::InvalidArg(format!(
+                "Page {} is past the end, the listing has {} pages of {} chunks",
+                page, self.total_pages(page_size), page_size)));
+        }
+        Ok(start_idx..start_idx.saturating_add(page_size).min(self.chunks_info.len()))
+    }
+
+    fn totals(&self) -> ListingTotals {
+        ListingTotals {
+            chunks: self.chunks_info.len() as u64,
+            total_len: self.total_actual_size,
+            total_allocated: self.total_allocated_size,
+        }
+    }
+
+    /// Index range of the chunks of `slice`. Only a page can be out of range, `--first-n` and `--last-n`
+    /// show the whole listing when it has fewer chunks
+    fn slice_range(&self, slice: ListingSlice) -> Result<Range<usize>> {
+        let len = self.chunks_info.len();
+        match slice {
+            ListingSlice::Page { page_size, page } => self.page_range(page_size, page),
+            ListingSlice::First(n) => Ok(0..n.min(len)),
+            ListingSlice::Last(n) => Ok(len.saturating_sub(n)..len),
+        }
+    }
+
+    /// The line above the table naming the chunks shown, rather than page numbers for `--first-n` and `--last-n`
+    fn slice_caption(&self, slice: ListingSlice, shown: usize) -> String {
+        match slice {
+            ListingSlice::Page { page_size, page } => format!("Page {}/{} (showing {} chunks)", page, self.total_pages(page_size), shown),
+            ListingSlice::First(_) => format!("First {} of {} chunks", shown, self.chunks_info.len()),
+            ListingSlice::Last(_) => format!("Last {} of {} chunks", shown, self.chunks_info.len()),
+        }
+    }
+
+    /// Rows of the chunks on one page, numbered from the start of the listing
+    fn page_rows(&self, page_size: usize, page: usize) -> Result<Vec<ChunkRow>> {
+        self.slice_rows(ListingSlice::Page { page_size, page })
+    }
+
+    /// Rows of the chunks of `slice`, numbered from the start of the listing
+    fn slice_rows(&self, slice: ListingSlice) -> Result<Vec<ChunkRow>> {
+        let range = self.slice_range(slice)?;
+        let rows = self.chunks_info[range.clone()].iter().zip(range)
+            .map(|((chunk_id, chunk_meta), i)| ChunkRow {
+                index: i + 1,
+                chunk_id: format_hex_chunk_id(chunk_id),
+                alloc_size: self.target_size as u64,
+                actual_len: chunk_meta.len,
+                utilization: (chunk_meta.len as f64 / self.target_size as f64) * 100.0,
+                chain_ver: chunk_meta.chain_ver,
+                chunk_ver: chunk_meta.chunk_ver,
+                checksum: chunk_meta.checksum,
+                uncommitted: chunk_meta.uncommitted,
+            })
+            .collect();
+        Ok(rows)
+    }
+
+    /// The rows of `slice` wrapped with the page numbers, see `ListingPage`
+    fn json_page(&self, slice: ListingSlice) -> Result<ListingPage> {
+        let (page, total_pages) = match slice {
+            ListingSlice::Page { page_size, page } => (Some(page), Some(self.total_pages(page_size))),
+            ListingSlice::First(_) | ListingSlice::Last(_) => (None, None),
+        };
+        Ok(ListingPage { page, total_pages, total_chunks: self.chunks_info.len(), chunks: self.slice_rows(slice)? })
+    }
 }
 
 impl ChunkLister {
     pub fn new(meta_store: MetaStore) -> Self {
-        Self { meta_store }
+        Self { meta_store, color: false, precision: DEFAULT_PRECISION, id_range: IdRange::default(), min_chunks: 0, util_bars: false, quiet: false }
+    }
+
+    pub fn with_id_range(mut self, id_range: IdRange) -> Self {
+        self.id_range = id_range;
+        self
+    }
+
+    pub fn with_min_chunks(mut self, min_chunks: u64) -> Self {
+        self.min_chunks = min_chunks;
+        self
+    }
+
+    pub fn with_util_bars(mut self, util_bars: bool) -> Self {
+        self.util_bars = util_bars;
+        self
+    }
+
+    pub fn with_precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Print `--bucket-stats` as the bare numbers on one line, without labels
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// First byte of the chunk meta keys of the store, see `--chunk-meta-prefix`
+    fn key_prefix(&self) -> u8 {
+        self.meta_store.chunk_meta_key_prefix()
     }
 
-    pub fn show_summary(&self) -> Result<()> {
+    /// `text` in `color` if --color is on
+    fn highlight(&self, text: &str, color: Color) -> String {
+        if self.color { paint(text, color) } else { text.to_string() }
+    }
+
+    pub fn show_summary(&self, output_format: OutputFormat) -> Result<Summary> {
+        // The summary has no rows to delimit, rejected before the scan rather than printed as a table
+        if output_format.delimiter().is_some() {
+            return Err(Error::InvalidArg("--output-format csv and tsv are only supported by --list-size and --list-all".to_string()));
+        }
+        let summary = self.collect_summary()?;
+
+        match output_format {
+            OutputFormat::Table => self.display_summary(&summary),
+            OutputFormat::Csv | OutputFormat::Tsv => unreachable!("rejected above"),
+            OutputFormat::Json => println!("{}", to_json(&summary)?),
+            OutputFormat::JsonLines => {
+                if summary.empty {
+                    eprintln!("{}", EMPTY_STORE_WARNING);
+                }
+                for bucket in &summary.buckets {
+                    println!("{}", to_json_line(bucket)?);
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Pick up the primary's latest writes when the store is opened as a secondary
+    pub fn catch_up_with_primary(&self) -> Result<()> {
+        self.meta_store.catch_up_with_primary()
+    }
+
+    pub fn collect_summary(&self) -> Result<Summary> {
         let mut chunk_allocators = HashMap::new();
-        let mut used_map = BTreeMap::new();
-        let mut reversed_map = BTreeMap::new();
-        let mut group_count = BTreeMap::new();
-        let mut chunk_size = CHUNK_SIZE_SMALL;
+        let mut buckets = BTreeMap::new();
         let mut real_map = BTreeMap::new();
+        let mut checksum_algorithms = BTreeMap::new();
+        let mut utilization: BTreeMap<Size, RunningStats> = BTreeMap::new();
+        let mut total = SummaryTotal::default();
+        let mut empty = true;
 
-        // Load allocation data for all chunk sizes
-        loop {
-            let counter = Arc::new(AllocatorCounter::new(chunk_size));
-            let it = self.meta_store.iterator();
-            let chunk_allocator = ChunkAllocator::load(it, counter.clone(), chunk_size)?;
+        // Load allocation data for all chunk sizes in one pass over the group bits
+        let counters: Vec<_> = Size::all_buckets().map(|chunk_size| Arc::new(AllocatorCounter::new(chunk_size))).collect();
+        let allocators = ChunkAllocator::load_all(self.meta_store.iterator(), counters.clone())?;
+        for (counter, chunk_allocator) in counters.into_iter().zip(allocators) {
+            let chunk_size = counter.chunk_size;
             let allocated_chunks = counter.allocated_chunks();
             let reserved_chunks = counter.reserved_chunks();
-            used_map.insert(chunk_size, allocated_chunks - reserved_chunks);
-            reversed_map.insert(chunk_size, reserved_chunks);
-            group_count.insert(
-                chunk_size,
-                (
-                    chunk_allocator.full_groups.len(),
-                    chunk_allocator.active_groups.len(),
-                ),
-            );
+            let bucket = BucketSummary {
+                chunk_size: chunk_size.0,
+                used_chunks: allocated_chunks - reserved_chunks,
+                reserved_chunks,
+                full_groups: chunk_allocator.full_groups.len(),
+                active_groups: chunk_allocator.active_groups.len(),
+                fragmentation: chunk_allocator.fragmentation(),
+                empty_chunks: 0,
+                full_chunks: 0,
+                avg_utilization: 0.0,
+                utilization_stddev: 0.0,
+                committed_chunks: 0,
+                uncommitted_chunks: 0,
+                stale_positions: 0,
+                excess_chunks: 0,
+                zero_checksum_chunks: 0,
+            };
+            total.add(&bucket);
+            buckets.insert(chunk_size, bucket);
             real_map.insert(chunk_size, 0u64);
             chunk_allocators.insert(chunk_size, chunk_allocator);
-
-            if chunk_size >= CHUNK_SIZE_ULTRA {
-                break;
-            }
-            chunk_size *= 2;
         }
 
         // Count actual chunks in metadata
         let mut it = self.meta_store.iterator();
-        let end_key = MetaKey::chunk_meta_key_prefix();
-        it.seek(&end_key)?;
-
-        if it.key() == Some(end_key.as_ref()) {
-            it.next(); // [begin, end)
-        }
+        self.id_range.seek(&mut it, self.key_prefix())?;
 
         loop {
             if !it.valid() {
                 break;
             }
 
-            if it.key().unwrap()[0] != MetaKey::CHUNK_META_KEY_PREFIX {
+            if it.key().unwrap()[0] != self.key_prefix() {
+                break;
+            }
+            if self.id_range.is_past(&MetaKey::parse_chunk_meta_key(it.key().unwrap())) {
                 break;
             }
 
             let chunk_meta =
                 ChunkMeta::deserialize(it.value().unwrap()).map_err(Error::SerializationError)?;
+            empty = false;
 
             let chunk_size = chunk_meta.pos.chunk_size();
             let allocator = chunk_allocators.get_mut(&chunk_size).unwrap();
-            allocator.reference(chunk_meta.pos, true);
+            // Not asserted as the first reference, chunk metas sharing a slot are reported as excess chunks below
+            allocator.reference(chunk_meta.pos, false);
             real_map.entry(chunk_size).and_modify(|v| *v += 1);
+            let bucket = buckets.get_mut(&chunk_size).unwrap();
+            if chunk_meta.len == 0 {
+                bucket.empty_chunks += 1;
+            } else if chunk_meta.len as u64 == chunk_size.0 {
+                bucket.full_chunks += 1;
+            }
+            // Empty data checksums to 0, so only chunks with data are suspect
+            if chunk_meta.len > 0 && chunk_meta.checksum == 0 {
+                bucket.zero_checksum_chunks += 1;
+            }
+            utilization.entry(chunk_size).or_default().add(chunk_meta.len as f64 / chunk_size.0 as f64 * 100.0);
+            if chunk_meta.uncommitted {
+                bucket.uncommitted_chunks += 1;
+            } else {
+                bucket.committed_chunks += 1;
+            }
+            *checksum_algorithms.entry(checksum_algorithm(&chunk_meta)).or_insert(0) += 1;
 
             it.next();
         }
         
-        // Display summary
-        self.display_summary(&used_map, &reversed_map, &group_count);
-        assert_eq!(used_map, real_map);
+        for (size, stats) in utilization {
+            let bucket = buckets.get_mut(&size).unwrap();
+            bucket.avg_utilization = stats.mean();
+            bucket.utilization_stddev = stats.stddev();
+        }
 
-        Ok(())
+        // Taken slots beyond the chunk metas were never committed or never released, chunk metas beyond the taken
+        // slots share them. Either way the group bits disagree with the metadata, which only a scan of every chunk
+        // can tell
+        let mut consistent = true;
+        if self.id_range.is_full() {
+            for (size, bucket) in buckets.iter_mut() {
+                let real_chunks = real_map[size];
+                match bucket.used_chunks.cmp(&real_chunks) {
+                    Ordering::Greater => bucket.stale_positions = bucket.used_chunks - real_chunks,
+                    Ordering::Less => bucket.excess_chunks = real_chunks - bucket.used_chunks,
+                    Ordering::Equal => continue,
+                }
+                consistent = false;
+            }
+        }
+
+        Ok(Summary {
+            buckets: buckets.into_values().collect(),
+            total,
+            checksum_algorithms,
+            empty,
+            consistent,
+        })
     }
 
-    pub fn list_chunks_detailed(
-        &self,
-        target_size: u32,
-        page_size: usize,
-        page: usize,
-        short_ids: bool,
-    ) -> Result<()> {
-        let mut it = self.meta_store.iterator();
-        let end_key = MetaKey::chunk_meta_key_prefix();
-        it.seek(&end_key)?;
+    /// Report the stale positions `--release-reservations` would release for one size bucket without mutating the store
+    pub fn show_release_plan(&self, target_size: u32) -> Result<()> {
+        let chunk_size = bucket_size(target_size)?;
+        let counter = Arc::new(AllocatorCounter::new(chunk_size));
+        let chunk_allocator = ChunkAllocator::load(self.meta_store.iterator(), counter, chunk_size)?;
+        let in_use = self.meta_store.chunk_positions(chunk_size)?;
+        let stale: Vec<Position> = chunk_allocator.taken_positions().into_iter().filter(|pos| !in_use.contains(pos)).collect();
 
-        if it.key() == Some(end_key.as_ref()) {
-            it.next(); // [begin, end)
+        println!("=== Reservation Release Plan (dry run) ===");
+        println!("Size bucket: {} ({} bytes)", format_size(chunk_size.0), chunk_size.0);
+        println!("Stale positions to release: {} ({})", stale.len(), format_size(stale.len() as u64 * chunk_size.0));
+        for pos in &stale {
+            println!("  group {} slot {}", pos.group_index(), pos.index());
+        }
+        if !stale.is_empty() {
+            println!("\nRun again with --force instead of --dry-run to release them (the storage service must not be running on this store)");
         }
 
-        let mut chunks_info: Vec<(Bytes, ChunkMeta)> = Vec::new();
-        let mut total_chunks = 0u64;
-        let mut total_actual_size = 0u64;
-        let mut total_allocated_size = 0u64;
+        Ok(())
+    }
 
-        // Collect all chunks for the target size
-        loop {
-            if !it.valid() {
-                break;
-            }
+    /// Report which of the listed chunks `--delete-from-manifest` would delete and which are already gone
+    pub fn show_delete_plan(&self, chunk_ids: &[Vec<u8>]) -> Result<()> {
+        let chunk_metas = self.meta_store.get_chunk_metas(chunk_ids)?;
 
-            if it.key().unwrap()[0] != MetaKey::CHUNK_META_KEY_PREFIX {
-                break;
+        println!("=== Deletion Plan (dry run) ===");
+        let mut deleted = 0;
+        for (chunk_id, chunk_meta) in chunk_ids.iter().zip(&chunk_metas) {
+            match chunk_meta {
+                Some(meta) => {
+                    deleted += 1;
+                    println!("  DELETE  {}  {} in size bucket {}", format_hex_chunk_id(chunk_id), format_size(meta.len as u64), format_size(meta.pos.chunk_size().0));
+                }
+                None => println!("  ABSENT  {}", format_hex_chunk_id(chunk_id)),
             }
+        }
+        println!("Chunks to delete: {}, already absent: {}", deleted, chunk_ids.len() - deleted);
+        if deleted > 0 {
+            println!("\nRun again with --force instead of --dry-run to delete them (the storage service must not be running on this store)");
+        }
 
-            let chunk_meta =
-                ChunkMeta::deserialize(it.value().unwrap()).map_err(Error::SerializationError)?;
+        Ok(())
+    }
 
-            let chunk_size = chunk_meta.pos.chunk_size();
-            
-            if chunk_size == target_size {
-                let raw_key = it.key().unwrap();
-                let chunk_id = MetaKey::parse_chunk_meta_key(raw_key);
-                chunks_info.push((chunk_id, chunk_meta.clone()));
-                total_chunks += 1;
-                total_actual_size += chunk_meta.len as u64;
-                total_allocated_size += u64::from(chunk_size);
-            }
+    /// Report what `--defragment` would do for one size bucket without mutating the store
+    pub fn show_defrag_plan(&self, target_size: u32) -> Result<()> {
+        let chunk_size = bucket_size(target_size)?;
+        let counter = Arc::new(AllocatorCounter::new(chunk_size));
+        let chunk_allocator = ChunkAllocator::load(self.meta_store.iterator(), counter, chunk_size)?;
+        let (_, stats) = chunk_allocator.defrag_plan();
 
-            it.next();
+        println!("=== Defragmentation Plan (dry run) ===");
+        println!("Size bucket: {} ({} bytes)", format_size(chunk_size.0), chunk_size.0);
+        println!("Active groups: {}, fragmentation: {:.2}%", chunk_allocator.active_groups.len(), chunk_allocator.fragmentation() * 100.0);
+        println!("Chunks to move: {}", stats.moved_chunks);
+        println!("Groups to free: {} ({})", stats.freed_groups, format_size(stats.freed_groups * GroupState::TOTAL_BITS as u64 * chunk_size.0));
+
+        Ok(())
+    }
+
+    /// Print the allocator state of one size bucket as JSON, enough to rebuild its group bits in a test
+    pub fn show_allocator_dump(&self, target_size: u32) -> Result<()> {
+        println!("{}", to_json(&self.allocator_dump(target_size)?)?);
+        Ok(())
+    }
+
+    fn allocator_dump(&self, target_size: u32) -> Result<AllocatorDump> {
+        let chunk_size = bucket_size(target_size)?;
+        let counter = Arc::new(AllocatorCounter::new(chunk_size));
+        let state = ChunkAllocator::load(self.meta_store.iterator(), counter, chunk_size)?.state();
+        let indices = |groups: &[GroupId]| groups.iter().map(GroupId::index).collect();
+        let bitmaps = |groups: &[(GroupId, GroupState)]| {
+            groups.iter()
+                .map(|(group_id, group_state)| GroupBitmap { index: group_id.index(), used: group_state.count(), bitmap: group_state.as_bytes().iter().map(|b| format!("{:02x}", b)).collect() })
+                .collect()
+        };
+
+        Ok(AllocatorDump {
+            chunk_size: state.chunk_size.0,
+            allocated_chunks: state.allocated_chunks,
+            reserved_chunks: state.reserved_chunks,
+            next_group: state.next_group_id.index(),
+            full_groups: indices(&state.full_groups),
+            active_groups: bitmaps(&state.active_groups),
+            frozen_groups: bitmaps(&state.frozen_groups),
+            empty_groups: indices(&state.empty_groups),
+            unallocated_groups: indices(&state.unallocated_groups),
+        })
+    }
+
+    /// List every full and active group of one size bucket with its slot usage, sorted by group index
+    pub fn list_groups(&self, target_size: u32, output_format: OutputFormat) -> Result<()> {
+        let chunk_size = bucket_size(target_size)?;
+        let counter = Arc::new(AllocatorCounter::new(chunk_size));
+        let chunk_allocator = ChunkAllocator::load(self.meta_store.iterator(), counter, chunk_size)?;
+        let groups: Vec<GroupInfo> = chunk_allocator.groups()
+            .map(|g| GroupInfo {
+                index: g.group_id.index(),
+                group: g.group_id.group(),
+                cluster: g.group_id.cluster(),
+                used: g.used,
+                free: g.free,
+                full: g.full,
+            })
+            .collect();
+
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {}
+            OutputFormat::Json => {
+                println!("{}", to_json(&groups)?);
+                return Ok(());
+            }
+            OutputFormat::JsonLines => {
+                for group in &groups {
+                    println!("{}", to_json_line(group)?);
+                }
+                return Ok(());
+            }
         }
 
-        if chunks_info.is_empty() {
-            println!("No chunks found for size bucket: {} ({} bytes)", format_size(target_size as u64), target_size);
-            println!("Run without --list-size to see available size buckets");
+        println!("=== Groups of Size Bucket {} ({} bytes) ===", format_size(chunk_size.0), chunk_size.0);
+        if groups.is_empty() {
+            println!("No groups allocated for this size.");
             return Ok(());
         }
+        println!("{:>8} {:>8} {:>7} {:>6} {:>6} {:>8}  State", "Index", "Group", "Cluster", "Used", "Free", "Used %");
+        println!("{}", "-".repeat(55));
+        for group in &groups {
+            let total = group.used + group.free;
+            println!("{:>8} {:>8} {:>7} {:>6} {:>6} {:>7.2}%  {}",
+                group.index, group.group, group.cluster, group.used, group.free,
+                group.used as f64 * 100.0 / total as f64,
+                if group.full { "full" } else { "active" });
+        }
+        let full_groups = groups.iter().filter(|group| group.full).count();
+        println!("\nTotal: {} groups ({} full, {} active)", groups.len(), full_groups, groups.len() - full_groups);
+        println!("Use --list-size {} --group <INDEX> to see the chunks of one group", size_arg(chunk_size));
 
-        // Sort chunks by chunk ID for consistent ordering
-        chunks_info.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(())
+    }
 
-        // Calculate pagination
-        let total_pages = (total_chunks as usize + page_size - 1) / page_size;
-        let start_idx = (page - 1) * page_size;
-        let end_idx = std::cmp::min(start_idx + page_size, chunks_info.len());
+    /// Count and byte totals of one size bucket, without collecting its chunks
+    pub fn show_bucket_stats(&self, target_size: u32, output_format: OutputFormat) -> Result<()> {
+        let chunk_size = bucket_size(target_size)?;
+        let stats = self.meta_store.bucket_stats(chunk_size)?;
+        let totals = BucketTotals {
+            chunk_size: chunk_size.0,
+            count: stats.count,
+            total_len: stats.total_len,
+            total_allocated: stats.total_allocated,
+        };
 
-        self.display_detailed_header(target_size, total_chunks, total_actual_size, total_allocated_size, page, total_pages, end_idx - start_idx);
-        self.display_chunks_table(&chunks_info, start_idx, end_idx, target_size, short_ids);
-        self.display_pagination_info(page, total_pages);
+        match output_format {
+            // Chunks, bytes of data and allocated bytes, for `read count len allocated` in a shell script
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv if self.quiet => {
+                println!("{} {} {}", totals.count, totals.total_len, totals.total_allocated);
+            }
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {
+                println!("=== Bucket Statistics ===");
+                println!("Size bucket: {} ({})", format_size_to(totals.chunk_size, self.precision), totals.chunk_size);
+                println!("Total chunks: {}", totals.count);
+                println!("Total actual size: {} ({})", format_size_to(totals.total_len, self.precision), totals.total_len);
+                println!("Total allocated size: {} ({})", format_size_to(totals.total_allocated, self.precision), totals.total_allocated);
+                if totals.total_allocated > 0 {
+                    println!("Average utilization: {:.*}%", self.precision, (totals.total_len as f64 / totals.total_allocated as f64) * 100.0);
+                }
+            }
+            OutputFormat::Json => println!("{}", to_json(&totals)?),
+            OutputFormat::JsonLines => println!("{}", to_json_line(&totals)?),
+        }
 
         Ok(())
     }
 
-    fn display_summary(
-        &self,
-        used_map: &BTreeMap<Size, u64>,
-        reversed_map: &BTreeMap<Size, u64>,
-        group_count: &BTreeMap<Size, (usize, usize)>,
-    ) {
-        println!("=== Chunk Allocation Summary ===");
-        
-        // Show available size buckets with friendly names
-        println!("\nAvailable size buckets:");
-        for (size, count) in used_map {
-            println!("  {:<10} ({} bytes): {} used chunks", format_size(u64::from(*size)), size, count);
+    /// Print the raw chunk meta keys in key order with the chunk ID decoded from each and the length of its value,
+    /// which is never deserialized, so key layout problems show up independently of the value format
+    pub fn show_raw_keys(&self, limit: Option<usize>, output_format: OutputFormat) -> Result<()> {
+        let keys = self.raw_keys(limit)?;
+
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {}
+            OutputFormat::Json => {
+                println!("{}", to_json(&keys)?);
+                return Ok(());
+            }
+            OutputFormat::JsonLines => {
+                for key in &keys {
+                    println!("{}", to_json_line(key)?);
+                }
+                return Ok(());
+            }
         }
-        
-        println!("\nReserved chunks per size:");
-        for (size, count) in reversed_map {
-            println!("  {:<10} ({} bytes): {} reserved chunks", format_size(u64::from(*size)), size, count);
+
+        println!("=== Raw Chunk Meta Keys ===");
+        for key in &keys {
+            println!("{}  chunk {}  value {} bytes", key.key, key.chunk_id, key.value_len);
+        }
+        println!("\n{} keys{}", keys.len(), if limit == Some(keys.len()) { " (limit reached)" } else { "" });
+
+        Ok(())
+    }
+
+    fn raw_keys(&self, limit: Option<usize>) -> Result<Vec<RawKey>> {
+        let mut it = self.meta_store.iterator();
+        let end_key = MetaKey::chunk_meta_key_with(self.key_prefix(), &[]);
+        it.seek(&end_key)?;
+        if it.key() == Some(end_key.as_ref()) {
+            it.next(); // [begin, end)
+        }
+
+        let mut keys = Vec::new();
+        while keys.len() < limit.unwrap_or(usize::MAX) && it.valid() {
+            let key = it.key().unwrap();
+            if key[0] != self.key_prefix() {
+                break;
+            }
+            keys.push(RawKey {
+                key: format_hex_chunk_id(key),
+                chunk_id: format_hex_chunk_id(&MetaKey::parse_chunk_meta_key(key)),
+                value_len: it.value().unwrap().len(),
+            });
+            it.next();
+        }
+        Ok(keys)
+    }
+
+    /// Scan the chunk meta keys and report every key that is not strictly greater than the previous one. The
+    /// scan never stops at a violation, they are all reported at the end.
+    pub fn check_key_order(&self, output_format: OutputFormat) -> Result<KeyOrderReport> {
+        let mut it = self.meta_store.iterator();
+        let end_key = MetaKey::chunk_meta_key_with(self.key_prefix(), &[]);
+        it.seek(&end_key)?;
+        if it.key() == Some(end_key.as_ref()) {
+            it.next(); // [begin, end)
+        }
+        let keys = std::iter::from_fn(|| {
+            let key = it.key().filter(|key| key[0] == self.key_prefix())?.to_vec();
+            it.next();
+            Some(key)
+        });
+        let report = check_key_order(keys);
+
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {
+                println!("=== Key Order Check ===");
+                for violation in &report.violations {
+                    let kind = match violation.kind {
+                        KeyOrderViolationKind::Duplicate => "DUPLICATE   ",
+                        KeyOrderViolationKind::OutOfOrder => "OUT OF ORDER",
+                    };
+                    println!("{} previous {} key {}", kind, violation.previous_key, violation.key);
+                }
+                println!("Checked keys: {}", report.checked_keys);
+                println!("Violations: {}", report.violations.len());
+            }
+            OutputFormat::Json => println!("{}", to_json(&report)?),
+            OutputFormat::JsonLines => {
+                for violation in &report.violations {
+                    println!("{}", to_json_line(violation)?);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// A row of shaded cells per size bucket, its chunks sorted from least to most utilized left to right,
+    /// so the buckets wasting the most space stand out at a glance
+    pub fn show_heatmap(&self, ascii: bool, output_format: OutputFormat) -> Result<()> {
+        let rows = self.heatmap()?;
+
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => print!("{}", render_heatmap(&rows, ascii)),
+            OutputFormat::Json => println!("{}", to_json(&rows)?),
+            OutputFormat::JsonLines => {
+                for row in &rows {
+                    println!("{}", to_json_line(row)?);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// One pass over the chunk metas, keeping a histogram of whole percent utilization per bucket
+    fn heatmap(&self) -> Result<Vec<HeatmapRow>> {
+        let mut buckets: BTreeMap<u64, ([u64; 101], u64)> = Size::all_buckets().map(|size| (size.0, ([0; 101], 0))).collect();
+
+        let mut it = self.meta_store.iterator();
+        let end_key = MetaKey::chunk_meta_key_with(self.key_prefix(), &[]);
+        it.seek(&end_key)?;
+        if it.key() == Some(end_key.as_ref()) {
+            it.next(); // [begin, end)
+        }
+        while let Some(value) = it.key().filter(|key| key[0] == self.key_prefix()).and(it.value()) {
+            let chunk_meta = ChunkMeta::deserialize(value).map_err(Error::SerializationError)?;
+            let chunk_size = chunk_meta.pos.chunk_size().0;
+            let (histogram, total_len) = buckets.entry(chunk_size).or_insert(([0; 101], 0));
+            // Corrupt lengths beyond the slot count as full
+            histogram[(chunk_meta.len as u64 * 100 / chunk_size).min(100) as usize] += 1;
+            *total_len += chunk_meta.len as u64;
+            it.next();
+        }
+
+        Ok(buckets.into_iter().map(|(chunk_size, (histogram, total_len))| {
+            let chunks: u64 = histogram.iter().sum();
+            let cells = if chunks == 0 {
+                vec![]
+            } else {
+                (0..HEATMAP_CELLS).map(|cell| {
+                    // The chunk in the middle of the cell's share, by nearest rank
+                    let rank = ((2 * cell + 1) as u64 * chunks).div_ceil(2 * HEATMAP_CELLS as u64).max(1);
+                    let mut seen = 0;
+                    histogram.iter().position(|&count| {
+                        seen += count;
+                        seen >= rank
+                    }).unwrap() as u8
+                }).collect()
+            };
+            let avg_utilization = if chunks == 0 { 0.0 } else { total_len as f64 * 100.0 / (chunks * chunk_size) as f64 };
+            HeatmapRow { chunk_size, chunks, avg_utilization, cells }
+        }).collect())
+    }
+
+    /// Chunk counts per distinct `prefix_len`-byte chunk ID prefix with their spread and the `top` hottest
+    /// prefixes. Chunk IDs are expected to be hashes, so a few prefixes holding most chunks point at an ID problem.
+    pub fn show_prefix_distribution(&self, prefix_len: usize, top: usize, output_format: OutputFormat) -> Result<()> {
+        let distribution = self.prefix_distribution(prefix_len, top)?;
+
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {}
+            OutputFormat::Json => {
+                println!("{}", to_json(&distribution)?);
+                return Ok(());
+            }
+            OutputFormat::JsonLines => {
+                for prefix in &distribution.hottest {
+                    println!("{}", to_json_line(prefix)?);
+                }
+                return Ok(());
+            }
+        }
+
+        println!("=== Chunk Prefix Distribution ===");
+        println!("Prefix length: {} bytes", distribution.prefix_len);
+        println!("Chunks: {}", distribution.chunks);
+        println!("Distinct prefixes: {}", distribution.prefixes);
+        if distribution.prefixes == 0 {
+            return Ok(());
+        }
+        println!("Chunks per prefix: min {}, max {}, mean {:.2}, stddev {:.2}", distribution.min, distribution.max, distribution.mean, distribution.stddev);
+
+        println!("\nHottest prefixes:");
+        println!("{:<20} {:>10} {:>8}", "Prefix", "Chunks", "Share");
+        println!("{}", "-".repeat(40));
+        for prefix in &distribution.hottest {
+            println!("{:<20} {:>10} {:>7.2}%", prefix.prefix, prefix.chunks, prefix.chunks as f64 * 100.0 / distribution.chunks as f64);
+        }
+
+        Ok(())
+    }
+
+    /// One pass over the chunk meta keys counting the chunks of every ID prefix, the values are never read
+    fn prefix_distribution(&self, prefix_len: usize, top: usize) -> Result<PrefixDistribution> {
+        let mut counts: HashMap<Vec<u8>, u64> = HashMap::new();
+
+        let mut it = self.meta_store.iterator();
+        let end_key = MetaKey::chunk_meta_key_with(self.key_prefix(), &[]);
+        it.seek(&end_key)?;
+        if it.key() == Some(end_key.as_ref()) {
+            it.next(); // [begin, end)
+        }
+        while let Some(key) = it.key().filter(|key| key[0] == self.key_prefix()) {
+            let chunk_id = MetaKey::parse_chunk_meta_key(key);
+            *counts.entry(chunk_id[..prefix_len.min(chunk_id.len())].to_vec()).or_default() += 1;
+            it.next();
+        }
+
+        let chunks: u64 = counts.values().sum();
+        let prefixes = counts.len() as u64;
+        let mean = if prefixes == 0 { 0.0 } else { chunks as f64 / prefixes as f64 };
+        let variance = if prefixes == 0 { 0.0 } else { counts.values().map(|&count| (count as f64 - mean).powi(2)).sum::<f64>() / prefixes as f64 };
+
+        let mut hottest: Vec<(Vec<u8>, u64)> = counts.into_iter().collect();
+        hottest.sort_by(|(prefix_a, count_a), (prefix_b, count_b)| count_b.cmp(count_a).then_with(|| prefix_a.cmp(prefix_b)));
+        Ok(PrefixDistribution {
+            prefix_len,
+            chunks,
+            prefixes,
+            min: hottest.last().map_or(0, |(_, count)| *count),
+            max: hottest.first().map_or(0, |(_, count)| *count),
+            mean,
+            stddev: variance.sqrt(),
+            hottest: hottest.into_iter().take(top).map(|(prefix, chunks)| PrefixCount { prefix: format_hex_chunk_id(&prefix), chunks }).collect(),
+        })
+    }
+
+    /// Allocated, reserved and used slots of every size bucket that has groups, flagging the buckets whose
+    /// reserved share is above `threshold` percent
+    pub fn show_reservation_audit(&self, threshold: f64, output_format: OutputFormat) -> Result<()> {
+        let audit = self.reservation_audit(threshold)?;
+
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {}
+            OutputFormat::Json => {
+                println!("{}", to_json(&audit)?);
+                return Ok(());
+            }
+            OutputFormat::JsonLines => {
+                for bucket in &audit {
+                    println!("{}", to_json_line(bucket)?);
+                }
+                return Ok(());
+            }
+        }
+
+        println!("=== Reservation Audit (threshold {:.2}%) ===", threshold);
+        if audit.is_empty() {
+            println!("No groups allocated in any size bucket.");
+            return Ok(());
+        }
+        println!("{:<12} {:>12} {:>12} {:>12} {:>10}", "Size", "Allocated", "Reserved", "Used", "Reserved %");
+        println!("{}", "-".repeat(62));
+        for bucket in &audit {
+            println!("{:<12} {:>12} {:>12} {:>12} {:>9.2}%{}",
+                format_size(bucket.chunk_size), bucket.allocated_chunks, bucket.reserved_chunks, bucket.used_chunks,
+                bucket.reserved_percent, if bucket.over_threshold { "  !" } else { "" });
+        }
+
+        let flagged: Vec<_> = audit.iter().filter(|bucket| bucket.over_threshold).collect();
+        if flagged.is_empty() {
+            println!("\nNo bucket has more than {:.2}% of its slots reserved.", threshold);
+            return Ok(());
+        }
+        println!("\n{} bucket(s) above the threshold:", flagged.len());
+        for bucket in flagged {
+            let size = size_arg(Size::new(bucket.chunk_size));
+            println!("  {}: {} of {} allocated slots are reserved but hold no chunk ({:.2}%)",
+                size, bucket.reserved_chunks, bucket.allocated_chunks, bucket.reserved_percent);
+            println!("    If nothing is writing to this bucket, the reservations were likely leaked by writes that failed before committing.");
+            println!("    Run --list-groups {} to find mostly empty groups, and --defragment {} --dry-run to see how many groups compacting would free.", size, size);
+            if bucket.stale_positions > 0 {
+                println!("    {} slots are taken without a chunk meta, run --release-reservations {} --dry-run to list them.", bucket.stale_positions, size);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn reservation_audit(&self, threshold: f64) -> Result<Vec<BucketReservations>> {
+        if !(0.0..=100.0).contains(&threshold) {
+            return Err(Error::InvalidArg(format!("--reserved-threshold must be between 0 and 100, got {}", threshold)));
+        }
+
+        // The summary already loads every allocator, reserved slots come from its AllocatorCounter
+        let summary = self.collect_summary()?;
+        let audit = summary.buckets.iter()
+            .map(|bucket| (bucket, bucket.used_chunks + bucket.reserved_chunks))
+            .filter(|&(_, allocated_chunks)| allocated_chunks > 0)
+            .map(|(bucket, allocated_chunks)| {
+                let reserved_percent = bucket.reserved_chunks as f64 * 100.0 / allocated_chunks as f64;
+                BucketReservations {
+                    chunk_size: bucket.chunk_size,
+                    allocated_chunks,
+                    reserved_chunks: bucket.reserved_chunks,
+                    used_chunks: bucket.used_chunks,
+                    stale_positions: bucket.stale_positions,
+                    reserved_percent,
+                    over_threshold: reserved_percent > threshold,
+                }
+            })
+            .collect();
+        Ok(audit)
+    }
+
+    /// Theoretical slot capacity against occupancy of the full and active groups of every size bucket, showing
+    /// whether the allocator packs groups tightly or leaves them half empty
+    pub fn show_allocator_efficiency(&self, output_format: OutputFormat) -> Result<()> {
+        let efficiency = self.allocator_efficiency()?;
+
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {}
+            OutputFormat::Json => {
+                println!("{}", to_json(&efficiency)?);
+                return Ok(());
+            }
+            OutputFormat::JsonLines => {
+                for bucket in &efficiency.buckets {
+                    println!("{}", to_json_line(bucket)?);
+                }
+                return Ok(());
+            }
+        }
+
+        println!("=== Allocator Efficiency ({} slots per group) ===", ChunkAllocator::group_capacity());
+        if efficiency.buckets.is_empty() {
+            println!("No groups allocated in any size bucket.");
+            return Ok(());
+        }
+        println!("{:<12} {:>8} {:>8} {:>12} {:>12} {:>11}", "Size", "Full", "Active", "Capacity", "Occupied", "Efficiency");
+        println!("{}", "-".repeat(68));
+        for bucket in &efficiency.buckets {
+            let percent = format!("{:>10.2}%", bucket.efficiency);
+            let percent = if bucket.efficiency < LOW_UTILIZATION { self.highlight(&percent, Color::Red) } else { percent };
+            println!("{:<12} {:>8} {:>8} {:>12} {:>12} {}",
+                format_size(bucket.chunk_size), bucket.full_groups, bucket.active_groups, bucket.capacity, bucket.occupied, percent);
+        }
+        println!("\nOverall: {} of {} group capacity occupied ({:.2}%)",
+            format_size(efficiency.occupied_bytes), format_size(efficiency.capacity_bytes), efficiency.efficiency);
+
+        Ok(())
+    }
+
+    fn allocator_efficiency(&self) -> Result<AllocatorEfficiency> {
+        let mut buckets = Vec::new();
+        let (mut capacity_bytes, mut occupied_bytes) = (0u64, 0u64);
+        for chunk_size in Size::all_buckets() {
+            let counter = Arc::new(AllocatorCounter::new(chunk_size));
+            let chunk_allocator = ChunkAllocator::load(self.meta_store.iterator(), counter, chunk_size)?;
+            let packing = chunk_allocator.packing();
+            if packing.groups == 0 {
+                continue;
+            }
+            capacity_bytes += packing.capacity * chunk_size.0;
+            occupied_bytes += packing.occupied * chunk_size.0;
+            buckets.push(BucketPacking {
+                chunk_size: chunk_size.0,
+                full_groups: chunk_allocator.full_groups.len(),
+                active_groups: chunk_allocator.active_groups.len(),
+                capacity: packing.capacity,
+                occupied: packing.occupied,
+                efficiency: packing.efficiency() * 100.0,
+            });
+        }
+
+        let efficiency = if capacity_bytes == 0 { 0.0 } else { occupied_bytes as f64 * 100.0 / capacity_bytes as f64 };
+        Ok(AllocatorEfficiency { buckets, capacity_bytes, occupied_bytes, efficiency })
+    }
+
+    /// Chunk count and average utilization of every chain version, lowest utilization first. A chain version
+    /// holding mostly underfilled chunks may be a stale or partially migrated replica.
+    pub fn show_chain_utilization(&self, output_format: OutputFormat) -> Result<()> {
+        let chains = self.chain_utilization()?;
+
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {}
+            OutputFormat::Json => {
+                println!("{}", to_json(&chains)?);
+                return Ok(());
+            }
+            OutputFormat::JsonLines => {
+                for chain in &chains {
+                    println!("{}", to_json_line(chain)?);
+                }
+                return Ok(());
+            }
+        }
+
+        println!("=== Utilization by Chain Version ===");
+        if chains.is_empty() {
+            println!("No chunks found");
+            return Ok(());
+        }
+        println!("{:>10} {:>10} {:>14} {:>14} {:>8} {:>10}", "Chain Ver", "Chunks", "Actual", "Allocated", "Util %", "Low Util");
+        println!("{}", "-".repeat(71));
+        for chain in &chains {
+            let utilization = format!("{:>7.2}%", chain.avg_utilization);
+            let utilization = if chain.avg_utilization < LOW_UTILIZATION { self.highlight(&utilization, Color::Red) } else { utilization };
+            println!("{:>10} {:>10} {:>14} {:>14} {} {:>10}",
+                chain.chain_ver, chain.chunks, format_size(chain.total_len), format_size(chain.total_allocated),
+                utilization, chain.low_utilization_chunks);
+        }
+        println!("\nLow Util counts chunks using less than {:.0}% of their slot", LOW_UTILIZATION);
+
+        Ok(())
+    }
+
+    /// One pass over the chunk metas, grouped by chain version and sorted by average utilization
+    fn chain_utilization(&self) -> Result<Vec<ChainUtilization>> {
+        let mut chains: BTreeMap<u32, ChainUtilization> = BTreeMap::new();
+
+        let mut it = self.meta_store.iterator();
+        let end_key = MetaKey::chunk_meta_key_with(self.key_prefix(), &[]);
+        it.seek(&end_key)?;
+        if it.key() == Some(end_key.as_ref()) {
+            it.next(); // [begin, end)
+        }
+        while let Some(value) = it.key().filter(|key| key[0] == self.key_prefix()).and(it.value()) {
+            let chunk_meta = ChunkMeta::deserialize(value).map_err(Error::SerializationError)?;
+            let chunk_size = chunk_meta.pos.chunk_size().0;
+            let chain = chains.entry(chunk_meta.chain_ver).or_insert_with(|| ChainUtilization {
+                chain_ver: chunk_meta.chain_ver,
+                chunks: 0,
+                total_len: 0,
+                total_allocated: 0,
+                avg_utilization: 0.0,
+                low_utilization_chunks: 0,
+            });
+            chain.chunks += 1;
+            chain.total_len += chunk_meta.len as u64;
+            chain.total_allocated += chunk_size;
+            if (chunk_meta.len as f64) * 100.0 < LOW_UTILIZATION * chunk_size as f64 {
+                chain.low_utilization_chunks += 1;
+            }
+            it.next();
+        }
+
+        let mut chains: Vec<ChainUtilization> = chains.into_values().collect();
+        for chain in &mut chains {
+            chain.avg_utilization = chain.total_len as f64 * 100.0 / chain.total_allocated as f64;
+        }
+        // Chain versions come out of the map in order, the stable sort keeps it for equal utilization
+        chains.sort_by(|a, b| a.avg_utilization.total_cmp(&b.avg_utilization));
+        Ok(chains)
+    }
+
+    /// Per size bucket, the chunks whose length fits in the next smaller bucket and the bytes moving them down would reclaim
+    pub fn show_rebucket_candidates(&self, output_format: OutputFormat) -> Result<()> {
+        let buckets = self.rebucket_candidates()?;
+
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {}
+            OutputFormat::Json => {
+                println!("{}", to_json(&buckets)?);
+                return Ok(());
+            }
+            OutputFormat::JsonLines => {
+                for bucket in &buckets {
+                    println!("{}", to_json_line(bucket)?);
+                }
+                return Ok(());
+            }
+        }
+
+        println!("=== Rebucket Candidates ===");
+        if buckets.is_empty() {
+            println!("No chunks above the smallest size bucket.");
+            return Ok(());
+        }
+        println!("{:<12} {:<12} {:>12} {:>12} {:>14}", "Size", "Target", "Chunks", "Candidates", "Reclaimable");
+        println!("{}", "-".repeat(66));
+        for bucket in &buckets {
+            println!("{:<12} {:<12} {:>12} {:>12} {:>14}",
+                format_size(bucket.chunk_size), format_size(bucket.target_size), bucket.chunks, bucket.candidates,
+                format_size(bucket.reclaimable_bytes));
+        }
+
+        let candidates: u64 = buckets.iter().map(|bucket| bucket.candidates).sum();
+        let reclaimable_bytes: u64 = buckets.iter().map(|bucket| bucket.reclaimable_bytes).sum();
+        println!("\nTotal: {} candidates, {} reclaimable ({} bytes)", candidates, format_size(reclaimable_bytes), reclaimable_bytes);
+
+        Ok(())
+    }
+
+    /// Report what `--rebucket` would move out of one size bucket without mutating the store
+    pub fn show_rebucket_plan(&self, target_size: u32) -> Result<()> {
+        let chunk_size = bucket_size(target_size)?;
+        let Some(smaller) = chunk_size.prev_bucket() else {
+            return Err(Error::InvalidArg(format!("{} is the smallest size bucket, there is no smaller one to move chunks to", format_size(chunk_size.0))));
+        };
+        let bucket = self.rebucket_candidates()?.into_iter().find(|bucket| bucket.chunk_size == chunk_size.0);
+        let (chunks, candidates, reclaimable_bytes) = bucket.map_or((0, 0, 0), |bucket| (bucket.chunks, bucket.candidates, bucket.reclaimable_bytes));
+
+        println!("=== Rebucket Plan (dry run) ===");
+        println!("Size bucket: {} ({} bytes) -> {} ({} bytes)", format_size(chunk_size.0), chunk_size.0, format_size(smaller.0), smaller.0);
+        println!("Chunks to move: {} of {}", candidates, chunks);
+        println!("Space to reclaim: {} ({} bytes)", format_size(reclaimable_bytes), reclaimable_bytes);
+
+        Ok(())
+    }
+
+    /// One pass over the chunk metas, every bucket holding chunks except the smallest, which has nowhere to move them
+    fn rebucket_candidates(&self) -> Result<Vec<RebucketCandidates>> {
+        let mut buckets: BTreeMap<Size, RebucketCandidates> = BTreeMap::new();
+
+        let mut it = self.meta_store.iterator();
+        let end_key = MetaKey::chunk_meta_key_with(self.key_prefix(), &[]);
+        it.seek(&end_key)?;
+        if it.key() == Some(end_key.as_ref()) {
+            it.next(); // [begin, end)
+        }
+        while let Some(value) = it.key().filter(|key| key[0] == self.key_prefix()).and(it.value()) {
+            let chunk_meta = ChunkMeta::deserialize(value).map_err(Error::SerializationError)?;
+            let chunk_size = chunk_meta.pos.chunk_size();
+            if let Some(target_size) = chunk_size.prev_bucket() {
+                let bucket = buckets.entry(chunk_size).or_insert_with(|| RebucketCandidates {
+                    chunk_size: chunk_size.0,
+                    target_size: target_size.0,
+                    chunks: 0,
+                    candidates: 0,
+                    reclaimable_bytes: 0,
+                });
+                bucket.chunks += 1;
+                if chunk_meta.len as u64 <= target_size.0 {
+                    bucket.candidates += 1;
+                    bucket.reclaimable_bytes += chunk_size.0 - target_size.0;
+                }
+            }
+            it.next();
+        }
+
+        Ok(buckets.into_values().collect())
+    }
+
+    /// p50, p90, p99 and max of the chunk lengths of one size bucket, or of all chunks if `target_size` is `None`
+    pub fn show_length_percentiles(&self, target_size: Option<u32>, exact: bool, output_format: OutputFormat) -> Result<()> {
+        let chunk_size = target_size.map(bucket_size).transpose()?;
+        let mut collector = LengthCollector::new(exact);
+
+        let mut it = self.meta_store.iterator();
+        let end_key = MetaKey::chunk_meta_key_with(self.key_prefix(), &[]);
+        it.seek(&end_key)?;
+        if it.key() == Some(end_key.as_ref()) {
+            it.next(); // [begin, end)
+        }
+        while let Some(value) = it.key().filter(|key| key[0] == self.key_prefix()).and(it.value()) {
+            let chunk_meta = ChunkMeta::deserialize(value).map_err(Error::SerializationError)?;
+            if chunk_size.is_none_or(|chunk_size| chunk_meta.pos.chunk_size() == chunk_size) {
+                collector.add(chunk_meta.len);
+            }
+            it.next();
+        }
+
+        let percentiles = collector.finish();
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {
+                println!("=== Chunk Length Percentiles ===");
+                match chunk_size {
+                    Some(chunk_size) => println!("Size bucket: {} ({})", format_size(chunk_size.0), chunk_size.0),
+                    None => println!("Size bucket: all"),
+                }
+                let Some(percentiles) = percentiles else {
+                    println!("No chunks found");
+                    return Ok(());
+                };
+                println!("Chunks: {}", percentiles.chunks);
+                for (name, len) in [("p50", percentiles.p50), ("p90", percentiles.p90), ("p99", percentiles.p99), ("max", percentiles.max)] {
+                    println!("{:<6} {:>12} ({})", name, format_size(len), len);
+                }
+                if percentiles.approximate {
+                    println!("\np50 to p99 are streaming estimates, use --exact for exact values (buffers every length)");
+                }
+            }
+            OutputFormat::Json => println!("{}", to_json(&percentiles)?),
+            OutputFormat::JsonLines => println!("{}", to_json_line(&percentiles)?),
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn list_chunks_detailed(
+        &self,
+        target_size: u32,
+        slice: ListingSlice,
+        short_ids: bool,
+        show_checksum: bool,
+        table_style: TableStyle,
+        filter: ChunkFilter,
+        output_format: OutputFormat,
+    ) -> Result<()> {
+        let listing = self.collect_chunks(target_size, filter)?;
+
+        // Only the chunks on the requested page, so the output can be piped into jq. Rows always carry the checksum.
+        // JSON wraps them with the page numbers, JSON lines streams the bare rows
+        match output_format {
+            OutputFormat::Table => {}
+            OutputFormat::Json => {
+                println!("{}", to_json(&listing.json_page(slice)?)?);
+                return Ok(());
+            }
+            OutputFormat::JsonLines => {
+                for row in listing.slice_rows(slice)? {
+                    println!("{}", to_json_line(&row)?);
+                }
+                return Ok(());
+            }
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                print_delimited(&listing.slice_rows(slice)?, output_format);
+                return Ok(());
+            }
+        }
+
+        if listing.chunks_info.is_empty() {
+            self.display_no_chunks(target_size);
+            return Ok(());
+        }
+
+        self.display_slice(&listing, slice, short_ids, show_checksum, table_style)?;
+        if let ListingSlice::Page { page_size, page } = slice {
+            self.display_pagination_info(page, listing.total_pages(page_size));
+        }
+
+        Ok(())
+    }
+
+    /// The detailed listing of every size bucket in turn, showing at most `limit_per_bucket` chunks of each, then the totals of all buckets
+    pub fn list_all_chunks(
+        &self,
+        limit_per_bucket: Option<usize>,
+        short_ids: bool,
+        show_checksum: bool,
+        table_style: TableStyle,
+        output_format: OutputFormat,
+    ) -> Result<()> {
+        let (buckets, total) = self.collect_all_chunks(limit_per_bucket)?;
+
+        match output_format {
+            OutputFormat::Table => {}
+            OutputFormat::Json => {
+                #[derive(Serialize)]
+                struct AllBuckets {
+                    buckets: Vec<BucketListing>,
+                    total: ListingTotals,
+                }
+                println!("{}", to_json(&AllBuckets { buckets, total })?);
+                return Ok(());
+            }
+            OutputFormat::JsonLines => {
+                for row in buckets.into_iter().flat_map(|bucket| bucket.rows) {
+                    println!("{}", to_json_line(&row)?);
+                }
+                return Ok(());
+            }
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                let rows: Vec<ChunkRow> = buckets.into_iter().flat_map(|bucket| bucket.rows).collect();
+                print_delimited(&rows, output_format);
+                return Ok(());
+            }
+        }
+
+        for bucket in buckets {
+            let totals = bucket.totals;
+            println!("=== Size Bucket {} ({}) ===", format_size_to(bucket.chunk_size, self.precision), bucket.chunk_size);
+            println!("Chunks: {}", totals.chunks);
+            println!("Total actual size: {} ({})", format_size_to(totals.total_len, self.precision), totals.total_len);
+            println!("Total allocated size: {} ({})", format_size_to(totals.total_allocated, self.precision), totals.total_allocated);
+            println!("Average utilization: {:.*}%", self.precision, (totals.total_len as f64 / totals.total_allocated as f64) * 100.0);
+            println!();
+            let shown = bucket.rows.len() as u64;
+            self.display_chunks_table(bucket.rows, short_ids, show_checksum, table_style);
+            if shown < totals.chunks {
+                println!("... {} more chunks, use --list-size {} to page through them", totals.chunks - shown, size_arg(Size::from(bucket.chunk_size)));
+            }
+            println!();
+        }
+
+        println!("=== All Size Buckets ===");
+        if total.chunks == 0 {
+            println!("No chunks found");
+            return Ok(());
+        }
+        println!("Total chunks: {}", total.chunks);
+        println!("Total actual size: {} ({})", format_size_to(total.total_len, self.precision), total.total_len);
+        println!("Total allocated size: {} ({})", format_size_to(total.total_allocated, self.precision), total.total_allocated);
+        println!("Average utilization: {:.*}%", self.precision, (total.total_len as f64 / total.total_allocated as f64) * 100.0);
+
+        Ok(())
+    }
+
+    /// The listing of every size bucket holding chunks, limited to the first `limit_per_bucket` rows of each, and the grand total
+    fn collect_all_chunks(&self, limit_per_bucket: Option<usize>) -> Result<(Vec<BucketListing>, ListingTotals)> {
+        let mut buckets = vec![];
+        let mut total = ListingTotals::default();
+        for chunk_size in Size::all_buckets() {
+            let listing = self.collect_chunks(chunk_size.0 as u32, ChunkFilter::default())?;
+            if listing.chunks_info.is_empty() {
+                continue;
+            }
+            let totals = listing.totals();
+            total.add(totals);
+            let rows = listing.page_rows(limit_per_bucket.unwrap_or(usize::MAX), 1)?;
+            buckets.push(BucketListing { chunk_size: chunk_size.0, totals, rows });
+        }
+        Ok((buckets, total))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn browse_chunks_interactive(
+        &self,
+        target_size: u32,
+        page_size: usize,
+        page: usize,
+        short_ids: bool,
+        show_checksum: bool,
+        table_style: TableStyle,
+        filter: ChunkFilter,
+    ) -> Result<()> {
+        let listing = self.collect_chunks(target_size, filter)?;
+
+        if listing.chunks_info.is_empty() {
+            self.display_no_chunks(target_size);
+            return Ok(());
+        }
+
+        // The collected chunks are already sorted, so paging just moves the window.
+        let total_pages = listing.total_pages(page_size);
+        let mut page = page.clamp(1, total_pages);
+        loop {
+            clear_screen();
+            self.display_slice(&listing, ListingSlice::Page { page_size, page }, short_ids, show_checksum, table_style)?;
+            println!();
+            print!("[n]ext  [p]revious  [q]uit > ");
+            let _ = std::io::stdout().flush();
+
+            match read_key()? {
+                b'n' | b'N' | b' ' if page < total_pages => page += 1,
+                b'p' | b'P' if page > 1 => page -= 1,
+                b'q' | b'Q' | 0x1b => break,
+                _ => {}
+            }
+        }
+        println!();
+
+        Ok(())
+    }
+
+    fn collect_chunks(&self, target_size: u32, filter: ChunkFilter) -> Result<DetailedListing> {
+        if let Some(group) = filter.group {
+            self.check_group_exists(target_size, group)?;
+        }
+
+        let mut it = self.meta_store.iterator();
+        self.id_range.seek(&mut it, self.key_prefix())?;
+
+        let mut chunks_info: Vec<(Bytes, ChunkMeta)> = Vec::new();
+        let mut total_actual_size = 0u64;
+        let mut total_allocated_size = 0u64;
+        let mut utilization = RunningStats::default();
+
+        // Collect all chunks for the target size
+        loop {
+            if !it.valid() {
+                break;
+            }
+
+            if it.key().unwrap()[0] != self.key_prefix() {
+                break;
+            }
+            if self.id_range.is_past(&MetaKey::parse_chunk_meta_key(it.key().unwrap())) {
+                break;
+            }
+
+            let chunk_meta =
+                ChunkMeta::deserialize(it.value().unwrap()).map_err(Error::SerializationError)?;
+
+            let chunk_size = chunk_meta.pos.chunk_size();
+            
+            if chunk_size == target_size && filter.matches(&chunk_meta) {
+                let raw_key = it.key().unwrap();
+                let chunk_id = MetaKey::parse_chunk_meta_key(raw_key);
+                if chunk_meta.len as u64 > chunk_size.0 {
+                    eprintln!("Warning: chunk {} has length {} beyond its capacity {}, its metadata is corrupt",
+                        format_hex_chunk_id(&chunk_id), chunk_meta.len, chunk_size.0);
+                }
+                total_actual_size += chunk_meta.len as u64;
+                total_allocated_size += u64::from(chunk_size);
+                utilization.add(chunk_meta.len as f64 / chunk_size.0 as f64 * 100.0);
+                chunks_info.push((chunk_id, chunk_meta));
+            }
+
+            it.next();
+        }
+
+        // Sort chunks by chunk ID for consistent ordering
+        chunks_info.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(DetailedListing {
+            target_size,
+            filter,
+            chunks_info,
+            total_actual_size,
+            total_allocated_size,
+            utilization,
+        })
+    }
+
+    /// Only the totals of the detailed listing after the filters are applied, without printing any chunk
+    pub fn show_listing_stats(&self, target_size: u32, filter: ChunkFilter, output_format: OutputFormat) -> Result<()> {
+        let listing = self.collect_chunks(target_size, filter)?;
+        let totals = listing.totals();
+
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => print!("{}", render_listing_totals(target_size, filter, totals, listing.utilization.stddev(), self.precision)),
+            OutputFormat::Json => println!("{}", to_json(&totals)?),
+            OutputFormat::JsonLines => println!("{}", to_json_line(&totals)?),
+        }
+
+        Ok(())
+    }
+
+    /// Page of the detailed listing holding `chunk_id`, found by counting the chunks sorted before it without collecting the bucket
+    pub fn find_page(&self, target_size: u32, filter: ChunkFilter, chunk_id: &[u8], page_size: usize) -> Result<usize> {
+        if page_size == 0 {
+            return Err(Error::InvalidArg("--page-size must be at least 1".into()));
+        }
+
+        let chunk_id_hex = format_hex_chunk_id(chunk_id);
+        let Some(chunk_meta) = self.meta_store.get_chunk_meta(chunk_id)? else {
+            return Err(Error::InvalidArg(format!("Chunk {} not found", chunk_id_hex)));
+        };
+        let chunk_size = chunk_meta.pos.chunk_size();
+        if chunk_size != target_size {
+            return Err(Error::InvalidArg(format!(
+                "Chunk {} is in size bucket {}, not {}, try --list-size {}",
+                chunk_id_hex, format_size(chunk_size.0), format_size(target_size as u64), size_arg(chunk_size))));
+        }
+        if !filter.matches(&chunk_meta) {
+            return Err(Error::InvalidArg(format!("Chunk {} is filtered out of the listing by --group, --empty-only or --full-only", chunk_id_hex)));
+        }
+
+        let mut it = self.meta_store.iterator();
+        let end_key = MetaKey::chunk_meta_key_with(self.key_prefix(), &[]);
+        it.seek(&end_key)?;
+        if it.key() == Some(end_key.as_ref()) {
+            it.next(); // [begin, end)
+        }
+
+        let mut index = 0usize;
+        while let Some(key) = it.key().filter(|key| key[0] == self.key_prefix()) {
+            if MetaKey::parse_chunk_meta_key(key).as_ref() < chunk_id {
+                let chunk_meta = ChunkMeta::deserialize(it.value().unwrap()).map_err(Error::SerializationError)?;
+                if chunk_meta.pos.chunk_size() == target_size && filter.matches(&chunk_meta) {
+                    index += 1;
+                }
+            }
+            it.next();
+        }
+
+        Ok(index / page_size + 1)
+    }
+
+    fn check_group_exists(&self, target_size: u32, group: u32) -> Result<()> {
+        let chunk_size = bucket_size(target_size)?;
+        let counter = Arc::new(AllocatorCounter::new(chunk_size));
+        let chunk_allocator = ChunkAllocator::load(self.meta_store.iterator(), counter, chunk_size)?;
+        if !chunk_allocator.groups().any(|g| g.group_id.index() == group) {
+            return Err(Error::InvalidArg(format!(
+                "Group {} does not exist in size bucket {}, run --list-groups {} to see the groups",
+                group, format_size(chunk_size.0), size_arg(chunk_size))));
+        }
+        Ok(())
+    }
+
+    fn display_slice(&self, listing: &DetailedListing, slice: ListingSlice, short_ids: bool, show_checksum: bool, table_style: TableStyle) -> Result<()> {
+        let rows = listing.slice_rows(slice)?;
+
+        print!("{}", render_listing_totals(listing.target_size, listing.filter, listing.totals(), listing.utilization.stddev(), self.precision));
+        println!();
+        println!("{}", listing.slice_caption(slice, rows.len()));
+        self.display_chunks_table(rows, short_ids, show_checksum, table_style);
+        Ok(())
+    }
+
+    fn display_no_chunks(&self, target_size: u32) {
+        println!("No chunks found for size bucket: {} ({} bytes)", format_size(target_size as u64), target_size);
+        println!("Run without --list-size to see available size buckets");
+    }
+
+    /// The buckets the summary tables show, and how many `--min-chunks` hid
+    fn shown_buckets<'a>(&self, summary: &'a Summary) -> (Vec<&'a BucketSummary>, usize) {
+        let shown: Vec<_> = summary.buckets.iter().filter(|bucket| bucket.used_chunks >= self.min_chunks).collect();
+        let hidden = summary.buckets.len() - shown.len();
+        (shown, hidden)
+    }
+
+    pub fn display_summary(&self, summary: &Summary) {
+        println!("=== Chunk Allocation Summary ===");
+        if summary.empty {
+            println!("\n{}", self.highlight(EMPTY_STORE_WARNING, Color::Yellow));
+        }
+        let (buckets, hidden) = self.shown_buckets(summary);
+        
+        // Show available size buckets with friendly names
+        println!("\nAvailable size buckets:");
+        for bucket in &buckets {
+            println!("  {:<10} ({} bytes): {} used chunks", format_size(bucket.chunk_size), bucket.chunk_size, bucket.used_chunks);
+        }
+        if hidden > 0 {
+            println!("  ({} buckets with fewer than {} used chunks hidden by --min-chunks)", hidden, self.min_chunks);
+        }
+        
+        println!("\nReserved chunks per size:");
+        for bucket in &buckets {
+            println!("  {:<10} ({} bytes): {} reserved chunks", format_size(bucket.chunk_size), bucket.chunk_size, bucket.reserved_chunks);
         }
         
         println!("\nGroup counts (full, active):");
-        for (size, (full, active)) in group_count {
-            println!("  {:<10} ({} bytes): {} full, {} active groups", format_size(u64::from(*size)), size, full, active);
+        for bucket in &buckets {
+            println!("  {:<10} ({} bytes): {} full, {} active groups", format_size(bucket.chunk_size), bucket.chunk_size, bucket.full_groups, bucket.active_groups);
+        }
+
+        println!("\nFragmentation (free slots / total slots in active groups):");
+        for bucket in &buckets {
+            println!("  {:<10} ({} bytes): {:.2}%", format_size(bucket.chunk_size), bucket.chunk_size, bucket.fragmentation * 100.0);
+        }
+        
+        println!("\nZero-length chunks (allocated space they hold):");
+        for bucket in &buckets {
+            println!("  {:<10} ({} bytes): {} empty chunks ({})", format_size(bucket.chunk_size), bucket.chunk_size, bucket.empty_chunks, format_size(bucket.empty_chunks * bucket.chunk_size));
+        }
+
+        println!("\nChunks at capacity (grow into a larger bucket on the next append):");
+        for bucket in &buckets {
+            println!("  {:<10} ({} bytes): {} full chunks", format_size(bucket.chunk_size), bucket.chunk_size, bucket.full_chunks);
+        }
+
+        println!("\nUtilization per chunk (mean and standard deviation, a high deviation means inconsistent packing):");
+        for bucket in &buckets {
+            if bucket.committed_chunks + bucket.uncommitted_chunks == 0 {
+                println!("  {:<10} ({} bytes): no chunks", format_size(bucket.chunk_size), bucket.chunk_size);
+            } else {
+                println!("  {:<10} ({} bytes): {:.*}% mean, {:.*}% stddev", format_size(bucket.chunk_size), bucket.chunk_size,
+                    self.precision, bucket.avg_utilization, self.precision, bucket.utilization_stddev);
+            }
+        }
+
+        println!("\nCommitted / uncommitted chunks (uncommitted ones are interrupted writes needing recovery):");
+        for bucket in &buckets {
+            let line = format!("  {:<10} ({} bytes): {} committed, {} uncommitted", format_size(bucket.chunk_size), bucket.chunk_size, bucket.committed_chunks, bucket.uncommitted_chunks);
+            if bucket.uncommitted_chunks > 0 {
+                println!("{}", self.highlight(&line, Color::Yellow));
+            } else {
+                println!("{}", line);
+            }
+        }
+
+        if !summary.checksum_algorithms.is_empty() {
+            println!("\nChecksum algorithms:");
+            for (algorithm, chunks) in &summary.checksum_algorithms {
+                println!("  {:<16} {} chunks", algorithm, chunks);
+            }
+            if summary.checksum_algorithms.contains_key(UNKNOWN_CHECKSUM_ALGORITHM) {
+                println!("  (chunk metadata does not record the algorithm, --verify detects chunks using another one)");
+            }
         }
-        
+
+        let total = &summary.total;
+        println!("\nTotal of all buckets{}:", if hidden > 0 { ", including hidden ones" } else { "" });
+        println!("  {} used chunks ({}), {} reserved chunks", total.used_chunks, format_size(total.used_bytes), total.reserved_chunks);
+        println!("  {} full, {} active groups", total.full_groups, total.active_groups);
+
+        // A consistency warning, shown even for buckets --min-chunks hides
+        if summary.buckets.iter().any(|bucket| bucket.stale_positions > 0) {
+            println!("\n{}", self.highlight("Stale positions (taken without a chunk meta, see --release-reservations):", Color::Yellow));
+            for bucket in summary.buckets.iter().filter(|bucket| bucket.stale_positions > 0) {
+                let line = format!("  {:<10} ({} bytes): {} stale positions", format_size(bucket.chunk_size), bucket.chunk_size, bucket.stale_positions);
+                println!("{}", self.highlight(&line, Color::Yellow));
+            }
+        }
+
+        if summary.buckets.iter().any(|bucket| bucket.excess_chunks > 0) {
+            println!("\n{}", self.highlight("Excess chunk metas (more chunk metas than slots taken for them, the allocator may hand their slots out again):", Color::Red));
+            for bucket in summary.buckets.iter().filter(|bucket| bucket.excess_chunks > 0) {
+                let line = format!("  {:<10} ({} bytes): {} excess chunk metas", format_size(bucket.chunk_size), bucket.chunk_size, bucket.excess_chunks);
+                println!("{}", self.highlight(&line, Color::Red));
+            }
+        }
+
+        // Shown even for buckets --min-chunks hides, like the stale positions
+        if summary.buckets.iter().any(|bucket| bucket.zero_checksum_chunks > 0) {
+            println!("\n{}", self.highlight("Zero checksums (chunks with data whose checksum was likely never computed, see --zero-checksum):", Color::Yellow));
+            for bucket in summary.buckets.iter().filter(|bucket| bucket.zero_checksum_chunks > 0) {
+                let line = format!("  {:<10} ({} bytes): {} zero checksum chunks", format_size(bucket.chunk_size), bucket.chunk_size, bucket.zero_checksum_chunks);
+                println!("{}", self.highlight(&line, Color::Yellow));
+            }
+        }
+
         println!("\nUse --list-size <SIZE> to see detailed chunk information (e.g., --list-size 4MB)");
         println!("Use --read-chunk <CHUNK_ID> to read actual chunk content (e.g., --read-chunk a1b2c3d4...)");
     }
 
-    fn display_detailed_header(
-        &self,
-        target_size: u32,
-        total_chunks: u64,
-        total_actual_size: u64,
-        total_allocated_size: u64,
-        page: usize,
-        total_pages: usize,
-        chunks_on_page: usize,
-    ) {
-        println!("=== Detailed Chunk Information ===");
-        println!("Size bucket: {} ({})", format_size(target_size as u64), target_size);
-        println!("Total chunks: {}", total_chunks);
-        println!("Total actual size: {} ({})", format_size(total_actual_size), total_actual_size);
-        println!("Total allocated size: {} ({})", format_size(total_allocated_size), total_allocated_size);
-        println!("Average utilization: {:.2}%", (total_actual_size as f64 / total_allocated_size as f64) * 100.0);
-        println!();
-        println!("Page {}/{} (showing {} chunks)", page, total_pages, chunks_on_page);
-    }
+    #[allow(clippy::too_many_arguments)]
+    fn display_chunks_table(&self, chunk_rows: Vec<ChunkRow>, short_ids: bool, show_checksum: bool, table_style: TableStyle) {
+        let colors: Vec<Vec<Option<Color>>> = chunk_rows.iter().map(|row| row.colors(show_checksum)).collect();
+        let rows: Vec<Vec<String>> = chunk_rows.into_iter()
+            .map(|row| {
+                let bar = self.util_bars.then(|| util_bar(row.utilization));
+                let mut cells = row.cells(short_ids, show_checksum, self.precision);
+                if let Some(bar) = bar {
+                    cells[4] = format!("{} {}", cells[4], bar);
+                }
+                cells
+            })
+            .collect();
+        // Cells are painted after padding so escape codes do not shift the columns
+        let paint_cell = |row: usize, column: usize, cell: String| match colors[row][column] {
+            Some(color) if self.color => paint(&cell, color),
+            _ => cell,
+        };
 
-    fn display_chunks_table(
-        &self,
-        chunks_info: &[(Bytes, ChunkMeta)],
-        start_idx: usize,
-        end_idx: usize,
-        target_size: u32,
-        short_ids: bool,
-    ) {
-        let (id_width, total_width) = if short_ids { (20, 130) } else { (68, 175) };
-        println!("{:<8} {:<width$} {:<15} {:<15} {:<8} {:<12} {:<12} {:<8}", 
-                 "Index", "Chunk ID (hex)", "Alloc Size", "Actual Len", "Util %", "Chain Ver", "Chunk Ver", "Uncommit", width = id_width);
-        println!("{}", "-".repeat(total_width));
-
-        for (i, (chunk_id, chunk_meta)) in chunks_info.iter().enumerate().skip(start_idx).take(end_idx - start_idx) {
-            let chunk_id_hex = chunk_id.iter()
-                .map(|b| format!("{:02x}", b))
-                .collect::<Vec<_>>()
-                .join("");
-            
-            let chunk_id_display = if short_ids {
-                if chunk_id_hex.len() > 16 {
-                    format!("{}...", &chunk_id_hex[..16])
-                } else {
-                    chunk_id_hex
+        match table_style {
+            TableStyle::Plain => print!("{}", render_plain_table(&rows, short_ids, show_checksum, &paint_cell)),
+            TableStyle::Box => {
+                // Full IDs can be up to 64 bytes, keep the table within a wide terminal
+                let id_max_width = if short_ids { 19 } else { 64 };
+                let mut columns = vec![
+                    Column::new("Index", Align::Right),
+                    Column::new("Chunk ID (hex)", Align::Left).with_max_width(id_max_width),
+                    Column::new("Alloc Size", Align::Right),
+                    Column::new("Actual Len", Align::Right),
+                    Column::new("Util %", Align::Right),
+                    Column::new("Chain Ver", Align::Right),
+                    Column::new("Chunk Ver", Align::Right),
+                ];
+                if show_checksum {
+                    columns.push(Column::new("Checksum", Align::Left));
                 }
-            } else {
-                chunk_id_hex
-            };
-            
-            let utilization = (chunk_meta.len as f64 / target_size as f64) * 100.0;
-            
-            println!("{:<8} {:<width$} {:<15} {:<15} {:<8.2} {:<12} {:<12} {:<8}", 
-                     i + 1,
-                     chunk_id_display,
-                     format_size(target_size as u64),
-                     format_size(chunk_meta.len as u64),
-                     utilization,
-                     chunk_meta.chain_ver,
-                     chunk_meta.chunk_ver,
-                     if chunk_meta.uncommitted { "Yes" } else { "No" },
-                     width = id_width
-            );
+                columns.push(Column::new("Uncommit", Align::Left));
+                print!("{}", render_box_table_with(&columns, &rows, &paint_cell));
+            }
         }
     }
 
@@ -255,4 +2052,1757 @@ impl ChunkLister {
             println!("Use --page {} to see previous page", page - 1);
         }
     }
-}
\ No newline at end of file
+}
+
+/// A bucket size the way it is typed on the command line, e.g. "4MB"
+fn size_arg(chunk_size: Size) -> String {
+    format_size_with(chunk_size.0, SizeFormat { trim_exact: true, ..Default::default() }).replace(' ', "")
+}
+
+/// A fixed-width bar of `UTIL_BAR_WIDTH` characters filled to the nearest tenth of `utilization`, e.g. `[####------]`
+fn util_bar(utilization: f64) -> String {
+    let filled = ((utilization / 100.0 * UTIL_BAR_WIDTH as f64).round() as usize).min(UTIL_BAR_WIDTH);
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(UTIL_BAR_WIDTH - filled))
+}
+
+/// The detailed listing as space padded fixed-width columns, passing each padded body cell through `paint`
+fn render_plain_table(rows: &[Vec<String>], short_ids: bool, show_checksum: bool, paint: &dyn Fn(usize, usize, String) -> String) -> String {
+    let (id_width, mut total_width) = if short_ids { (20, 130) } else { (68, 175) };
+    let mut headers = vec!["Index", "Chunk ID (hex)", "Alloc Size", "Actual Len", "Util %", "Chain Ver", "Chunk Ver"];
+    // The utilization column grows to fit --util-bars
+    let util_width = rows.iter().map(|row| row[4].chars().count()).max().unwrap_or(0).max(8);
+    total_width += util_width - 8;
+    let mut widths = vec![8, id_width, 15, 15, util_width, 12, 12];
+    if show_checksum {
+        headers.push("Checksum");
+        widths.push(12);
+        total_width += 13;
+    }
+    headers.push("Uncommit");
+    widths.push(8);
+
+    let format_line = |cells: &[String], paint: &dyn Fn(usize, String) -> String| -> String {
+        let cells: Vec<String> = cells.iter().zip(&widths).enumerate()
+            .map(|(column, (cell, &width))| paint(column, format!("{:<width$}", cell, width = width)))
+            .collect();
+        format!("{}\n", cells.join(" "))
+    };
+    let headers: Vec<String> = headers.into_iter().map(String::from).collect();
+    let mut output = format_line(&headers, &|_, cell| cell);
+    output.push_str(&format!("{}\n", "-".repeat(total_width)));
+    for (i, row) in rows.iter().enumerate() {
+        output.push_str(&format_line(row, &|column, cell| paint(i, column, cell)));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::utils::{meta_store_config, JSON_SCHEMA_VERSION};
+    use super::*;
+
+    fn create_engine(path: &std::path::Path) -> Engine {
+        let config = EngineConfig {
+            path: path.into(),
+            create: true,
+            prefix_len: 4,
+            ..Default::default()
+        };
+        Engine::open(&config).unwrap()
+    }
+
+    fn open_lister(path: &std::path::Path) -> ChunkLister {
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: path.join("meta"),
+                mode: DbOpenMode::ReadOnly,
+                ..Default::default()
+            },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        ChunkLister::new(MetaStore::open(&config).unwrap())
+    }
+
+    /// Store `n` chunks of `len` bytes under the IDs `0..n`, chunk `i` filled with `i`
+    fn put_chunks(path: &std::path::Path, n: u32, len: usize) {
+        let engine = create_engine(path);
+        for i in 0..n {
+            engine
+                .put(&i.to_be_bytes(), &vec![i as u8; len], 1, 1)
+                .unwrap();
+        }
+    }
+
+    /// A lister over a new store of `n` chunks of `len` bytes, the directory has to outlive it
+    fn lister_with_chunks(n: u32, len: usize) -> (tempfile::TempDir, ChunkLister) {
+        let dir = tempfile::tempdir().unwrap();
+        put_chunks(dir.path(), n, len);
+        let chunk_lister = open_lister(dir.path());
+        (dir, chunk_lister)
+    }
+
+    #[test]
+    fn test_summary_exit_status() {
+        let dir = tempfile::tempdir().unwrap();
+        put_chunks(dir.path(), 4, 4096);
+        let summary = open_lister(dir.path())
+            .show_summary(OutputFormat::Json)
+            .unwrap();
+        assert_eq!(summary.exit_status(), ExitStatus::Ok);
+
+        // A stale position fails the summary after the report is printed
+        {
+            let engine = create_engine(dir.path());
+            let mut write_batch = RocksDB::new_write_batch();
+            write_batch.delete(MetaKey::chunk_meta_key(&2u32.to_be_bytes()));
+            engine.meta_store.write(write_batch, true).unwrap();
+        }
+        for output_format in [
+            OutputFormat::Table,
+            OutputFormat::Json,
+            OutputFormat::JsonLines,
+        ] {
+            let summary = open_lister(dir.path()).show_summary(output_format).unwrap();
+            assert_eq!(summary.exit_status(), ExitStatus::Mismatch);
+        }
+    }
+
+    #[test]
+    fn test_empty_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let engine = create_engine(dir.path());
+            for i in 0..4u32 {
+                let len = if i == 2 { 0 } else { 4096 };
+                engine
+                    .put(&i.to_be_bytes(), &vec![i as u8; len], 1, 1)
+                    .unwrap();
+            }
+        }
+
+        let chunk_lister = open_lister(dir.path());
+        let summary = chunk_lister.collect_summary().unwrap();
+        let bucket = summary
+            .buckets
+            .iter()
+            .find(|bucket| bucket.used_chunks > 0)
+            .unwrap();
+        assert_eq!(bucket.used_chunks, 4);
+        assert_eq!(bucket.empty_chunks, 1);
+        assert_eq!(
+            summary
+                .buckets
+                .iter()
+                .map(|bucket| bucket.empty_chunks)
+                .sum::<u64>(),
+            1
+        );
+        assert!(!summary.empty);
+        // The empty chunk's checksum is 0 like that of any empty data, it is no zero checksum chunk
+        assert_eq!(
+            summary
+                .buckets
+                .iter()
+                .map(|bucket| bucket.zero_checksum_chunks)
+                .sum::<u64>(),
+            0
+        );
+
+        let filter = ChunkFilter {
+            empty_only: true,
+            ..Default::default()
+        };
+        let listing = chunk_lister
+            .collect_chunks(bucket.chunk_size as u32, filter)
+            .unwrap();
+        assert_eq!(listing.chunks_info.len(), 1);
+        assert_eq!(listing.chunks_info[0].0.as_ref(), 2u32.to_be_bytes());
+        assert_eq!(listing.total_actual_size, 0);
+        assert_eq!(listing.total_allocated_size, bucket.chunk_size);
+
+        // Three full 4096 byte chunks and an empty one, against the stddev computed from all the samples at once
+        let samples: Vec<f64> = [4096.0, 4096.0, 0.0, 4096.0]
+            .iter()
+            .map(|len| len / bucket.chunk_size as f64 * 100.0)
+            .collect();
+        let mean = samples.iter().sum::<f64>() / 4.0;
+        let stddev = (samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / 4.0).sqrt();
+        assert!(
+            (bucket.avg_utilization - mean).abs() < 1e-9
+                && (bucket.utilization_stddev - stddev).abs() < 1e-9,
+            "{:?}",
+            bucket
+        );
+        let listing = chunk_lister
+            .collect_chunks(bucket.chunk_size as u32, ChunkFilter::default())
+            .unwrap();
+        assert!((listing.utilization.stddev() - stddev).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_full_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk_size = Size::all_buckets().next().unwrap();
+        {
+            let engine = create_engine(dir.path());
+            for i in 0..4u32 {
+                let len = if i % 2 == 0 {
+                    chunk_size.0 as usize
+                } else {
+                    4096
+                };
+                engine
+                    .put(&i.to_be_bytes(), &vec![i as u8; len], 1, 1)
+                    .unwrap();
+            }
+        }
+
+        let chunk_lister = open_lister(dir.path());
+        let summary = chunk_lister.collect_summary().unwrap();
+        let bucket = summary
+            .buckets
+            .iter()
+            .find(|bucket| bucket.chunk_size == chunk_size.0)
+            .unwrap();
+        assert_eq!(bucket.used_chunks, 4);
+        assert_eq!(bucket.full_chunks, 2);
+        assert_eq!(bucket.empty_chunks, 0);
+
+        let filter = ChunkFilter {
+            full_only: true,
+            ..Default::default()
+        };
+        let listing = chunk_lister
+            .collect_chunks(chunk_size.0 as u32, filter)
+            .unwrap();
+        let chunk_ids: Vec<_> = listing
+            .chunks_info
+            .iter()
+            .map(|(chunk_id, _)| chunk_id.to_vec())
+            .collect();
+        assert_eq!(
+            chunk_ids,
+            [0u32.to_be_bytes().to_vec(), 2u32.to_be_bytes().to_vec()]
+        );
+        assert_eq!(listing.total_actual_size, listing.total_allocated_size);
+    }
+
+    #[test]
+    fn test_bucket_stats_matches_listing() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let engine = create_engine(dir.path());
+            for i in 0..16u32 {
+                engine
+                    .put(
+                        &i.to_be_bytes(),
+                        &vec![i as u8; 1000 * (i as usize + 1)],
+                        1,
+                        1,
+                    )
+                    .unwrap();
+            }
+        }
+
+        let chunk_lister = open_lister(dir.path());
+        for chunk_size in Size::all_buckets() {
+            let stats = chunk_lister.meta_store.bucket_stats(chunk_size).unwrap();
+            let listing = chunk_lister
+                .collect_chunks(chunk_size.0 as u32, ChunkFilter::default())
+                .unwrap();
+            assert_eq!(stats.count, listing.chunks_info.len() as u64);
+            assert_eq!(stats.total_len, listing.total_actual_size);
+            assert_eq!(stats.total_allocated, listing.total_allocated_size);
+        }
+        let total: u64 = Size::all_buckets()
+            .map(|chunk_size| {
+                chunk_lister
+                    .meta_store
+                    .bucket_stats(chunk_size)
+                    .unwrap()
+                    .count
+            })
+            .sum();
+        assert_eq!(total, 16);
+    }
+
+    #[test]
+    fn test_page_range() {
+        let (_dir, chunk_lister) = lister_with_chunks(5, 4096);
+        let chunk_size = Size::all_buckets().next().unwrap();
+        let listing = chunk_lister
+            .collect_chunks(chunk_size.0 as u32, ChunkFilter::default())
+            .unwrap();
+        assert_eq!(listing.page_range(2, 1).unwrap(), 0..2);
+        assert_eq!(listing.page_range(2, 3).unwrap(), 4..5);
+        let rows = listing.page_rows(2, 3).unwrap();
+        assert_eq!(rows.iter().map(|row| row.index).collect::<Vec<_>>(), [5]);
+
+        // A page past the end is an error, not an empty page
+        assert!(matches!(
+            listing.page_range(2, 4),
+            Err(Error::InvalidArg(_))
+        ));
+        assert!(matches!(
+            listing.page_rows(10, 2),
+            Err(Error::InvalidArg(_))
+        ));
+        assert!(matches!(
+            listing.page_range(2, 0),
+            Err(Error::InvalidArg(_))
+        ));
+        assert!(matches!(
+            listing.page_range(0, 1),
+            Err(Error::InvalidArg(_))
+        ));
+        // (page - 1) * page_size overflows instead of wrapping around to a valid start
+        assert!(matches!(
+            listing.page_range(usize::MAX, 3),
+            Err(Error::InvalidArg(_))
+        ));
+        assert_eq!(listing.page_range(usize::MAX, 1).unwrap(), 0..5);
+
+        let empty = chunk_lister
+            .collect_chunks(
+                chunk_size.0 as u32,
+                ChunkFilter {
+                    empty_only: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(empty.page_range(20, 1).unwrap(), 0..0);
+        assert!(matches!(empty.page_range(20, 2), Err(Error::InvalidArg(_))));
+    }
+
+    #[test]
+    fn test_find_page() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk_size = Size::all_buckets().next().unwrap();
+        {
+            let engine = create_engine(dir.path());
+            for i in 0..12u32 {
+                // Every third chunk is empty, and chunk 11 is in a larger bucket
+                let len = if i == 11 {
+                    chunk_size.0 as usize + 1
+                } else if i % 3 == 0 {
+                    0
+                } else {
+                    1000
+                };
+                engine
+                    .put(&(i * 0x01010101).to_be_bytes(), &vec![i as u8; len], 1, 1)
+                    .unwrap();
+            }
+        }
+
+        let chunk_lister = open_lister(dir.path());
+        let target_size = chunk_size.0 as u32;
+        for filter in [
+            ChunkFilter::default(),
+            ChunkFilter {
+                empty_only: true,
+                ..Default::default()
+            },
+        ] {
+            let listing = chunk_lister.collect_chunks(target_size, filter).unwrap();
+            for (i, (chunk_id, _)) in listing.chunks_info.iter().enumerate() {
+                let page = chunk_lister
+                    .find_page(target_size, filter, chunk_id, 3)
+                    .unwrap();
+                assert_eq!(page, i / 3 + 1);
+                let rows = listing.page_rows(3, page).unwrap();
+                assert!(rows
+                    .iter()
+                    .any(|row| row.chunk_id == format_hex_chunk_id(chunk_id)));
+            }
+        }
+
+        let not_in_bucket = (11u32 * 0x01010101).to_be_bytes();
+        assert!(matches!(
+            chunk_lister.find_page(target_size, ChunkFilter::default(), &not_in_bucket, 3),
+            Err(Error::InvalidArg(_))
+        ));
+        let filtered_out = 0x01010101u32.to_be_bytes();
+        let filter = ChunkFilter {
+            empty_only: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            chunk_lister.find_page(target_size, filter, &filtered_out, 3),
+            Err(Error::InvalidArg(_))
+        ));
+        assert!(matches!(
+            chunk_lister.find_page(target_size, filter, &[0xff; 4], 3),
+            Err(Error::InvalidArg(_))
+        ));
+    }
+
+    #[test]
+    fn test_checksum_column() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = vec![0x5au8; 4096];
+        {
+            let engine = create_engine(dir.path());
+            engine.put(&1u32.to_be_bytes(), &data, 1, 1).unwrap();
+        }
+
+        let chunk_lister = open_lister(dir.path());
+        let chunk_size = Size::all_buckets().next().unwrap();
+        let listing = chunk_lister
+            .collect_chunks(chunk_size.0 as u32, ChunkFilter::default())
+            .unwrap();
+        let checksum = format_checksum(compute_checksum(&data));
+        assert_eq!(checksum.len(), 10);
+
+        let cells =
+            listing
+                .page_rows(10, 1)
+                .unwrap()
+                .pop()
+                .unwrap()
+                .cells(false, true, DEFAULT_PRECISION);
+        assert_eq!(cells.len(), 9);
+        assert_eq!(cells[7], checksum);
+        assert_eq!(cells[8], "No");
+        let cells =
+            listing
+                .page_rows(10, 1)
+                .unwrap()
+                .pop()
+                .unwrap()
+                .cells(false, false, DEFAULT_PRECISION);
+        assert_eq!(cells.len(), 8);
+        assert!(!cells.contains(&checksum));
+
+        // JSON rows carry the checksum without --show-checksum
+        let json = to_json_line(&listing.page_rows(10, 1).unwrap()[0]).unwrap();
+        assert!(
+            json.contains(&format!("\"checksum\":{}", compute_checksum(&data))),
+            "{}",
+            json
+        );
+    }
+
+    #[test]
+    fn test_empty_store() {
+        let (_dir, chunk_lister) = lister_with_chunks(0, 0);
+        let summary = chunk_lister.collect_summary().unwrap();
+        assert!(summary.empty);
+        assert!(summary
+            .buckets
+            .iter()
+            .all(|bucket| bucket.used_chunks == 0 && bucket.reserved_chunks == 0));
+        assert!(to_json(&summary).unwrap().contains("\"empty\": true"));
+    }
+
+    #[test]
+    fn test_reservation_audit() {
+        let (_dir, chunk_lister) = lister_with_chunks(4, 4096);
+        let chunk_size = Size::all_buckets().next().unwrap();
+        let audit = chunk_lister.reservation_audit(50.0).unwrap();
+        // Only the bucket holding the chunks has a group, its other slots are reserved
+        assert_eq!(audit.len(), 1);
+        let bucket = &audit[0];
+        assert_eq!(bucket.chunk_size, chunk_size.0);
+        assert_eq!(bucket.allocated_chunks, GroupState::TOTAL_BITS as u64);
+        assert_eq!(bucket.used_chunks, 4);
+        assert_eq!(bucket.reserved_chunks, bucket.allocated_chunks - 4);
+        assert!(bucket.over_threshold);
+
+        assert!(!chunk_lister.reservation_audit(100.0).unwrap()[0].over_threshold);
+        assert!(matches!(
+            chunk_lister.reservation_audit(101.0),
+            Err(Error::InvalidArg(_))
+        ));
+        assert!(matches!(
+            chunk_lister.reservation_audit(-1.0),
+            Err(Error::InvalidArg(_))
+        ));
+    }
+
+    #[test]
+    fn test_stale_positions() {
+        let dir = tempfile::tempdir().unwrap();
+        put_chunks(dir.path(), 4, 4096);
+        {
+            let engine = create_engine(dir.path());
+            // Drop a chunk meta but keep its slot taken, as if the chunk was never committed
+            let mut write_batch = RocksDB::new_write_batch();
+            write_batch.delete(MetaKey::chunk_meta_key(&2u32.to_be_bytes()));
+            engine.meta_store.write(write_batch, true).unwrap();
+        }
+
+        let chunk_size = Size::all_buckets().next().unwrap();
+        let stale_positions = |path: &std::path::Path| {
+            let summary = open_lister(path).collect_summary().unwrap();
+            summary
+                .buckets
+                .iter()
+                .find(|bucket| bucket.chunk_size == chunk_size.0)
+                .unwrap()
+                .stale_positions
+        };
+        assert_eq!(stale_positions(dir.path()), 1);
+
+        let chunk_writer = crate::ChunkWriter::new(
+            &meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadWrite),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            chunk_writer
+                .release_reservations(chunk_size.0 as u32)
+                .unwrap(),
+            1
+        );
+        drop(chunk_writer);
+        assert_eq!(stale_positions(dir.path()), 0);
+    }
+
+    #[test]
+    fn test_summary_consistency() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk_size = Size::all_buckets().next().unwrap();
+        let bucket = |summary: &Summary| {
+            let bucket = summary
+                .buckets
+                .iter()
+                .find(|bucket| bucket.chunk_size == chunk_size.0)
+                .unwrap();
+            (bucket.stale_positions, bucket.excess_chunks)
+        };
+        put_chunks(dir.path(), 4, 4096);
+        let summary = open_lister(dir.path()).collect_summary().unwrap();
+        assert!(summary.consistent);
+        assert_eq!(bucket(&summary), (0, 0));
+
+        // A second chunk meta on the slot of chunk 0, more chunk metas than taken slots
+        {
+            let engine = create_engine(dir.path());
+            let meta = engine
+                .get(&0u32.to_be_bytes())
+                .unwrap()
+                .unwrap()
+                .meta()
+                .clone();
+            engine
+                .meta_store
+                .add_chunk(&9u32.to_be_bytes(), &meta, true)
+                .unwrap();
+        }
+        let summary = open_lister(dir.path()).collect_summary().unwrap();
+        assert!(!summary.consistent);
+        assert_eq!(bucket(&summary), (0, 1));
+
+        // Only a scan of every chunk can tell
+        let id_range = IdRange::parse(Some("00000000"), Some("00000002")).unwrap();
+        let summary = open_lister(dir.path())
+            .with_id_range(id_range)
+            .collect_summary()
+            .unwrap();
+        assert!(summary.consistent);
+        assert_eq!(bucket(&summary), (0, 0));
+
+        // Without any chunk meta on it, the slot of chunk 1 stays taken
+        {
+            let engine = create_engine(dir.path());
+            let mut write_batch = RocksDB::new_write_batch();
+            write_batch.delete(MetaKey::chunk_meta_key(&9u32.to_be_bytes()));
+            write_batch.delete(MetaKey::chunk_meta_key(&1u32.to_be_bytes()));
+            engine.meta_store.write(write_batch, true).unwrap();
+        }
+        let summary = open_lister(dir.path()).collect_summary().unwrap();
+        assert!(!summary.consistent);
+        assert_eq!(bucket(&summary), (1, 0));
+    }
+
+    #[test]
+    fn test_table_color() {
+        let chunk_rows = || {
+            [(10.0, false), (60.0, true), (100.0, false)]
+                .into_iter()
+                .enumerate()
+                .map(|(i, (utilization, uncommitted))| ChunkRow {
+                    index: i + 1,
+                    chunk_id: format!("{:032x}", i),
+                    alloc_size: 1 << 20,
+                    actual_len: (utilization * (1 << 20) as f64 / 100.0) as u32,
+                    utilization,
+                    chain_ver: 1,
+                    chunk_ver: 1,
+                    checksum: 0,
+                    uncommitted,
+                })
+                .collect::<Vec<_>>()
+        };
+        let rows: Vec<Vec<String>> = chunk_rows()
+            .into_iter()
+            .map(|row| row.cells(false, false, DEFAULT_PRECISION))
+            .collect();
+
+        // Without color the table is byte-identical to the fixed-width layout from before --color
+        let never = render_plain_table(&rows, false, false, &|_, _, cell| cell);
+        let line = |c: &[&str]| {
+            format!(
+                "{:<8} {:<68} {:<15} {:<15} {:<8} {:<12} {:<12} {:<8}\n",
+                c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]
+            )
+        };
+        let mut expected = line(&[
+            "Index",
+            "Chunk ID (hex)",
+            "Alloc Size",
+            "Actual Len",
+            "Util %",
+            "Chain Ver",
+            "Chunk Ver",
+            "Uncommit",
+        ]);
+        expected.push_str(&format!("{}\n", "-".repeat(175)));
+        for row in &rows {
+            expected.push_str(&line(&row.iter().map(String::as_str).collect::<Vec<_>>()));
+        }
+        assert_eq!(never, expected);
+
+        // With color only the highlighted cells gain escape codes, around their padding
+        let colors: Vec<_> = chunk_rows().iter().map(|row| row.colors(false)).collect();
+        let colored = render_plain_table(
+            &rows,
+            false,
+            false,
+            &|row, column, cell| match colors[row][column] {
+                Some(color) => paint(&cell, color),
+                None => cell,
+            },
+        );
+        assert!(colored.contains(&paint("10.00   ", Color::Red)));
+        assert!(colored.contains(&paint("100.00  ", Color::Green)));
+        assert!(colored.contains(&paint("Yes     ", Color::Yellow)));
+        assert!(!colored.contains(&paint("60.00   ", Color::Red)));
+        let mut stripped = String::new();
+        let mut chars = colored.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|&c| c == 'm');
+            } else {
+                stripped.push(c);
+            }
+        }
+        assert_eq!(stripped, never);
+    }
+
+    #[test]
+    fn test_chain_utilization() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk_size = Size::all_buckets().next().unwrap().0 as usize;
+        {
+            let engine = create_engine(dir.path());
+            // Chain version 2 is well filled, chain version 1 holds mostly small leftovers
+            for i in 0..4u32 {
+                engine
+                    .put(&i.to_be_bytes(), &vec![i as u8; chunk_size * 3 / 4], 2, 1)
+                    .unwrap();
+            }
+            for i in 4..7u32 {
+                let len = if i == 4 { chunk_size } else { chunk_size / 8 };
+                engine
+                    .put(&i.to_be_bytes(), &vec![i as u8; len], 1, 1)
+                    .unwrap();
+            }
+        }
+
+        let chains = open_lister(dir.path()).chain_utilization().unwrap();
+        let chunk_size = chunk_size as u64;
+        assert_eq!(
+            chains,
+            [
+                ChainUtilization {
+                    chain_ver: 1,
+                    chunks: 3,
+                    total_len: chunk_size + chunk_size / 4,
+                    total_allocated: 3 * chunk_size,
+                    avg_utilization: 125.0 / 3.0,
+                    low_utilization_chunks: 2,
+                },
+                ChainUtilization {
+                    chain_ver: 2,
+                    chunks: 4,
+                    total_len: 3 * chunk_size,
+                    total_allocated: 4 * chunk_size,
+                    avg_utilization: 75.0,
+                    low_utilization_chunks: 0,
+                },
+            ]
+        );
+        assert!(to_json(&chains)
+            .unwrap()
+            .contains("\"low_utilization_chunks\": 2"));
+    }
+
+    #[test]
+    fn test_rebucket_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().join("meta"),
+                create: true,
+                ..Default::default()
+            },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        let [small, medium, large]: [Size; 3] = Size::all_buckets()
+            .take(3)
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        // (bucket, len): a chunk fits a smaller bucket when its length is at most half its slot
+        let chunks = [
+            (small, 10),
+            (medium, 10),
+            (medium, small.0 as u32),
+            (medium, small.0 as u32 + 1),
+            (large, medium.0 as u32 + 1),
+            (large, 0),
+        ];
+        for (i, &(chunk_size, len)) in chunks.iter().enumerate() {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(GroupId::new(chunk_size, 0, 0), i as u8),
+                len,
+                ..Default::default()
+            };
+            meta_store
+                .add_chunk(&(i as u32).to_be_bytes(), &chunk_meta, false)
+                .unwrap();
+        }
+
+        let buckets = ChunkLister::new(meta_store).rebucket_candidates().unwrap();
+        // The smallest bucket has no smaller one to move to
+        assert_eq!(
+            buckets,
+            [
+                RebucketCandidates {
+                    chunk_size: medium.0,
+                    target_size: small.0,
+                    chunks: 3,
+                    candidates: 2,
+                    reclaimable_bytes: 2 * small.0
+                },
+                RebucketCandidates {
+                    chunk_size: large.0,
+                    target_size: medium.0,
+                    chunks: 2,
+                    candidates: 1,
+                    reclaimable_bytes: medium.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rebucket() {
+        let dir = tempfile::tempdir().unwrap();
+        let [small, medium]: [Size; 2] = Size::all_buckets()
+            .take(2)
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let data: Vec<u8> = (0..small.0 + 1).map(|i| i as u8).collect();
+        {
+            let engine = create_engine(dir.path());
+            // Truncating keeps the slot, chain version 0 lets the truncate through
+            for (i, len) in [(0u32, 1000), (1, 4096), (2, data.len() as u32)] {
+                engine.put(&i.to_be_bytes(), &data, 0, 1).unwrap();
+                engine.truncate(&i.to_be_bytes(), len).unwrap();
+            }
+        }
+        let candidates = |path: &std::path::Path| {
+            let buckets = open_lister(path).rebucket_candidates().unwrap();
+            buckets
+                .iter()
+                .find(|bucket| bucket.chunk_size == medium.0)
+                .map_or(0, |bucket| bucket.candidates)
+        };
+        assert_eq!(candidates(dir.path()), 2);
+
+        let chunk_writer = crate::ChunkWriter::new(
+            &meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadWrite),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(matches!(
+            chunk_writer.rebucket(small.0 as u32),
+            Err(Error::InvalidArg(_))
+        ));
+        assert_eq!(chunk_writer.rebucket(medium.0 as u32).unwrap(), 2);
+        drop(chunk_writer);
+        assert_eq!(candidates(dir.path()), 0);
+
+        // The moved chunks read back the same from their new slots
+        let engine = create_engine(dir.path());
+        for (i, len, chunk_size) in [
+            (0u32, 1000, small),
+            (1, 4096, small),
+            (2, data.len(), medium),
+        ] {
+            let chunk = engine.get(&i.to_be_bytes()).unwrap().unwrap();
+            assert_eq!(chunk.meta().pos.chunk_size(), chunk_size);
+            let mut buffer = vec![0u8; len];
+            chunk.pread(&mut buffer, 0).unwrap();
+            assert_eq!(buffer, data[..len]);
+        }
+    }
+
+    #[test]
+    fn test_list_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().join("meta"),
+                create: true,
+                ..Default::default()
+            },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        let [small, _, large]: [Size; 3] = Size::all_buckets()
+            .take(3)
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let chunks = [(small, 10), (small, 20), (small, 30), (large, 100)];
+        for (i, &(chunk_size, len)) in chunks.iter().enumerate() {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(GroupId::new(chunk_size, 0, 0), i as u8),
+                len,
+                ..Default::default()
+            };
+            meta_store
+                .add_chunk(&(i as u32).to_be_bytes(), &chunk_meta, false)
+                .unwrap();
+        }
+        let lister = ChunkLister::new(meta_store);
+
+        // Empty buckets are skipped, the totals cover every chunk even when the rows are limited
+        let (buckets, total) = lister.collect_all_chunks(Some(2)).unwrap();
+        let shown: Vec<_> = buckets
+            .iter()
+            .map(|bucket| (bucket.chunk_size, bucket.totals, bucket.rows.len()))
+            .collect();
+        assert_eq!(
+            shown,
+            [
+                (
+                    small.0,
+                    ListingTotals {
+                        chunks: 3,
+                        total_len: 60,
+                        total_allocated: 3 * small.0
+                    },
+                    2
+                ),
+                (
+                    large.0,
+                    ListingTotals {
+                        chunks: 1,
+                        total_len: 100,
+                        total_allocated: large.0
+                    },
+                    1
+                ),
+            ]
+        );
+        assert_eq!(
+            total,
+            ListingTotals {
+                chunks: 4,
+                total_len: 160,
+                total_allocated: 3 * small.0 + large.0
+            }
+        );
+
+        let (buckets, _) = lister.collect_all_chunks(None).unwrap();
+        assert_eq!(
+            buckets[0]
+                .rows
+                .iter()
+                .map(|row| row.actual_len)
+                .collect::<Vec<_>>(),
+            [10, 20, 30]
+        );
+    }
+
+    #[test]
+    fn test_uncommitted_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().join("meta"),
+                create: true,
+                ..Default::default()
+            },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        let [small, medium]: [Size; 2] = Size::all_buckets()
+            .take(2)
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let chunks = [
+            (small, false),
+            (small, true),
+            (small, true),
+            (medium, false),
+            (medium, false),
+        ];
+        for (i, &(chunk_size, uncommitted)) in chunks.iter().enumerate() {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(GroupId::new(chunk_size, 0, 0), i as u8),
+                uncommitted,
+                ..Default::default()
+            };
+            meta_store
+                .add_chunk(&(i as u32).to_be_bytes(), &chunk_meta, false)
+                .unwrap();
+        }
+
+        let summary = ChunkLister::new(meta_store).collect_summary().unwrap();
+        let counts: Vec<_> = summary
+            .buckets
+            .iter()
+            .filter(|bucket| bucket.committed_chunks + bucket.uncommitted_chunks > 0)
+            .map(|bucket| {
+                (
+                    bucket.chunk_size,
+                    bucket.committed_chunks,
+                    bucket.uncommitted_chunks,
+                )
+            })
+            .collect();
+        assert_eq!(counts, [(small.0, 1, 2), (medium.0, 2, 0)]);
+        assert_eq!(
+            summary.checksum_algorithms,
+            BTreeMap::from([(UNKNOWN_CHECKSUM_ALGORITHM, 5)])
+        );
+    }
+
+    #[test]
+    fn test_allocator_efficiency() {
+        let dir = tempfile::tempdir().unwrap();
+        drop(create_engine(dir.path()));
+        let efficiency = open_lister(dir.path()).allocator_efficiency().unwrap();
+        assert_eq!(
+            efficiency,
+            AllocatorEfficiency {
+                buckets: vec![],
+                capacity_bytes: 0,
+                occupied_bytes: 0,
+                efficiency: 0.0
+            }
+        );
+
+        put_chunks(dir.path(), 4, 4096);
+        let chunk_lister = open_lister(dir.path());
+        let efficiency = chunk_lister.allocator_efficiency().unwrap();
+        assert_eq!(efficiency.buckets.len(), 1);
+        let bucket = &efficiency.buckets[0];
+        assert_eq!(
+            bucket.capacity,
+            (bucket.full_groups + bucket.active_groups) as u64
+                * ChunkAllocator::group_capacity() as u64
+        );
+        // Reservations are counted apart from the group bits, only the chunks occupy slots
+        assert_eq!(bucket.occupied, 4);
+        assert_eq!(
+            bucket.efficiency,
+            bucket.occupied as f64 * 100.0 / bucket.capacity as f64
+        );
+        // With a single bucket the overall efficiency is the bucket's
+        assert_eq!(
+            efficiency.capacity_bytes,
+            bucket.capacity * bucket.chunk_size
+        );
+        assert_eq!(efficiency.efficiency, bucket.efficiency);
+    }
+
+    #[test]
+    fn test_delimited_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let engine = create_engine(dir.path());
+            for i in 0..3u32 {
+                engine
+                    .put(
+                        &i.to_be_bytes(),
+                        &vec![i as u8; 1000 * (i as usize + 1)],
+                        1,
+                        1,
+                    )
+                    .unwrap();
+            }
+        }
+        let chunk_lister = open_lister(dir.path());
+        let chunk_size = Size::all_buckets().next().unwrap();
+        let rows = chunk_lister
+            .collect_chunks(chunk_size.0 as u32, ChunkFilter::default())
+            .unwrap()
+            .page_rows(10, 1)
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+
+        // TSV and CSV carry the same values, in the order of the header and the JSON fields
+        for row in &rows {
+            let tsv = row.delimited('\t');
+            let csv = row.delimited(',');
+            let fields: Vec<&str> = tsv.split('\t').collect();
+            assert_eq!(fields, csv.split(',').collect::<Vec<_>>());
+            assert_eq!(fields.len(), ChunkRow::DELIMITED_HEADER.len());
+            let json: serde_json::Value =
+                serde_json::from_str(&to_json_line(row).unwrap()).unwrap();
+            // Every JSON field apart from the schema version has a column
+            assert_eq!(
+                json.as_object().unwrap().keys().collect::<Vec<_>>().len(),
+                fields.len() + 1
+            );
+            for (name, field) in ChunkRow::DELIMITED_HEADER.iter().zip(&fields) {
+                match *name {
+                    "chunk_id" => assert_eq!(json[name].as_str().unwrap(), *field),
+                    "checksum" => {
+                        assert_eq!(format_checksum(json[name].as_u64().unwrap() as u32), *field)
+                    }
+                    "utilization" => {
+                        assert_eq!(format!("{:.2}", json[name].as_f64().unwrap()), *field)
+                    }
+                    _ => assert_eq!(json[name].to_string(), *field),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_summary_delimited_output_format() {
+        let (_dir, chunk_lister) = lister_with_chunks(0, 0);
+        for output_format in [OutputFormat::Csv, OutputFormat::Tsv] {
+            let error = chunk_lister.show_summary(output_format).err().unwrap();
+            assert_eq!(ExitStatus::from_error(&error), ExitStatus::UsageError);
+        }
+        assert!(chunk_lister.show_summary(OutputFormat::Table).is_ok());
+    }
+
+    #[test]
+    fn test_stats_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().join("meta"),
+                create: true,
+                ..Default::default()
+            },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        let chunk_size = Size::all_buckets().next().unwrap();
+        for (i, (len, uncommitted)) in [(10, false), (20, true), (30, true)]
+            .into_iter()
+            .enumerate()
+        {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(GroupId::new(chunk_size, 0, 0), i as u8),
+                len,
+                uncommitted,
+                ..Default::default()
+            };
+            meta_store
+                .add_chunk(&(i as u32).to_be_bytes(), &chunk_meta, false)
+                .unwrap();
+        }
+        let lister = ChunkLister::new(meta_store);
+
+        let filter = ChunkFilter {
+            uncommitted_only: true,
+            ..Default::default()
+        };
+        let totals = lister
+            .collect_chunks(chunk_size.0 as u32, filter)
+            .unwrap()
+            .totals();
+        assert_eq!(
+            totals,
+            ListingTotals {
+                chunks: 2,
+                total_len: 50,
+                total_allocated: 2 * chunk_size.0
+            }
+        );
+
+        // The totals alone, no table and no chunk
+        let out =
+            render_listing_totals(chunk_size.0 as u32, filter, totals, 0.0, DEFAULT_PRECISION);
+        assert!(
+            out.contains("Only uncommitted chunks\nTotal chunks: 2\n"),
+            "{}",
+            out
+        );
+        assert!(
+            !out.contains("Page") && !out.contains("Chunk ID"),
+            "{}",
+            out
+        );
+        for i in 0..3u32 {
+            assert!(
+                !out.contains(&format_hex_chunk_id(&i.to_be_bytes())),
+                "{}",
+                out
+            );
+        }
+    }
+
+    #[test]
+    fn test_raw_keys() {
+        use super::super::utils::parse_hex_chunk_id;
+
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let engine = create_engine(dir.path());
+            for i in 0..5u32 {
+                engine
+                    .put(&(i << 24 | 0xabcd).to_be_bytes(), &[i as u8; 100], 1, 1)
+                    .unwrap();
+            }
+        }
+        let chunk_lister = open_lister(dir.path());
+        let keys = chunk_lister.raw_keys(None).unwrap();
+        assert_eq!(keys.len(), 5);
+        for key in &keys {
+            // The printed key parses back to the printed chunk ID, and encodes from it
+            let raw = parse_hex_chunk_id(&key.key).unwrap();
+            assert_eq!(
+                format_hex_chunk_id(&MetaKey::parse_chunk_meta_key(&raw)),
+                key.chunk_id
+            );
+            let chunk_id = parse_hex_chunk_id(&key.chunk_id).unwrap();
+            assert_eq!(MetaKey::chunk_meta_key(&chunk_id).as_ref(), raw.as_slice());
+            assert!(key.value_len > 0);
+        }
+        // Inverted keys put the largest ID first
+        assert_eq!(
+            keys[0].chunk_id,
+            format_hex_chunk_id(&(4u32 << 24 | 0xabcd).to_be_bytes())
+        );
+
+        assert_eq!(chunk_lister.raw_keys(Some(2)).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_check_key_order() {
+        let key = |i: u8| MetaKey::chunk_meta_key(&[i; 16]).as_ref().to_vec();
+        let report = check_key_order([key(9), key(7), key(5)]);
+        assert_eq!(
+            report,
+            KeyOrderReport {
+                checked_keys: 3,
+                violations: vec![]
+            }
+        );
+        assert_eq!(report.exit_status(), ExitStatus::Ok);
+
+        // Keys from a corrupt store, a mock of what the iterator would return
+        let report = check_key_order([key(9), key(5), key(7), key(3), key(3)]);
+        assert_eq!(report.checked_keys, 5);
+        assert_eq!(
+            report.violations,
+            [
+                KeyOrderViolation {
+                    kind: KeyOrderViolationKind::OutOfOrder,
+                    previous_key: format_hex_chunk_id(&key(5)),
+                    key: format_hex_chunk_id(&key(7))
+                },
+                KeyOrderViolation {
+                    kind: KeyOrderViolationKind::Duplicate,
+                    previous_key: format_hex_chunk_id(&key(3)),
+                    key: format_hex_chunk_id(&key(3))
+                },
+            ]
+        );
+        assert_eq!(report.exit_status(), ExitStatus::Corruption);
+
+        // A real store is always ordered
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let engine = create_engine(dir.path());
+            for i in 0..8u32 {
+                engine
+                    .put(&(i << 24).to_be_bytes(), &[i as u8; 100], 1, 1)
+                    .unwrap();
+            }
+        }
+        let report = open_lister(dir.path())
+            .check_key_order(OutputFormat::Json)
+            .unwrap();
+        assert_eq!(
+            report,
+            KeyOrderReport {
+                checked_keys: 8,
+                violations: vec![]
+            }
+        );
+    }
+
+    #[test]
+    fn test_listing_precision() {
+        // 7 of 8 bytes, 87.5%
+        let totals = ListingTotals {
+            chunks: 1,
+            total_len: 7 * 1536,
+            total_allocated: 8 * 1536,
+        };
+        let out = render_listing_totals(8 * 1536, ChunkFilter::default(), totals, 12.25, 0);
+        assert!(out.contains("Size bucket: 12 KB (12288)\n"), "{}", out);
+        assert!(
+            out.contains("Total actual size: 10 KB (10752)\n"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("Average utilization: 88%\nUtilization stddev: 12%\n"),
+            "{}",
+            out
+        );
+        let out = render_listing_totals(8 * 1536, ChunkFilter::default(), totals, 12.25, 4);
+        assert!(
+            out.contains("Total actual size: 10.5000 KB (10752)\n"),
+            "{}",
+            out
+        );
+        assert!(out.contains("Average utilization: 87.5000%\n"), "{}", out);
+
+        let row = || ChunkRow {
+            index: 1,
+            chunk_id: String::new(),
+            alloc_size: 1536,
+            actual_len: 1000,
+            utilization: 65.10416,
+            chain_ver: 1,
+            chunk_ver: 1,
+            checksum: 0,
+            uncommitted: false,
+        };
+        let cells = row().cells(false, false, 0);
+        assert_eq!(cells[2..5], ["2 KB", "1000 B", "65"]);
+        let cells = row().cells(false, false, 3);
+        assert_eq!(cells[2..5], ["1.500 KB", "1000 B", "65.104"]);
+    }
+
+    #[test]
+    fn test_prefix_distribution() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let engine = create_engine(dir.path());
+            // A hotspot of 8 chunks under one prefix next to 4 prefixes holding a chunk each
+            for i in 0..8u64 {
+                engine
+                    .put(
+                        &(0xaaaa_aaaa_0000_0000 | i).to_be_bytes(),
+                        &[1u8; 100],
+                        1,
+                        1,
+                    )
+                    .unwrap();
+            }
+            for i in 1..5u64 {
+                engine
+                    .put(&(i << 32).to_be_bytes(), &[1u8; 100], 1, 1)
+                    .unwrap();
+            }
+        }
+
+        let distribution = open_lister(dir.path()).prefix_distribution(4, 2).unwrap();
+        assert_eq!(
+            (
+                distribution.chunks,
+                distribution.prefixes,
+                distribution.min,
+                distribution.max
+            ),
+            (12, 5, 1, 8)
+        );
+        assert!((distribution.mean - 2.4).abs() < 1e-9);
+        // The squared deviations are 5.6² and four times 1.4², 7.84 on average
+        assert!((distribution.stddev - 2.8).abs() < 1e-9);
+        assert_eq!(
+            distribution.hottest,
+            [
+                PrefixCount {
+                    prefix: "aaaaaaaa".to_string(),
+                    chunks: 8
+                },
+                PrefixCount {
+                    prefix: "00000001".to_string(),
+                    chunks: 1
+                },
+            ]
+        );
+
+        // A 1-byte prefix still singles out the hotspot
+        let distribution = open_lister(dir.path()).prefix_distribution(1, 1).unwrap();
+        assert_eq!((distribution.prefixes, distribution.max), (2, 8));
+        assert_eq!(distribution.hottest[0].prefix, "aa");
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk_size = Size::all_buckets().next().unwrap().0 as usize;
+        {
+            let engine = create_engine(dir.path());
+            // Half the chunks a quarter full and half full
+            for i in 0..10u32 {
+                let len = if i < 5 { chunk_size / 4 } else { chunk_size };
+                engine.put(&i.to_be_bytes(), &vec![1u8; len], 1, 1).unwrap();
+            }
+        }
+
+        let rows = open_lister(dir.path()).heatmap().unwrap();
+        assert_eq!(rows.len(), Size::all_buckets().count());
+        assert_eq!(
+            render_heatmap(&rows, false)
+                .lines()
+                .filter(|line| line.ends_with('|'))
+                .count(),
+            rows.len()
+        );
+        let row = &rows[0];
+        assert_eq!(
+            (row.chunk_size, row.chunks, row.avg_utilization),
+            (chunk_size as u64, 10, 62.5)
+        );
+        assert_eq!(
+            row.cells,
+            [[25u8; HEATMAP_CELLS / 2], [100u8; HEATMAP_CELLS / 2]].concat()
+        );
+        assert!(rows[1..]
+            .iter()
+            .all(|row| row.chunks == 0 && row.cells.is_empty()));
+
+        let out = render_heatmap(&rows, true);
+        assert!(out.is_ascii(), "{}", out);
+        assert!(
+            out.contains(&format!(
+                "|{}{}|",
+                ":".repeat(HEATMAP_CELLS / 2),
+                "@".repeat(HEATMAP_CELLS / 2)
+            )),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_id_range() {
+        let dir = tempfile::tempdir().unwrap();
+        put_chunks(dir.path(), 8, 4096);
+
+        let id_range = IdRange {
+            from: Some(2u32.to_be_bytes().to_vec()),
+            to: Some(5u32.to_be_bytes().to_vec()),
+        };
+        let chunk_lister = open_lister(dir.path()).with_id_range(id_range);
+        let chunk_size = Size::all_buckets().next().unwrap();
+        let listing = chunk_lister
+            .collect_chunks(chunk_size.0 as u32, ChunkFilter::default())
+            .unwrap();
+        let chunk_ids: Vec<_> = listing
+            .chunks_info
+            .iter()
+            .map(|(chunk_id, _)| chunk_id.to_vec())
+            .collect();
+        assert_eq!(chunk_ids, [2u32, 3, 4].map(|i| i.to_be_bytes().to_vec()));
+
+        let summary = chunk_lister.collect_summary().unwrap();
+        let bucket = summary
+            .buckets
+            .iter()
+            .find(|bucket| bucket.chunk_size == chunk_size.0)
+            .unwrap();
+        assert_eq!(bucket.committed_chunks, 3);
+        // The other chunks' slots are not stale, they were just left out of the scan
+        assert_eq!(bucket.stale_positions, 0);
+
+        // Open ended on either side
+        let chunk_lister = open_lister(dir.path()).with_id_range(IdRange {
+            from: Some(6u32.to_be_bytes().to_vec()),
+            to: None,
+        });
+        assert_eq!(
+            chunk_lister
+                .collect_chunks(chunk_size.0 as u32, ChunkFilter::default())
+                .unwrap()
+                .chunks_info
+                .len(),
+            2
+        );
+        let chunk_lister = open_lister(dir.path()).with_id_range(IdRange {
+            from: None,
+            to: Some(6u32.to_be_bytes().to_vec()),
+        });
+        assert_eq!(
+            chunk_lister
+                .collect_chunks(chunk_size.0 as u32, ChunkFilter::default())
+                .unwrap()
+                .chunks_info
+                .len(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_allocator_dump() {
+        let (_dir, chunk_lister) = lister_with_chunks(3, 4096);
+        let chunk_size = Size::all_buckets().next().unwrap();
+        let dump = chunk_lister.allocator_dump(chunk_size.0 as u32).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&to_json(&dump).unwrap()).unwrap();
+        assert_eq!(json["chunk_size"], chunk_size.0);
+        assert_eq!(json["full_groups"], serde_json::json!([]));
+        // The three chunks took the first slots of group 0
+        let active = json["active_groups"].as_array().unwrap();
+        assert_eq!(active.len(), 1, "{}", json);
+        assert_eq!(active[0]["index"], 0);
+        assert_eq!(active[0]["used"], 3);
+        let bitmap = active[0]["bitmap"].as_str().unwrap();
+        assert_eq!(bitmap.len(), 2 * GroupState::TOTAL_BITS / 8);
+        assert!(
+            bitmap.starts_with("0700") && bitmap[2..].bytes().all(|b| b == b'0'),
+            "{}",
+            bitmap
+        );
+        assert_eq!(json["reserved_chunks"], GroupState::TOTAL_BITS as u64 - 3);
+        assert_eq!(json["next_group"], 1);
+
+        assert!(matches!(
+            chunk_lister.allocator_dump(1000),
+            Err(Error::InvalidArg(_))
+        ));
+    }
+
+    #[test]
+    fn test_min_chunks() {
+        let (_dir, chunk_lister) = lister_with_chunks(4, 4096);
+        let summary = chunk_lister.collect_summary().unwrap();
+        let (shown, hidden) = chunk_lister.shown_buckets(&summary);
+        assert_eq!((shown.len(), hidden), (Size::all_buckets().count(), 0));
+
+        // Empty buckets are hidden, the summary itself keeps all of them
+        let chunk_lister = chunk_lister.with_min_chunks(1);
+        let (shown, hidden) = chunk_lister.shown_buckets(&summary);
+        assert_eq!(
+            shown
+                .iter()
+                .map(|bucket| bucket.used_chunks)
+                .collect::<Vec<_>>(),
+            [4]
+        );
+        assert_eq!(hidden, Size::all_buckets().count() - 1);
+        assert_eq!(summary.buckets.len(), Size::all_buckets().count());
+        assert!(chunk_lister
+            .with_min_chunks(5)
+            .shown_buckets(&summary)
+            .0
+            .is_empty());
+    }
+
+    #[test]
+    fn test_util_bar() {
+        let filled = |utilization: f64| util_bar(utilization).matches('#').count();
+        for (utilization, tenths) in [
+            (0.0, 0),
+            (4.9, 0),
+            (5.0, 1),
+            (37.5, 4),
+            (50.0, 5),
+            (94.9, 9),
+            (100.0, 10),
+            (150.0, 10),
+        ] {
+            assert_eq!(filled(utilization), tenths, "{}", utilization);
+            assert_eq!(util_bar(utilization).len(), UTIL_BAR_WIDTH + 2);
+        }
+        assert_eq!(util_bar(40.0), "[####------]");
+
+        // Bars widen the plain utilization column, the columns after it stay aligned
+        let rows: Vec<Vec<String>> = [12.5, 100.0]
+            .iter()
+            .map(|&utilization| {
+                [
+                    "1",
+                    "ab",
+                    "1 MB",
+                    "128 KB",
+                    &format!("{:.2} {}", utilization, util_bar(utilization)),
+                    "1",
+                    "1",
+                    "No",
+                ]
+                .map(String::from)
+                .to_vec()
+            })
+            .collect();
+        let table = render_plain_table(&rows, false, false, &|_, _, cell| cell);
+        let lines: Vec<&str> = table.lines().collect();
+        let column = lines[0].find("Chain Ver").unwrap();
+        assert!(
+            lines[2..]
+                .iter()
+                .all(|line| line[column..].starts_with("1 ")),
+            "{}",
+            table
+        );
+        assert!(lines[2].contains(" 12.50 [#---------]  1 "), "{}", table);
+        assert_eq!(lines[1].len(), 175 + "100.00 [##########]".len() - 8);
+    }
+
+    #[test]
+    fn test_chunk_meta_key_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        put_chunks(dir.path(), 3, 4096);
+
+        // Move the chunk metas under another key prefix, as a store with a customized key layout holds them
+        const PREFIX: u8 = 0x0a;
+        let config = |chunk_meta_key_prefix: u8| MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().join("meta"),
+                ..Default::default()
+            },
+            prefix_len: 4,
+            chunk_meta_key_prefix,
+        };
+        {
+            let meta_store = MetaStore::open(&config(MetaKey::CHUNK_META_KEY_PREFIX)).unwrap();
+            let mut write_batch = rocksdb::WriteBatch::default();
+            for i in 0..3u32 {
+                let chunk_id = i.to_be_bytes();
+                let value = meta_store.get_chunk_meta_raw(&chunk_id).unwrap().unwrap();
+                write_batch.delete(MetaKey::chunk_meta_key(&chunk_id));
+                write_batch.put(
+                    MetaKey::chunk_meta_key_with(PREFIX, &chunk_id),
+                    value.as_ref(),
+                );
+            }
+            meta_store.write(write_batch, true).unwrap();
+        }
+
+        let chunk_lister = ChunkLister::new(MetaStore::open(&config(PREFIX)).unwrap());
+        let summary = chunk_lister.collect_summary().unwrap();
+        assert!(!summary.empty);
+        let bucket = summary
+            .buckets
+            .iter()
+            .find(|bucket| bucket.committed_chunks > 0)
+            .unwrap();
+        assert_eq!(bucket.committed_chunks, 3);
+        let listing = chunk_lister
+            .collect_chunks(bucket.chunk_size as u32, ChunkFilter::default())
+            .unwrap();
+        let chunk_ids: Vec<&[u8]> = listing
+            .chunks_info
+            .iter()
+            .map(|(chunk_id, _)| chunk_id.as_ref())
+            .collect();
+        assert_eq!(
+            chunk_ids,
+            [0u32.to_be_bytes(), 1u32.to_be_bytes(), 2u32.to_be_bytes()]
+        );
+
+        // With the default prefix the same store looks empty
+        drop(chunk_lister);
+        assert!(
+            ChunkLister::new(MetaStore::open(&config(MetaKey::CHUNK_META_KEY_PREFIX)).unwrap())
+                .collect_summary()
+                .unwrap()
+                .empty
+        );
+    }
+
+    #[test]
+    fn test_first_and_last_n() {
+        let (_dir, chunk_lister) = lister_with_chunks(5, 4096);
+        let chunk_size = Size::all_buckets().next().unwrap();
+        let listing = chunk_lister
+            .collect_chunks(chunk_size.0 as u32, ChunkFilter::default())
+            .unwrap();
+        let shown = |slice| -> Vec<(usize, String)> {
+            listing
+                .slice_rows(slice)
+                .unwrap()
+                .into_iter()
+                .map(|row| (row.index, row.chunk_id))
+                .collect()
+        };
+        let expected = |ids: std::ops::Range<u32>| -> Vec<(usize, String)> {
+            ids.map(|i| (i as usize + 1, format_hex_chunk_id(&i.to_be_bytes())))
+                .collect()
+        };
+
+        // Rows keep their index in the whole listing, so the last chunks are numbered 4 and 5 of 5
+        assert_eq!(shown(ListingSlice::First(2)), expected(0..2));
+        assert_eq!(shown(ListingSlice::Last(2)), expected(3..5));
+        assert_eq!(
+            listing.slice_caption(ListingSlice::First(2), 2),
+            "First 2 of 5 chunks"
+        );
+        assert_eq!(
+            listing.slice_caption(ListingSlice::Last(2), 2),
+            "Last 2 of 5 chunks"
+        );
+        assert_eq!(
+            listing.slice_caption(
+                ListingSlice::Page {
+                    page_size: 2,
+                    page: 3
+                },
+                1
+            ),
+            "Page 3/3 (showing 1 chunks)"
+        );
+
+        // More than the listing holds is the whole listing rather than an error
+        assert_eq!(shown(ListingSlice::First(10)), expected(0..5));
+        assert_eq!(shown(ListingSlice::Last(10)), expected(0..5));
+    }
+
+    #[test]
+    fn test_zero_checksum_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().join("meta"),
+                create: true,
+                ..Default::default()
+            },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        let chunk_size = Size::all_buckets().next().unwrap();
+        // (len, checksum): only a chunk with data and a checksum of 0 counts
+        for (i, &(len, checksum)) in [(0, 0), (100, 0), (100, 7)].iter().enumerate() {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(GroupId::new(chunk_size, 0, 0), i as u8),
+                len,
+                checksum,
+                ..Default::default()
+            };
+            meta_store
+                .add_chunk(&(i as u32).to_be_bytes(), &chunk_meta, false)
+                .unwrap();
+        }
+
+        let summary = ChunkLister::new(meta_store).collect_summary().unwrap();
+        assert_eq!(summary.buckets[0].chunk_size, chunk_size.0);
+        assert_eq!(
+            summary
+                .buckets
+                .iter()
+                .map(|bucket| bucket.zero_checksum_chunks)
+                .collect::<Vec<_>>()[..2],
+            [1, 0]
+        );
+    }
+
+    #[test]
+    fn test_json_page() {
+        let (_dir, chunk_lister) = lister_with_chunks(5, 4096);
+        let chunk_size = Size::all_buckets().next().unwrap();
+        let listing = chunk_lister
+            .collect_chunks(chunk_size.0 as u32, ChunkFilter::default())
+            .unwrap();
+        let json = |slice| -> serde_json::Value {
+            serde_json::from_str(&to_json(&listing.json_page(slice).unwrap()).unwrap()).unwrap()
+        };
+
+        // 5 chunks in pages of 2, the middle page holds chunks 3 and 4
+        let page = json(ListingSlice::Page {
+            page_size: 2,
+            page: 2,
+        });
+        assert_eq!(page["schema_version"], JSON_SCHEMA_VERSION);
+        assert_eq!(page["page"], 2);
+        assert_eq!(page["total_pages"], 3);
+        assert_eq!(page["total_chunks"], 5);
+        let indexes: Vec<_> = page["chunks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|row| row["index"].as_u64().unwrap())
+            .collect();
+        assert_eq!(indexes, [3, 4]);
+        assert_eq!(
+            page["chunks"][0]["chunk_id"],
+            format_hex_chunk_id(&2u32.to_be_bytes())
+        );
+
+        let last = json(ListingSlice::Page {
+            page_size: 2,
+            page: 3,
+        });
+        assert_eq!(
+            (last["page"].clone(), last["total_pages"].clone()),
+            (3.into(), 3.into())
+        );
+        assert_eq!(last["chunks"].as_array().unwrap().len(), 1);
+
+        // --last-n has no page numbers, only the total
+        let tail = json(ListingSlice::Last(2));
+        assert!(tail.get("page").is_none() && tail.get("total_pages").is_none());
+        assert_eq!(tail["total_chunks"], 5);
+        assert_eq!(tail["chunks"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_summary_total() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let engine = create_engine(dir.path());
+            // Chunks in two buckets, the larger ones do not fit the smallest bucket
+            for i in 0..5u32 {
+                let len = if i < 3 { 4096 } else { 100 << 10 };
+                engine
+                    .put(&i.to_be_bytes(), &vec![i as u8; len], 1, 1)
+                    .unwrap();
+            }
+        }
+
+        let summary = open_lister(dir.path()).collect_summary().unwrap();
+        assert_eq!(
+            summary
+                .buckets
+                .iter()
+                .filter(|bucket| bucket.used_chunks > 0)
+                .count(),
+            2
+        );
+        let mut expected = SummaryTotal::default();
+        for bucket in &summary.buckets {
+            expected.used_chunks += bucket.used_chunks;
+            expected.reserved_chunks += bucket.reserved_chunks;
+            expected.full_groups += bucket.full_groups;
+            expected.active_groups += bucket.active_groups;
+            expected.used_bytes += bucket.used_chunks * bucket.chunk_size;
+        }
+        assert_eq!(summary.total, expected);
+        assert_eq!(summary.total.used_chunks, 5);
+        assert!(summary.total.active_groups >= 2);
+
+        let json: serde_json::Value = serde_json::from_str(&to_json(&summary).unwrap()).unwrap();
+        assert_eq!(json["total"]["used_chunks"], 5);
+        assert_eq!(json["total"]["used_bytes"], expected.used_bytes);
+    }
+}
@@ -1,52 +1,384 @@
 pub mod args;
 pub mod utils;
 pub mod content_reader;
+pub mod exit_status;
 pub mod chunk_lister;
+pub mod chunk_writer;
+pub mod terminal;
+pub mod rocksdb_stats;
+pub mod table;
+pub mod verifier;
+pub mod migrator;
+pub mod store_diff;
+pub mod watch;
+pub mod percentiles;
+#[cfg(feature = "parquet")]
+pub mod parquet_export;
 
 use chunk_engine::*;
-use clap::Parser;
+use std::{process::ExitCode, time::{Duration, Instant}};
 
-pub use args::Args;
+pub use args::{Args, OpenMode, OutputFormat, TableStyle};
 pub use utils::*;
 pub use content_reader::ChunkContentReader;
-pub use chunk_lister::ChunkLister;
+pub use chunk_lister::{ChunkFilter, ChunkLister, ListingSlice};
+pub use chunk_writer::ChunkWriter;
+pub use exit_status::ExitStatus;
+pub use verifier::ChunkVerifier;
+pub use migrator::ChunkMigrator;
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+fn main() -> ExitCode {
+    // clap exits with 2 on usage errors, which is reserved for mismatches here
+    let args = match Args::try_parse_with_config() {
+        Ok(args) => args,
+        Err(e) => {
+            let _ = e.print();
+            let status = if e.use_stderr() { ExitStatus::UsageError } else { ExitStatus::Ok };
+            return status.into();
+        }
+    };
+    
+    let timing = args.timing.then(|| {
+        IoStats::enable();
+        (Instant::now(), IoStats::snapshot())
+    });
+    let status = match run(args) {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitStatus::from_error(&e)
+        }
+    };
+    if let Some((begin, before)) = timing {
+        eprintln!("{}", format_timing(begin.elapsed(), &IoStats::snapshot().since(&before)));
+    }
+    status.into()
+}
+
+/// The `--timing` footer, keys point at the metadata and chunk reads at the data files
+fn format_timing(elapsed: Duration, stats: &IoStats) -> String {
+    format!("Timing: {:.2?} wall, {} keys scanned, {} key lookups, {} chunk reads ({})",
+        elapsed, stats.keys_scanned, stats.key_lookups, stats.chunk_reads, format_size(stats.bytes_read))
+}
 
+fn run(args: Args) -> Result<ExitStatus> {
+    if let Some(shell) = args.generate_completions {
+        Args::write_completions(shell, &mut std::io::stdout());
+        return Ok(ExitStatus::Ok);
+    }
+    
     let meta_config = MetaStoreConfig {
-        rocksdb: RocksDBConfig {
-            path: args.path.clone(),
-            create: false,
-            read_only: true,
-        },
-        prefix_len: 4,
+        rocksdb: args.rocksdb_config()?,
+        prefix_len: PREFIX_LEN,
+        chunk_meta_key_prefix: args.chunk_meta_prefix,
     };
-    let meta_store = MetaStore::open(&meta_config)?;
+    let open_timeout = args.open_timeout.map(Duration::from_secs);
+    let id_range = IdRange::parse(args.id_from.as_deref(), args.id_to.as_deref())?;
+
+    // Dev mode: synthesize chunks, this needs the engine opened read-write
+    if let Some(count) = args.write_random {
+        let size = parse_size_string(args.size.as_deref().unwrap_or_default())?;
+        let chunk_writer = ChunkWriter::new(&meta_config, args.data_dir.as_deref(), open_timeout)?;
+        chunk_writer.write_random(count, size)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if let Some(size_str) = &args.defragment {
+        let target_size = parse_size_string(size_str)?;
+        if args.dry_run {
+            ChunkLister::new(open_meta_store(&meta_config, open_timeout)?).show_defrag_plan(target_size)?;
+        } else {
+            ChunkWriter::new(&meta_config, args.data_dir.as_deref(), open_timeout)?.defragment(target_size)?;
+        }
+        return Ok(ExitStatus::Ok);
+    }
+
+    if let Some(size_str) = &args.release_reservations {
+        let target_size = parse_size_string(size_str)?;
+        if args.dry_run {
+            ChunkLister::new(open_meta_store(&meta_config, open_timeout)?).show_release_plan(target_size)?;
+        } else if args.force {
+            ChunkWriter::new(&meta_config, args.data_dir.as_deref(), open_timeout)?.release_reservations(target_size)?;
+        } else {
+            return Err(Error::InvalidArg("--release-reservations writes to the store, pass --force, or --dry-run to only list the positions".into()));
+        }
+        return Ok(ExitStatus::Ok);
+    }
+
+    if let Some(manifest) = &args.delete_from_manifest {
+        let chunk_ids = verifier::read_corrupt_manifest(manifest)?;
+        if args.dry_run {
+            ChunkLister::new(open_meta_store(&meta_config, open_timeout)?).show_delete_plan(&chunk_ids)?;
+        } else if args.force {
+            ChunkWriter::new(&meta_config, args.data_dir.as_deref(), open_timeout)?.delete_chunks(&chunk_ids)?;
+        } else {
+            return Err(Error::InvalidArg("--delete-from-manifest writes to the store, pass --force, or --dry-run to only list the chunks".into()));
+        }
+        return Ok(ExitStatus::Ok);
+    }
+
+    if let Some(size_str) = &args.rebucket {
+        let target_size = parse_size_string(size_str)?;
+        if args.dry_run {
+            ChunkLister::new(open_meta_store(&meta_config, open_timeout)?).show_rebucket_plan(target_size)?;
+        } else {
+            ChunkWriter::new(&meta_config, args.data_dir.as_deref(), open_timeout)?.rebucket(target_size)?;
+        }
+        return Ok(ExitStatus::Ok);
+    }
+
+    if let Some(dest) = &args.migrate_to {
+        let id_prefix = parse_hex_chunk_id(args.id_prefix.as_deref().unwrap_or_default())?;
+        let migrator = ChunkMigrator::new(&meta_config, args.data_dir.as_deref(), dest, args.dry_run, open_timeout)?;
+        let report = migrator.migrate(&id_prefix)?;
+        migrator.display_report(&report);
+        return Ok(report.exit_status());
+    }
+
+    if let Some(other) = &args.diff_store {
+        let other_config = MetaStoreConfig {
+            rocksdb: RocksDBConfig { path: other.clone(), mode: DbOpenMode::ReadOnly, ..meta_config.rocksdb.clone() },
+            ..meta_config.clone()
+        };
+        let diff = store_diff::diff_stores(&open_meta_store(&meta_config, open_timeout)?, &open_meta_store(&other_config, open_timeout)?)?;
+        store_diff::display_store_diff(&diff, &args.store_path().display().to_string(), &other.display().to_string());
+        return Ok(diff.exit_status());
+    }
+
+    if let Some(chunk_ids) = &args.diff_chunks {
+        let content_reader = ChunkContentReader::new(&meta_config, args.data_dir.as_deref(), args.meta_cache_size, open_timeout)?.with_mmap(args.mmap).with_id_len(args.id_len).with_committed_only(args.committed_only_reads && !args.force);
+        let identical = content_reader.compare_chunks(&chunk_ids[0], &chunk_ids[1], args.max_diffs)?;
+        report_meta_cache(&content_reader, args.verbose);
+        if !identical {
+            return Ok(ExitStatus::Mismatch);
+        }
+        return Ok(ExitStatus::Ok);
+    }
+
+    if let Some(ids_path) = &args.lookup_chunks {
+        let content_reader = ChunkContentReader::new(&meta_config, args.data_dir.as_deref(), args.meta_cache_size, open_timeout)?.with_quiet(args.quiet);
+        content_reader.show_lookups(ids_path, args.prebuild_index)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if let Some(chunk_id_hex) = &args.dump_meta {
+        let content_reader = ChunkContentReader::new(&meta_config, args.data_dir.as_deref(), args.meta_cache_size, open_timeout)?.with_id_len(args.id_len);
+        content_reader.dump_meta(chunk_id_hex)?;
+        return Ok(ExitStatus::Ok);
+    }
 
     // Check if user wants to read a specific chunk
-    if let Some(chunk_id_hex) = args.read_chunk {
-        let content_reader = ChunkContentReader::new(&meta_config.rocksdb.path)?;
+    if let Some(chunk_id_hex) = &args.read_chunk {
+        if let Some(warning) = args.check_content_output(terminal::is_stdout_tty())? {
+            eprintln!("Warning: {}", warning);
+        }
+        let content_reader = ChunkContentReader::new(&meta_config, args.data_dir.as_deref(), args.meta_cache_size, open_timeout)?.with_mmap(args.mmap).with_id_len(args.id_len).with_committed_only(args.committed_only_reads && !args.force).with_at_version(args.at_version).with_gzip(args.gzip).with_mkdir_parents(args.mkdir_parents).with_quiet(args.quiet);
+        if let Some(file_path) = &args.compare_file {
+            let matches = content_reader.compare_with_file(chunk_id_hex, file_path)?;
+            report_meta_cache(&content_reader, args.verbose);
+            if !matches {
+                return Ok(ExitStatus::Mismatch);
+            }
+            return Ok(ExitStatus::Ok);
+        }
         content_reader.read_chunk_content(
-            &chunk_id_hex,
-            &args.content_format,
+            chunk_id_hex,
+            args.content_format,
             &args.output_file,
             args.show_preview,
+            args.content_stats,
         )?;
-        return Ok(());
+        report_meta_cache(&content_reader, args.verbose);
+        return Ok(ExitStatus::Ok);
+    }
+
+    if args.verify {
+        let algo = ChunkChecksum::from(args.checksum_algo);
+        let verifier = ChunkVerifier::new(&meta_config, args.data_dir.as_deref(), open_timeout)?
+            .with_id_range(id_range.clone());
+        let report = match &args.checkpoint {
+            Some(checkpoint) => {
+                // Stop on Ctrl-C with the progress saved instead of being killed
+                terminal::install_interrupt_handler();
+                verifier.verify_checksums(algo, args.threads, Some(checkpoint), &terminal::interrupted)?
+            }
+            None => verifier.verify_checksums(algo, args.threads, None, &|| false)?,
+        };
+        let Some(report) = report else {
+            println!("Interrupted, progress saved to {}, rerun with the same options to resume", args.checkpoint.as_ref().unwrap().display());
+            return Ok(ExitStatus::Interrupted);
+        };
+        verifier.display_report(algo, &report);
+        if let Some(manifest) = &args.corrupt_manifest {
+            report.write_corrupt_manifest(manifest, args.gzip, args.mkdir_parents)?;
+            println!("Wrote {} corrupt chunks to {}", report.corrupt.len(), manifest);
+        }
+        return Ok(report.exit_status());
+    }
+
+    if args.verify_lengths {
+        let verifier = ChunkVerifier::new(&meta_config, args.data_dir.as_deref(), open_timeout)?
+            .with_id_range(id_range.clone());
+        let report = verifier.verify_lengths()?;
+        verifier.display_length_report(&report);
+        return Ok(report.exit_status());
+    }
+
+    if args.zero_checksum {
+        let verifier = ChunkVerifier::new(&meta_config, args.data_dir.as_deref(), open_timeout)?
+            .with_id_range(id_range.clone());
+        let report = verifier.zero_checksum_audit(args.recompute)?;
+        verifier.display_zero_checksum_report(&report, args.show_chunks, args.output_format)?;
+        return Ok(report.exit_status());
+    }
+
+    if args.follow {
+        watch::follow_chunks(&meta_config, args.output_format, Duration::from_secs(args.interval), open_timeout)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if args.watch {
+        watch::watch_summary(&meta_config, args.output_format, Duration::from_secs(args.interval), args.color.enabled(), open_timeout)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    let meta_store = open_meta_store(&meta_config, open_timeout)?;
+    if args.rocksdb_stats || !args.rocksdb_property.is_empty() {
+        rocksdb_stats::show_rocksdb_stats(&meta_store, &args.rocksdb_property, args.output_format)?;
+        return Ok(ExitStatus::Ok);
+    }
+    
+    if let Some(path) = &args.export_parquet {
+        #[cfg(feature = "parquet")]
+        {
+            let chunks = parquet_export::export_parquet(&meta_store, path, &id_range)?;
+            println!("Exported {} chunks to {}", chunks, path.display());
+            return Ok(ExitStatus::Ok);
+        }
+        #[cfg(not(feature = "parquet"))]
+        return Err(Error::InvalidArg(format!("Cannot export {}, chunk_viewer was built without the parquet feature (build with --features parquet)", path.display())));
+    }
+    
+    let chunk_lister = ChunkLister::new(meta_store).with_color(args.color.enabled()).with_precision(args.precision).with_id_range(id_range).with_min_chunks(args.min_chunks).with_util_bars(args.util_bars).with_quiet(args.quiet);
+
+    if args.check_key_order {
+        return Ok(chunk_lister.check_key_order(args.output_format)?.exit_status());
+    }
+
+    if args.prefix_distribution {
+        chunk_lister.show_prefix_distribution(meta_config.prefix_len, args.top, args.output_format)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if args.raw_keys {
+        chunk_lister.show_raw_keys(args.limit, args.output_format)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if let Some(size_str) = &args.bucket_stats {
+        chunk_lister.show_bucket_stats(parse_size_string(size_str)?, args.output_format)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if let Some(size_str) = &args.length_percentiles {
+        let target_size = size_str.as_deref().map(parse_size_string).transpose()?;
+        chunk_lister.show_length_percentiles(target_size, args.exact, args.output_format)?;
+        return Ok(ExitStatus::Ok);
     }
 
-    let chunk_lister = ChunkLister::new(meta_store);
+    if args.reservation_audit {
+        chunk_lister.show_reservation_audit(args.reserved_threshold, args.output_format)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if args.allocator_efficiency {
+        chunk_lister.show_allocator_efficiency(args.output_format)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if args.chain_utilization {
+        chunk_lister.show_chain_utilization(args.output_format)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if args.heatmap {
+        chunk_lister.show_heatmap(args.ascii, args.output_format)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if args.rebucket_candidates {
+        chunk_lister.show_rebucket_candidates(args.output_format)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if let Some(size_str) = &args.list_groups {
+        chunk_lister.list_groups(parse_size_string(size_str)?, args.output_format)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if let Some(size_str) = &args.dump_allocator {
+        chunk_lister.show_allocator_dump(parse_size_string(size_str)?)?;
+        return Ok(ExitStatus::Ok);
+    }
+
+    if args.list_all {
+        chunk_lister.list_all_chunks(args.limit_per_bucket, args.short_ids, args.show_checksum, args.table_style, args.output_format)?;
+        return Ok(ExitStatus::Ok);
+    }
 
     // Check if user wants detailed listing for a specific size
     if let Some(size_str) = args.list_size {
-        let target_size = parse_size_string(&size_str)?;
-        chunk_lister.list_chunks_detailed(target_size, args.page_size, args.page, args.short_ids)?;
-        return Ok(());
+        let requested_size = parse_size_string(&size_str)?;
+        let target_size = if args.nearest_bucket {
+            let bucket = nearest_bucket(requested_size)?;
+            if bucket != requested_size {
+                eprintln!("{} is not a size bucket, using the next larger bucket {}", format_size(requested_size as u64), format_size(bucket.0));
+            }
+            bucket.0 as u32
+        } else {
+            bucket_size(requested_size)?.0 as u32
+        };
+        let filter = ChunkFilter { group: args.group, empty_only: args.empty_only, full_only: args.full_only, uncommitted_only: args.uncommitted_only };
+        if args.stats_only {
+            chunk_lister.show_listing_stats(target_size, filter, args.output_format)?;
+            return Ok(ExitStatus::Ok);
+        }
+        let page = match &args.find_page {
+            Some(chunk_id_hex) => {
+                let chunk_id = parse_hex_chunk_id(chunk_id_hex)?;
+                check_chunk_id_len(&chunk_id, args.id_len)?;
+                let page = chunk_lister.find_page(target_size, filter, &chunk_id, args.page_size)?;
+                eprintln!("Chunk {} is on page {}", chunk_id_hex, page);
+                page
+            }
+            None => args.page,
+        };
+        if args.interactive && args.output_format == OutputFormat::Table && terminal::is_stdout_tty() && terminal::is_stdin_tty() {
+            chunk_lister.browse_chunks_interactive(target_size, args.page_size, page, args.short_ids, args.show_checksum, args.table_style, filter)?;
+        } else {
+            if args.interactive && args.output_format != OutputFormat::Table {
+                eprintln!("Interactive mode only shows tables, falling back to --page output");
+            } else if args.interactive {
+                eprintln!("Interactive mode requires a terminal, falling back to --page output");
+            }
+            let slice = match (args.first_n, args.last_n) {
+                (Some(n), _) => ListingSlice::First(n),
+                (_, Some(n)) => ListingSlice::Last(n),
+                _ => ListingSlice::Page { page_size: args.page_size, page },
+            };
+            chunk_lister.list_chunks_detailed(target_size, slice, args.short_ids, args.show_checksum, args.table_style, filter, args.output_format)?;
+        }
+        return Ok(ExitStatus::Ok);
     }
 
     // Default: show summary
-    chunk_lister.show_summary()?;
+    Ok(chunk_lister.show_summary(args.output_format)?.exit_status())
+}
 
-    Ok(())
-}
\ No newline at end of file
+/// Under --verbose, print how many chunk meta lookups the meta cache answered
+fn report_meta_cache(content_reader: &ChunkContentReader, verbose: bool) {
+    if verbose {
+        let stats = content_reader.meta_cache_stats();
+        eprintln!("Meta cache: {} hits, {} misses, {} entries", stats.hits, stats.misses, stats.entries);
+    }
+}
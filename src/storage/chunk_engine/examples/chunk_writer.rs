@@ -0,0 +1,130 @@
+use chunk_engine::*;
+use derse::Deserialize;
+use rand::RngCore;
+use std::{path::Path, sync::Arc, time::{Duration, Instant}};
+
+use super::utils::{bucket_size, engine_config, format_size, open_store_with_timeout};
+
+pub struct ChunkWriter {
+    meta_store: Arc<MetaStore>,
+    engine: Engine,
+}
+
+impl ChunkWriter {
+    /// Open the engine of the meta store `meta_config` describes in read-write mode
+    pub fn new(meta_config: &MetaStoreConfig, data_dir: Option<&Path>, open_timeout: Option<Duration>) -> Result<Self> {
+        let meta_config = MetaStoreConfig {
+            rocksdb: RocksDBConfig { mode: DbOpenMode::ReadWrite, ..meta_config.rocksdb.clone() },
+            ..meta_config.clone()
+        };
+        let engine_config = engine_config(&meta_config, data_dir, true)?;
+        let (meta_store, engine) = open_store_with_timeout(engine_config, open_timeout)?;
+
+        Ok(Self { meta_store, engine })
+    }
+
+    /// Write `count` chunks of `size` bytes filled with random data under random chunk IDs
+    pub fn write_random(&self, count: usize, size: u32) -> Result<()> {
+        if size == 0 {
+            return Err(Error::InvalidArg("Chunk size must be greater than zero".to_string()));
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut data = vec![0u8; size as usize];
+        let mut chunk_id = [0u8; 16];
+
+        println!("Writing {} random chunks of {} ({} bytes)...", count, format_size(size as u64), size);
+        let begin = Instant::now();
+        for _ in 0..count {
+            rng.fill_bytes(&mut chunk_id);
+            rng.fill_bytes(&mut data);
+            self.engine.put(&chunk_id, &data, 1, 1)?;
+        }
+        let elapsed = begin.elapsed();
+
+        let total_bytes = count as u64 * size as u64;
+        let throughput = total_bytes as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        println!("Wrote {} chunks ({}) in {:.2?} ({}/s)", count, format_size(total_bytes), elapsed, format_size(throughput as u64));
+
+        let used_size = self.engine.used_size();
+        println!("Allocated: {}, Reserved: {}", format_size(used_size.allocated_size.0), format_size(used_size.reserved_size.0));
+
+        Ok(())
+    }
+
+    /// Relocate chunks out of sparse groups of one size bucket and free the emptied groups
+    pub fn defragment(&self, target_size: u32) -> Result<()> {
+        let chunk_size = bucket_size(target_size)?;
+
+        println!("Defragmenting size bucket {} ({} bytes)...", format_size(chunk_size.0), chunk_size.0);
+        let begin = Instant::now();
+        let stats = self.engine.defragment(chunk_size)?;
+
+        println!("Moved {} chunks, freed {} groups in {:.2?}", stats.moved_chunks, stats.freed_groups, begin.elapsed());
+
+        Ok(())
+    }
+
+    /// Move every chunk of one size bucket whose length fits in the next smaller bucket down to it, returning how many were moved
+    pub fn rebucket(&self, target_size: u32) -> Result<usize> {
+        let chunk_size = bucket_size(target_size)?;
+        let smaller = chunk_size.prev_bucket()
+            .ok_or_else(|| Error::InvalidArg(format!("{} is the smallest size bucket, there is no smaller one to move chunks to", format_size(chunk_size.0))))?;
+
+        // Collect the candidates first, moving a chunk rewrites its meta under the iterator
+        let mut chunk_ids = Vec::new();
+        let mut it = self.meta_store.iterator();
+        let mark = self.meta_store.chunk_meta_key_prefix();
+        let end_key = MetaKey::chunk_meta_key_with(mark, &[]);
+        it.seek(&end_key)?;
+        if it.key() == Some(end_key.as_ref()) {
+            it.next(); // [begin, end)
+        }
+        while let Some(key) = it.key().filter(|key| key[0] == mark) {
+            let chunk_meta = ChunkMeta::deserialize(it.value().unwrap()).map_err(Error::SerializationError)?;
+            if chunk_meta.pos.chunk_size() == chunk_size && chunk_meta.len as u64 <= smaller.0 {
+                chunk_ids.push(MetaKey::parse_chunk_meta_key(key));
+            }
+            it.next();
+        }
+        drop(it);
+
+        println!("Moving {} chunks from size bucket {} to {}...", chunk_ids.len(), format_size(chunk_size.0), format_size(smaller.0));
+        let begin = Instant::now();
+        for chunk_id in &chunk_ids {
+            self.engine.rebucket(chunk_id, smaller)?;
+        }
+        let reclaimed = chunk_ids.len() as u64 * (chunk_size.0 - smaller.0);
+        println!("Moved {} chunks, reclaimed {} in {:.2?}", chunk_ids.len(), format_size(reclaimed), begin.elapsed());
+
+        Ok(chunk_ids.len())
+    }
+
+    /// Delete the listed chunks in a single RocksDB write batch, so an interruption never leaves only some of them
+    /// deleted. Chunks that are already gone are skipped, returns how many were deleted.
+    pub fn delete_chunks(&self, chunk_ids: &[Vec<u8>]) -> Result<u64> {
+        println!("Deleting {} chunks...", chunk_ids.len());
+        let chunk_ids: Vec<Bytes> = chunk_ids.iter().map(|chunk_id| Bytes::from(chunk_id.as_slice())).collect();
+        let deleted = self.engine.remove_chunks(&chunk_ids)?;
+        println!("Deleted {} chunks, {} already absent", deleted, chunk_ids.len() as u64 - deleted);
+        Ok(deleted)
+    }
+
+    /// Release the positions of one size bucket that are taken but held by no chunk meta, returning how many were released
+    pub fn release_reservations(&self, target_size: u32) -> Result<usize> {
+        let chunk_size = bucket_size(target_size)?;
+
+        println!("Releasing stale positions of size bucket {} ({} bytes)...", format_size(chunk_size.0), chunk_size.0);
+        let reserved_before = self.engine.used_size().reserved_size;
+        let stale = self.engine.stale_positions(chunk_size)?;
+        for &pos in &stale {
+            self.engine.release_position(pos)?;
+        }
+        let reserved_after = self.engine.used_size().reserved_size;
+
+        println!("Released {} positions ({}), reserved space {} -> {}",
+            stale.len(), format_size(stale.len() as u64 * chunk_size.0), format_size(reserved_before.0), format_size(reserved_after.0));
+
+        Ok(stale.len())
+    }
+}
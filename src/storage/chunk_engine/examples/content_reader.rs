@@ -1,130 +1,510 @@
 use chunk_engine::*;
+use derse::Deserialize;
 use std::{
-    fs::File,
     io::Write,
-    path::PathBuf,
+    ops::Deref,
+    path::Path,
+    sync::{Arc, OnceLock},
+    time::Duration,
 };
 
-use super::utils::{parse_hex_chunk_id, format_size, format_hex_output};
+use super::args::ContentFormat;
+use super::utils::{engine_config, open_meta_store, open_with_timeout, format_hex_chunk_id, parse_hex_chunk_id, format_checksum, format_size, format_hex_output, format_hex_output_at, write_hex_output, format_byte_array, ArrayStyle, content_stats, first_difference, differing_ranges, format_timestamp, format_hex_side_by_side, check_chunk_id_len, OutputWriter};
+
+/// Most candidates listed when a chunk ID prefix is ambiguous
+const MAX_CANDIDATES: usize = 10;
 
 pub struct ChunkContentReader {
-    meta_store: MetaStore,
-    engine: Engine,
+    meta_store: Arc<MetaStore>,
+    /// Opened on the first read of chunk data, looking up metadata never needs the data files
+    engine: OnceLock<Engine>,
+    /// The config the meta store was opened from, shared with the engine so both agree on paths and prefix length
+    engine_config: EngineConfig,
+    open_timeout: Option<Duration>,
+    meta_cache: MetaCache,
+    /// Map chunk data instead of reading it into a buffer
+    mmap: bool,
+    /// Compress `--output-file` with gzip
+    gzip: bool,
+    /// Create missing parent directories of `--output-file`
+    mkdir_parents: bool,
+    /// Print only the content or the answer, see `--quiet`
+    quiet: bool,
+    /// Expected chunk ID length in bytes, see `check_chunk_id_len`
+    id_len: Option<usize>,
+    /// Refuse to read chunks whose last write was never committed
+    committed_only: bool,
+    /// Read the version of a chunk at or below this chunk version instead of the latest
+    at_version: Option<u32>,
 }
 
-impl ChunkContentReader {
-    pub fn new(rocksdb_path: &PathBuf) -> Result<Self> {
-        let meta_config = MetaStoreConfig {
-            rocksdb: RocksDBConfig {
-                path: rocksdb_path.clone(),
-                create: false,
-                read_only: true,
-            },
-            prefix_len: 4,
-        };
-        let meta_store = MetaStore::open(&meta_config)?;
+/// A range of a chunk's data with the metadata it was read under, see `ChunkContentReader::read`
+pub struct ChunkReadResult {
+    pub chunk_id: Vec<u8>,
+    pub meta: ChunkMeta,
+    /// Size of the slot holding the chunk
+    pub capacity: u64,
+    pub data: ChunkData,
+    /// Keeps the slot referenced while `data` maps it
+    _chunk: ChunkArc,
+}
 
-        // Create engine to read chunk data  
-        let parent_path = rocksdb_path.parent()
-            .ok_or_else(|| Error::InvalidArg("Invalid RocksDB path".to_string()))?;
-        
-        let engine_config = EngineConfig {
-            path: parent_path.to_path_buf(),
-            create: false,
-            prefix_len: 4, // Default prefix length used in examples
-        };
-        
-        let engine = Engine::open(&engine_config)?;
+/// The data of a chunk, read into memory or mapped from the chunk file
+pub enum ChunkData {
+    Read(Vec<u8>),
+    Mapped(MmapRegion),
+}
+
+impl Deref for ChunkData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            ChunkData::Read(buffer) => buffer,
+            ChunkData::Mapped(region) => region,
+        }
+    }
+}
+
+impl ChunkContentReader {
+    /// Open the meta store `meta_config` describes, the engine reading chunk data from `data_dir` waits until chunk data is read
+    pub fn new(meta_config: &MetaStoreConfig, data_dir: Option<&Path>, meta_cache_size: usize, open_timeout: Option<Duration>) -> Result<Self> {
+        let engine_config = engine_config(meta_config, data_dir, false)?;
+        let meta_store = Arc::new(open_meta_store(&engine_config.meta_store_config(), open_timeout)?);
 
         Ok(Self {
             meta_store,
-            engine,
+            engine: OnceLock::new(),
+            engine_config,
+            open_timeout,
+            meta_cache: MetaCache::new(meta_cache_size),
+            mmap: false,
+            gzip: false,
+            mkdir_parents: false,
+            quiet: false,
+            id_len: None,
+            committed_only: false,
+            at_version: None,
         })
     }
 
+    /// Map chunk data into memory instead of reading it, so large chunks are paged in as they are displayed
+    pub fn with_mmap(mut self, mmap: bool) -> Self {
+        self.mmap = mmap;
+        self
+    }
+
+    /// Compress `--output-file` with gzip as it is written
+    pub fn with_gzip(mut self, gzip: bool) -> Self {
+        self.gzip = gzip;
+        self
+    }
+
+    /// Create the missing parent directories of `--output-file` instead of failing
+    pub fn with_mkdir_parents(mut self, mkdir_parents: bool) -> Self {
+        self.mkdir_parents = mkdir_parents;
+        self
+    }
+
+    /// Leave out the chunk information and the headers around the content, and answer `--lookup-chunks` with
+    /// just yes or no per chunk. Notices such as a chunk not found go to stderr instead
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// A notice about a chunk rather than its data, on stderr with `--quiet` so stdout only carries the answer
+    fn notice(&self, message: &str) {
+        if self.quiet {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    /// Reject chunk IDs that are not found and are not `id_len` bytes long instead of reporting them missing
+    pub fn with_id_len(mut self, id_len: Option<usize>) -> Self {
+        self.id_len = id_len;
+        self
+    }
+
+    /// Fail reads of uncommitted chunks instead of returning their possibly partial data
+    pub fn with_committed_only(mut self, committed_only: bool) -> Self {
+        self.committed_only = committed_only;
+        self
+    }
+
+    /// Read chunks as of chunk version `at_version`. The store only keeps the latest version of a chunk,
+    /// so reads of a chunk that has moved past that version fail rather than show newer data
+    pub fn with_at_version(mut self, at_version: Option<u32>) -> Self {
+        self.at_version = at_version;
+        self
+    }
+
+    /// The engine reading chunk data, opened on first use on top of the already open meta store
+    fn engine(&self) -> Result<&Engine> {
+        if let Some(engine) = self.engine.get() {
+            return Ok(engine);
+        }
+        
+        let engine_config = self.engine_config.clone();
+        let meta_store = self.meta_store.clone();
+        let what = engine_config.path.display().to_string();
+        let mode = engine_config.mode.clone();
+        let engine = open_with_timeout(&what, &mode, self.open_timeout, move || Engine::with_meta_store(&engine_config, meta_store))?;
+        Ok(self.engine.get_or_init(|| engine))
+    }
+
+    pub fn meta_cache_stats(&self) -> MetaCacheStats {
+        self.meta_cache.stats()
+    }
+
     pub fn read_chunk_content(
         &self,
         chunk_id_hex: &str, 
-        content_format: &str, 
+        content_format: ContentFormat, 
         output_file: &Option<String>,
-        show_preview: bool
+        show_preview: bool,
+        show_stats: bool,
     ) -> Result<()> {
-        // Parse chunk ID from hex
-        let chunk_id = parse_hex_chunk_id(chunk_id_hex)?;
+        let content = match self.read_chunk(chunk_id_hex)? {
+            Some(content) => content,
+            None => return Ok(()),
+        };
         
-        // Get chunk metadata
-        let chunk_meta = self.meta_store.get_chunk_meta(&chunk_id)?;
-        let chunk_meta = match chunk_meta {
-            Some(meta) => meta,
-            None => {
-                println!("Chunk not found: {}", chunk_id_hex);
-                return Ok(());
-            }
+        self.render(&content, content_format, output_file, show_preview, show_stats)
+    }
+
+    /// Print a chunk read by `read`: its metadata, optionally statistics of its data, and the data in `content_format`
+    pub fn render(
+        &self,
+        content: &ChunkReadResult,
+        content_format: ContentFormat,
+        output_file: &Option<String>,
+        show_preview: bool,
+        show_stats: bool,
+    ) -> Result<()> {
+        self.display_chunk_info(content);
+        if show_stats {
+            self.display_content_stats(&content.data);
+        }
+        
+        self.output_content(&content.data, content_format, output_file, show_preview)
+    }
+
+    /// Compare a chunk byte-for-byte against a local file, returns whether they match
+    pub fn compare_with_file(&self, chunk_id_hex: &str, file_path: &str) -> Result<bool> {
+        let content = match self.read_chunk(chunk_id_hex)? {
+            Some(content) => content,
+            None => return Err(Error::InvalidArg(format!("Cannot compare, chunk {} does not exist", chunk_id_hex))),
         };
         
-        // Get chunk reference
-        let chunk_arc = self.engine.get(&chunk_id)?;
-        let chunk = match chunk_arc {
-            Some(chunk) => chunk,
+        let file_data = std::fs::read(file_path)
+            .map_err(|e| Error::IoError(format!("Failed to read compare file {}: {}", file_path, e)))?;
+        
+        println!("=== Compare Chunk {} With {} ===", format_hex_chunk_id(&content.chunk_id), file_path);
+        let buffer = &content.data[..];
+        let offset = match first_difference(buffer, &file_data) {
+            Some(offset) => offset,
             None => {
-                println!("Chunk data not found: {}", chunk_id_hex);
-                return Ok(());
+                println!("MATCH: {} bytes are identical", buffer.len());
+                return Ok(true);
             }
         };
         
-        // Read chunk data
-        let mut buffer = vec![0u8; chunk_meta.len as usize];
-        chunk.pread(&mut buffer, 0)?;
+        if buffer.len() != file_data.len() {
+            println!("LENGTH MISMATCH: chunk has {} bytes, file has {} bytes", buffer.len(), file_data.len());
+        }
+        println!("First difference at offset {} (0x{:x})", offset, offset);
+        
+        // Show a 32 byte window around the first difference, aligned to hex dump lines
+        let window_start = offset.saturating_sub(16) / 16 * 16;
+        let window = |data: &[u8]| {
+            let start = window_start.min(data.len());
+            let end = (window_start + 32).min(data.len());
+            format_hex_output_at(&data[start..end], start)
+        };
+        println!("\n--- Chunk ---");
+        print!("{}", window(buffer));
+        println!("--- File ---");
+        print!("{}", window(&file_data));
+        
+        Ok(false)
+    }
+
+    /// Compare two chunks byte-for-byte showing up to `max_diffs` differing regions, returns whether they are identical
+    pub fn compare_chunks(&self, chunk_id_a: &str, chunk_id_b: &str, max_diffs: usize) -> Result<bool> {
+        let chunk_a = self.read_chunk(chunk_id_a)?;
+        let chunk_b = self.read_chunk(chunk_id_b)?;
+        let (a, b) = match (chunk_a, chunk_b) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return Err(Error::InvalidArg("Cannot diff, at least one chunk does not exist".to_string())),
+        };
+        
+        println!("=== Diff Chunk A {} With Chunk B {} ===", format_hex_chunk_id(&a.chunk_id), format_hex_chunk_id(&b.chunk_id));
+        let identical = a.data[..] == b.data[..];
+        print!("{}", render_chunk_diff(&a.data, &b.data, max_diffs));
+        Ok(identical)
+    }
+
+    /// Print a chunk's serialized meta as a hex dump next to its decoded fields
+    pub fn dump_meta(&self, chunk_id_hex: &str) -> Result<()> {
+        let Some(chunk_id) = self.resolve_chunk_id(chunk_id_hex)? else {
+            println!("Chunk not found: {}", chunk_id_hex);
+            return Ok(());
+        };
+        let Some(raw) = self.meta_store.get_chunk_meta_raw(&chunk_id)? else {
+            println!("Chunk not found: {}", chunk_id_hex);
+            return Ok(());
+        };
+        
+        println!("=== Raw Chunk Meta ===");
+        println!("Chunk ID: {}", format_hex_chunk_id(&chunk_id));
+        println!("Key: {}", format_hex_chunk_id(MetaKey::chunk_meta_key_with(self.meta_store.chunk_meta_key_prefix(), &chunk_id).as_ref()));
+        println!("Value: {} bytes", raw.len());
+        print!("{}", format_hex_output(&raw));
+        println!();
+        
+        // Decoded separately so the raw bytes are still shown when they no longer deserialize
+        println!("=== Decoded Chunk Meta ===");
+        match ChunkMeta::deserialize(raw.as_ref()) {
+            Ok(chunk_meta) => {
+                let pos = chunk_meta.pos;
+                println!("Position: chunk size {}, cluster {}, group {}, index {}", format_size(pos.chunk_size().0), pos.cluster(), pos.group(), pos.index());
+                println!("Chain Version: {}", chunk_meta.chain_ver);
+                println!("Chunk Version: {}", chunk_meta.chunk_ver);
+                println!("Length: {}", chunk_meta.len);
+                println!("Checksum: {}", format_checksum(chunk_meta.checksum));
+                println!("Timestamp: {} ({})", chunk_meta.timestamp, format_timestamp(chunk_meta.timestamp));
+                println!("Last Request ID: {}", chunk_meta.last_request_id);
+                println!("Last Client: {:016x}{:016x}", chunk_meta.last_client_high, chunk_meta.last_client_low);
+                println!("ETag: {}", String::from_utf8_lossy(&chunk_meta.etag));
+                println!("Uncommitted: {}", if chunk_meta.uncommitted { "Yes" } else { "No" });
+            }
+            Err(e) => println!("Failed to deserialize: {:?}", e),
+        }
+        
+        Ok(())
+    }
+
+    /// Find the chunk a full ID or a unique ID prefix refers to, like git's short hashes
+    pub fn resolve_chunk_id(&self, chunk_id_hex: &str) -> Result<Option<Vec<u8>>> {
+        // Accept a short ID copied from --short-ids output, with its trailing "..."
+        let hex = chunk_id_hex.trim().trim_end_matches('.').to_lowercase();
+        if hex.len() % 2 == 0 {
+            let chunk_id = parse_hex_chunk_id(&hex)?;
+            if self.meta_cache.get_chunk_meta(&self.meta_store, &chunk_id)?.is_some() {
+                return Ok(Some(chunk_id));
+            }
+        }
         
-        // Display metadata
-        self.display_chunk_info(chunk_id_hex, &chunk_meta, &chunk);
+        let mut candidates = self.chunk_ids_with_prefix(&hex, MAX_CANDIDATES + 1)?;
+        match candidates.len() {
+            0 => {
+                // A prefix that matches nothing is most likely a truncated or mistyped full ID
+                if hex.len() % 2 == 0 {
+                    check_chunk_id_len(&parse_hex_chunk_id(&hex)?, self.id_len)?;
+                }
+                Ok(None)
+            }
+            1 => Ok(candidates.pop()),
+            count => {
+                println!("Chunk ID prefix {} matches:", hex);
+                for chunk_id in candidates.iter().take(MAX_CANDIDATES) {
+                    println!("  {}", format_hex_chunk_id(chunk_id));
+                }
+                if count > MAX_CANDIDATES {
+                    println!("  ...");
+                }
+                Err(Error::InvalidArg(format!("Chunk ID prefix {} is ambiguous, use more digits", hex)))
+            }
+        }
+    }
+
+    /// At most `limit` chunk IDs starting with the hex digits `hex`, sorted
+    fn chunk_ids_with_prefix(&self, hex: &str, limit: usize) -> Result<Vec<Vec<u8>>> {
+        // A trailing odd hex digit is the high nibble of the next byte, try its 16 values
+        let prefix = parse_hex_chunk_id(&hex[..hex.len() / 2 * 2])?;
+        let prefixes: Vec<Vec<u8>> = if hex.len() % 2 == 0 {
+            vec![prefix]
+        } else {
+            let high = u8::from_str_radix(&hex[hex.len() - 1..], 16)
+                .map_err(|_| Error::InvalidArg(format!("Invalid hex character in chunk ID: {}", &hex[hex.len() - 1..])))?;
+            (0..16u8).map(|low| [prefix.as_slice(), &[high << 4 | low]].concat()).collect()
+        };
         
-        // Process and output content
-        self.output_content(&buffer, content_format, output_file, show_preview)?;
+        let mut chunk_ids = Vec::new();
+        for prefix in prefixes {
+            if chunk_ids.len() == limit {
+                break;
+            }
+            let found = self.meta_store.chunks_with_prefix(&prefix, limit - chunk_ids.len())?;
+            chunk_ids.extend(found.iter().map(|chunk_id| chunk_id.to_vec()));
+        }
+        chunk_ids.sort();
+        Ok(chunk_ids)
+    }
+
+    /// Look up the metadata of many chunks by full ID in one batch, `None` for those that do not exist.
+    /// With `membership` only the chunks it cannot rule out are looked up in RocksDB
+    pub fn lookup_chunks(&self, chunk_ids: &[Vec<u8>], membership: Option<&ChunkMembership>) -> Result<Vec<Option<ChunkMeta>>> {
+        match membership {
+            Some(membership) => self.meta_store.get_chunk_metas_with(chunk_ids, membership),
+            None => self.meta_store.get_chunk_metas(chunk_ids),
+        }
+    }
+
+    /// Look up the chunks listed in `ids_path` and print one line each, `-` reads the IDs from stdin.
+    /// With `prebuild_index` one scan of the chunk keys first rules out most absent IDs, worth it for many IDs that mostly miss
+    pub fn show_lookups(&self, ids_path: &str, prebuild_index: bool) -> Result<()> {
+        let chunk_ids = read_chunk_id_list(ids_path)?;
+        let membership = if prebuild_index { Some(ChunkMembership::build(&self.meta_store)?) } else { None };
+        let chunk_metas = self.lookup_chunks(&chunk_ids, membership.as_ref())?;
         
+        print!("{}", self.render_lookups(&chunk_ids, &chunk_metas));
+        if let Some(membership) = membership {
+            let stats = membership.stats();
+            self.notice(&format!("Index ({}): skipped {} of {} lookups", if membership.is_bloom() { "bloom filter" } else { "ID prefixes" }, stats.skipped, stats.lookups));
+        }
         Ok(())
     }
 
-    fn display_chunk_info(&self, chunk_id_hex: &str, chunk_meta: &ChunkMeta, chunk: &Chunk) {
+    /// The lines `--lookup-chunks` prints, one per chunk and the number found. With `--quiet` only `yes` or `no`
+    /// per chunk, in the order of the list
+    fn render_lookups(&self, chunk_ids: &[Vec<u8>], chunk_metas: &[Option<ChunkMeta>]) -> String {
+        let mut out = String::new();
+        for (chunk_id, chunk_meta) in chunk_ids.iter().zip(chunk_metas) {
+            match chunk_meta {
+                _ if self.quiet => out += if chunk_meta.is_some() { "yes\n" } else { "no\n" },
+                Some(meta) => out += &format!("{}  len {}  chain_ver {}  chunk_ver {}  checksum {}\n",
+                    format_hex_chunk_id(chunk_id), meta.len, meta.chain_ver, meta.chunk_ver, format_checksum(meta.checksum)),
+                None => out += &format!("{}  missing\n", format_hex_chunk_id(chunk_id)),
+            }
+        }
+        if !self.quiet {
+            let found = chunk_metas.iter().filter(|chunk_meta| chunk_meta.is_some()).count();
+            out += &format!("\nFound {} of {} chunks\n", found, chunk_ids.len());
+        }
+        out
+    }
+
+    /// Resolve a chunk ID or unique prefix and read the whole chunk, printing why when it cannot be found
+    fn read_chunk(&self, chunk_id_hex: &str) -> Result<Option<ChunkReadResult>> {
+        let Some(chunk_id) = self.resolve_chunk_id(chunk_id_hex)? else {
+            self.notice(&format!("Chunk not found: {}", chunk_id_hex));
+            return Ok(None);
+        };
+        
+        let content = self.read(&chunk_id, 0, None)?;
+        if content.is_none() {
+            // The metadata may be there without the chunk the engine recovered from it
+            let what = if self.meta_cache.get_chunk_meta(&self.meta_store, &chunk_id)?.is_some() { "Chunk data" } else { "Chunk" };
+            self.notice(&format!("{} not found: {}", what, format_hex_chunk_id(&chunk_id)));
+        }
+        Ok(content)
+    }
+
+    /// Read `length` bytes of a chunk starting at `offset`, up to its end without a length, and
+    /// `None` if there is no such chunk. The range is clamped to the chunk's data. Nothing is
+    /// printed, see `render`.
+    pub fn read(&self, chunk_id: &[u8], offset: u32, length: Option<u32>) -> Result<Option<ChunkReadResult>> {
+        let Some(meta) = self.meta_cache.get_chunk_meta(&self.meta_store, chunk_id)? else {
+            return Ok(None);
+        };
+        if meta.uncommitted && self.committed_only {
+            return Err(Error::InvalidArg(format!(
+                "Chunk {} is uncommitted and its data may be partial, pass --force to read it anyway", format_hex_chunk_id(chunk_id))));
+        }
+        if let Some(at_version) = self.at_version.filter(|&at_version| meta.chunk_ver > at_version) {
+            return Err(Error::InvalidArg(format!(
+                "Chunk {} is at version {}, older versions are not kept by this store so version {} or below cannot be read",
+                format_hex_chunk_id(chunk_id), meta.chunk_ver, at_version)));
+        }
+        let Some(chunk) = self.engine()?.get(chunk_id)? else {
+            return Ok(None);
+        };
+        // A length beyond the slot would read into the chunks stored after it
+        if meta.len > chunk.capacity() {
+            return Err(Error::MetaError(format!(
+                "Chunk {} has length {} beyond its capacity {}, its metadata is corrupt", format_hex_chunk_id(chunk_id), meta.len, chunk.capacity())));
+        }
+        
+        let offset = offset.min(meta.len);
+        let length = length.unwrap_or(u32::MAX).min(meta.len - offset);
+        // SAFETY: --mmap is refused with --mode secondary, otherwise the store is a snapshot no
+        // process writes to, and the chunk is kept alive next to the mapping
+        let data = match self.mmap.then(|| unsafe { chunk.mmap_range(offset, length) }) {
+            Some(Ok(region)) => ChunkData::Mapped(region),
+            mapped => {
+                if let Some(Err(e)) = mapped {
+                    eprintln!("Cannot map chunk {}, reading it instead: {}", format_hex_chunk_id(chunk_id), e);
+                }
+                let mut buffer = vec![0u8; length as usize];
+                chunk.pread(&mut buffer, offset)?;
+                ChunkData::Read(buffer)
+            }
+        };
+        
+        Ok(Some(ChunkReadResult {
+            chunk_id: chunk_id.to_vec(),
+            meta,
+            capacity: chunk.capacity() as u64,
+            data,
+            _chunk: chunk,
+        }))
+    }
+
+    fn display_chunk_info(&self, content: &ChunkReadResult) {
+        let meta = &content.meta;
+        if self.quiet {
+            if let Some(warning) = uncommitted_warning(meta) {
+                eprintln!("{}", warning);
+            }
+            return;
+        }
         println!("=== Chunk Information ===");
-        println!("Chunk ID: {}", chunk_id_hex);
-        println!("Size: {} ({})", format_size(chunk_meta.len as u64), chunk_meta.len);
-        println!("Allocated Size: {} ({})", format_size(chunk.capacity() as u64), chunk.capacity());
-        println!("Utilization: {:.2}%", (chunk_meta.len as f64 / chunk.capacity() as f64) * 100.0);
-        println!("Chain Version: {}", chunk_meta.chain_ver);
-        println!("Chunk Version: {}", chunk_meta.chunk_ver);
-        println!("Checksum: 0x{:08x}", chunk_meta.checksum);
-        println!("Uncommitted: {}", if chunk_meta.uncommitted { "Yes" } else { "No" });
+        println!("Chunk ID: {}", format_hex_chunk_id(&content.chunk_id));
+        println!("Size: {} ({})", format_size(meta.len as u64), meta.len);
+        println!("Allocated Size: {} ({})", format_size(content.capacity), content.capacity);
+        println!("Utilization: {:.2}%", (meta.len as f64 / content.capacity as f64) * 100.0);
+        println!("Chain Version: {}", meta.chain_ver);
+        println!("Chunk Version: {}", meta.chunk_ver);
+        println!("Checksum: {}", format_checksum(meta.checksum));
+        println!("Uncommitted: {}", if meta.uncommitted { "Yes" } else { "No" });
+        if let Some(warning) = uncommitted_warning(meta) {
+            println!();
+            println!("{}", warning);
+        }
         println!();
     }
 
     fn output_content(
         &self,
         buffer: &[u8],
-        content_format: &str,
+        content_format: ContentFormat,
         output_file: &Option<String>,
         show_preview: bool,
     ) -> Result<()> {
         // Prepare output based on format
-        let is_hex_format = content_format == "hex";
-        let hex_output = if is_hex_format {
-            Some(format_hex_output(buffer))
-        } else {
-            None
+        let rendered = match content_format {
+            // Hex dumps are written straight to the output, they are several times larger than the data
+            ContentFormat::CArray => Some(format_byte_array(buffer, ArrayStyle::C)),
+            ContentFormat::RustArray => Some(format_byte_array(buffer, ArrayStyle::Rust)),
+            ContentFormat::Hex | ContentFormat::Binary | ContentFormat::Text => None,
         };
         
         // Write output
         match output_file {
             Some(file_path) => {
-                self.write_to_file(buffer, content_format, file_path, &hex_output)?;
+                self.write_to_file(buffer, content_format, file_path, &rendered)?;
             }
             None => {
-                self.write_to_stdout(buffer, content_format, &hex_output)?;
+                self.write_to_stdout(buffer, content_format, &rendered)?;
             }
         }
         
         // Show preview if requested
-        if show_preview && content_format != "text" {
+        if show_preview && content_format != ContentFormat::Text {
             self.show_text_preview(buffer);
         }
         
@@ -134,67 +514,86 @@ impl ChunkContentReader {
     fn write_to_file(
         &self,
         buffer: &[u8],
-        content_format: &str,
+        content_format: ContentFormat,
         file_path: &str,
-        hex_output: &Option<String>,
+        rendered: &Option<String>,
     ) -> Result<()> {
-        let mut file = File::create(file_path)
-            .map_err(|e| Error::IoError(format!("Failed to create output file: {}", e)))?;
+        let mut file = OutputWriter::create(file_path, self.gzip, self.mkdir_parents)?;
         
         match content_format {
-            "hex" => {
-                if let Some(hex_data) = hex_output {
-                    file.write_all(hex_data.as_bytes())
-                        .map_err(|e| Error::IoError(format!("Failed to write to file: {}", e)))?;
+            ContentFormat::Hex => {
+                write_hex_output(&mut file, buffer, 0).map_err(|e| file.error(e))?;
+            }
+            ContentFormat::CArray | ContentFormat::RustArray => {
+                if let Some(rendered) = rendered {
+                    file.write_all(rendered.as_bytes()).map_err(|e| file.error(e))?;
                 }
             }
-            "binary" | "text" => {
-                file.write_all(buffer)
-                    .map_err(|e| Error::IoError(format!("Failed to write to file: {}", e)))?;
+            ContentFormat::Binary | ContentFormat::Text => {
+                file.write_all(buffer).map_err(|e| file.error(e))?;
             }
-            _ => return Err(Error::InvalidArg(format!("Invalid content format: {}. Use 'hex', 'binary', or 'text'", content_format))),
         }
+        file.finish()?;
         
-        println!("Content written to: {}", file_path);
+        if !self.quiet {
+            println!("Content written to: {}{}", file_path, if self.gzip { " (gzip)" } else { "" });
+        }
         Ok(())
     }
 
     fn write_to_stdout(
         &self,
         buffer: &[u8],
-        content_format: &str,
-        hex_output: &Option<String>,
+        content_format: ContentFormat,
+        rendered: &Option<String>,
     ) -> Result<()> {
         match content_format {
-            "hex" => {
-                println!("=== Chunk Content (Hex) ===");
-                if let Some(hex_data) = hex_output {
-                    print!("{}", hex_data);
+            ContentFormat::Hex => {
+                if !self.quiet {
+                    println!("=== Chunk Content (Hex) ===");
+                }
+                write_hex_output(&mut std::io::stdout().lock(), buffer, 0)
+                    .map_err(|e| Error::IoError(format!("Failed to write to stdout: {}", e)))?;
+            }
+            ContentFormat::CArray | ContentFormat::RustArray => {
+                // No header, so the array can be pasted as it is
+                if let Some(source) = rendered {
+                    print!("{}", source);
                 }
             }
-            "binary" => {
+            ContentFormat::Binary => {
                 // For binary, just write to stdout
                 std::io::stdout().write_all(buffer)
                     .map_err(|e| Error::IoError(format!("Failed to write to stdout: {}", e)))?;
             }
-            "text" => {
-                println!("=== Chunk Content (Text) ===");
-                // Try to display as UTF-8, with fallback for invalid characters
-                match String::from_utf8(buffer.to_vec()) {
-                    Ok(text) => println!("{}", text),
-                    Err(_) => {
-                        // Display as best-effort UTF-8 with replacement characters
-                        let text = String::from_utf8_lossy(buffer);
-                        println!("{}", text);
-                    }
+            ContentFormat::Text => {
+                if !self.quiet {
+                    println!("=== Chunk Content (Text) ===");
                 }
+                // Display as best-effort UTF-8 with replacement characters, valid UTF-8 is not copied
+                println!("{}", String::from_utf8_lossy(buffer));
             }
-            _ => return Err(Error::InvalidArg(format!("Invalid content format: {}. Use 'hex', 'binary', or 'text'", content_format))),
         }
         
         Ok(())
     }
 
+    fn display_content_stats(&self, buffer: &[u8]) {
+        let stats = content_stats(buffer);
+        println!("=== Content Statistics ===");
+        // Compressed or encrypted data is close to 8 bits per byte, sparse or repetitive data far below
+        println!("Entropy: {:.4} bits/byte", stats.entropy);
+        println!("Zero bytes: {} ({:.2}%)", stats.zero_bytes, stats.zero_bytes as f64 * 100.0 / buffer.len().max(1) as f64);
+        // A long zero run could be stored sparsely, many short ones only help compression
+        if stats.longest_zero_run > 0 {
+            println!("Longest zero run: {} ({}) at offset {}", format_size(stats.longest_zero_run as u64), stats.longest_zero_run, stats.longest_zero_run_offset);
+        }
+        if let (Some(min), Some(max), Some((most_common, count))) = (stats.min, stats.max, stats.most_common) {
+            println!("Byte values: min 0x{:02x}, max 0x{:02x}, most common 0x{:02x} ({} times)", min, max, most_common, count);
+        }
+        println!();
+    }
+
     fn show_text_preview(&self, buffer: &[u8]) {
         println!("\n=== Text Preview (first 256 bytes) ===");
         let preview_len = std::cmp::min(256, buffer.len());
@@ -204,4 +603,344 @@ impl ChunkContentReader {
             println!("... ({} more bytes)", buffer.len() - 256);
         }
     }
-}
\ No newline at end of file
+}
+
+/// Shown with the metadata of a chunk whose last write was never committed
+fn uncommitted_warning(meta: &ChunkMeta) -> Option<&'static str> {
+    meta.uncommitted.then_some("WARNING: this chunk is uncommitted, its data may be partial and is not durable until the write is recovered")
+}
+
+/// Full chunk IDs in hex, one per line, from a file or from stdin for `-`. Blank lines are skipped
+fn read_chunk_id_list(path: &str) -> Result<Vec<Vec<u8>>> {
+    let content = if path == "-" {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    }
+    .map_err(|e| Error::IoError(format!("Failed to read chunk IDs from {}: {}", path, e)))?;
+    content.lines().filter(|line| !line.trim().is_empty()).map(parse_hex_chunk_id).collect()
+}
+
+/// The report `--diff-chunks` prints: whether chunks A and B are identical and if not, their lengths, the
+/// first divergence and a side-by-side hex view of each differing region, the first `max_diffs` of them
+fn render_chunk_diff(a: &[u8], b: &[u8], max_diffs: usize) -> String {
+    let Some(offset) = first_difference(a, b) else {
+        return format!("IDENTICAL: {} bytes\n", a.len());
+    };
+    
+    let mut out = "DIFFERENT\n".to_string();
+    if a.len() != b.len() {
+        out += &format!("Length mismatch: A has {} bytes, B has {} bytes\n", a.len(), b.len());
+    }
+    let differing = a.iter().zip(b).filter(|(x, y)| x != y).count();
+    out += &format!("Differing bytes in the common {} bytes: {}\n", a.len().min(b.len()), differing);
+    out += &format!("First difference at offset {} (0x{:x})\n", offset, offset);
+    
+    let ranges = differing_ranges(a, b);
+    let len = a.len().max(b.len());
+    for (i, range) in ranges.iter().take(max_diffs).enumerate() {
+        out += &format!("\nRegion {} of {}: offset {} (0x{:x}), {} bytes\n", i + 1, ranges.len(), range.start, range.start, range.len());
+        out += &format!("{:<8}  {:<23} | {}\n", "Offset", "A", "B");
+        // One line of context either side of the region
+        out += &format_hex_side_by_side(a, b, range.start.saturating_sub(8), (range.end + 8).min(len));
+    }
+    if ranges.len() > max_diffs {
+        out += &format!("\n... {} more differing regions, raise --max-diffs to show them\n", ranges.len() - max_diffs);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::utils::meta_store_config;
+
+    #[test]
+    fn test_resolve_chunk_id() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let config = EngineConfig {
+                path: dir.path().into(),
+                create: true,
+                prefix_len: 4,
+                ..Default::default()
+            };
+            let engine = Engine::open(&config).unwrap();
+            for chunk_id in [0x1234_5678u32, 0x1234_9abc, 0x1299_0000, 0xab00_0000] {
+                engine.put(&chunk_id.to_be_bytes(), &[1u8; 512], 1, 1).unwrap();
+            }
+        }
+
+        let reader = ChunkContentReader::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, 16, None).unwrap();
+        let resolve = |hex: &str| reader.resolve_chunk_id(hex).map(|id| id.map(|id| format_hex_chunk_id(&id)));
+
+        // Full IDs and unique prefixes, also of odd length
+        assert_eq!(resolve("12345678").unwrap().as_deref(), Some("12345678"));
+        assert_eq!(resolve("123456").unwrap().as_deref(), Some("12345678"));
+        assert_eq!(resolve("1299").unwrap().as_deref(), Some("12990000"));
+        assert_eq!(resolve("129").unwrap().as_deref(), Some("12990000"));
+        assert_eq!(resolve("a").unwrap().as_deref(), Some("ab000000"));
+        assert_eq!(resolve("AB00...").unwrap().as_deref(), Some("ab000000"));
+
+        // Ambiguous prefixes are rejected, their candidates are listed
+        assert!(resolve("1234").unwrap_err().to_string().contains("ambiguous"));
+        assert!(resolve("1").unwrap_err().to_string().contains("ambiguous"));
+        let candidates = |hex: &str, limit| -> Vec<String> {
+            reader.chunk_ids_with_prefix(hex, limit).unwrap().iter().map(|id| format_hex_chunk_id(id)).collect()
+        };
+        assert_eq!(candidates("1234", 10), ["12345678", "12349abc"]);
+        assert_eq!(candidates("1", 10), ["12345678", "12349abc", "12990000"]);
+        assert_eq!(candidates("", 2).len(), 2);
+
+        // Absent prefixes are not found
+        assert_eq!(resolve("5").unwrap(), None);
+        assert_eq!(resolve("123457").unwrap(), None);
+        assert_eq!(resolve("12345679").unwrap(), None);
+
+        // With --id-len, IDs of the wrong length are errors rather than not found, prefixes still resolve
+        let reader = reader.with_id_len(Some(4));
+        assert_eq!(reader.resolve_chunk_id("123456").unwrap().map(|id| format_hex_chunk_id(&id)).as_deref(), Some("12345678"));
+        assert_eq!(reader.resolve_chunk_id("12345679").unwrap(), None);
+        assert!(matches!(reader.resolve_chunk_id("123457"), Err(Error::InvalidArg(_))));
+        assert!(matches!(reader.resolve_chunk_id("1234567800"), Err(Error::InvalidArg(_))));
+    }
+
+    #[test]
+    fn test_metadata_without_engine() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let config = EngineConfig {
+                path: dir.path().into(),
+                create: true,
+                prefix_len: 4,
+                ..Default::default()
+            };
+            let engine = Engine::open(&config).unwrap();
+            engine.put(&0x1234_5678u32.to_be_bytes(), &[1u8; 512], 1, 1).unwrap();
+        }
+
+        // Metadata lookups work while the data directory cannot be opened, reading chunk data fails
+        let missing = dir.path().join("missing");
+        let reader = ChunkContentReader::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), Some(&missing), 16, None).unwrap();
+        assert_eq!(reader.resolve_chunk_id("1234").unwrap(), Some(0x1234_5678u32.to_be_bytes().to_vec()));
+        reader.dump_meta("12345678").unwrap();
+        assert!(reader.engine.get().is_none());
+        assert_eq!(reader.read_chunk("12345678").err(), Some(Error::DataDirMissing(missing)));
+
+        let reader = ChunkContentReader::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, 16, None).unwrap();
+        let content = reader.read_chunk("12345678").unwrap().unwrap();
+        assert_eq!(&content.data[..], [1u8; 512]);
+        assert!(reader.engine.get().is_some());
+    }
+
+    #[test]
+    fn test_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let data: Vec<u8> = (0..5000u32).map(|i| i as u8).collect();
+        let chunk_id = 0x1234_5678u32.to_be_bytes();
+        {
+            let config = EngineConfig {
+                path: dir.path().into(),
+                create: true,
+                prefix_len: 4,
+                ..Default::default()
+            };
+            let engine = Engine::open(&config).unwrap();
+            engine.put(&chunk_id, &data, 1, 2).unwrap();
+        }
+
+        for mmap in [false, true] {
+            let reader = ChunkContentReader::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, 16, None).unwrap().with_mmap(mmap);
+            let result = reader.read(&chunk_id, 0, None).unwrap().unwrap();
+            assert_eq!(result.chunk_id, chunk_id);
+            assert_eq!((result.meta.len, result.meta.chain_ver, result.meta.chunk_ver), (5000, 1, 2));
+            assert!(result.capacity >= 5000);
+            assert_eq!(&result.data[..], &data[..]);
+            assert_eq!(matches!(result.data, ChunkData::Mapped(_)), mmap);
+
+            // Ranges are clamped to the chunk's data
+            assert_eq!(&reader.read(&chunk_id, 1000, Some(100)).unwrap().unwrap().data[..], &data[1000..1100]);
+            assert_eq!(&reader.read(&chunk_id, 4990, Some(100)).unwrap().unwrap().data[..], &data[4990..]);
+            assert!(reader.read(&chunk_id, 6000, None).unwrap().unwrap().data.is_empty());
+
+            assert!(reader.read(&0x1234_5679u32.to_be_bytes(), 0, None).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn test_uncommitted_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk_id = 0x1234_5678u32.to_be_bytes();
+        {
+            let config = EngineConfig {
+                path: dir.path().into(),
+                create: true,
+                prefix_len: 4,
+                ..Default::default()
+            };
+            let engine = Engine::open(&config).unwrap();
+            engine.put(&chunk_id, &[7u8; 100], 1, 1).unwrap();
+            engine.put(&0x1234_5679u32.to_be_bytes(), &[8u8; 100], 1, 1).unwrap();
+            // Leave the first chunk's meta the way an interrupted write does
+            let meta = ChunkMeta { uncommitted: true, ..engine.get(&chunk_id).unwrap().unwrap().meta().clone() };
+            engine.meta_store.add_chunk(&chunk_id, &meta, true).unwrap();
+        }
+
+        let reader = ChunkContentReader::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, 16, None).unwrap();
+        let result = reader.read(&chunk_id, 0, None).unwrap().unwrap();
+        assert!(result.meta.uncommitted);
+        assert_eq!(&result.data[..], [7u8; 100]);
+        assert!(uncommitted_warning(&result.meta).unwrap().starts_with("WARNING"));
+        let committed = reader.read(&0x1234_5679u32.to_be_bytes(), 0, None).unwrap().unwrap();
+        assert_eq!(uncommitted_warning(&committed.meta), None);
+
+        // Only committed chunks can be read with --committed-only-reads
+        let reader = reader.with_committed_only(true);
+        assert!(matches!(reader.read(&chunk_id, 0, None), Err(Error::InvalidArg(_))));
+        assert!(reader.read(&0x1234_5679u32.to_be_bytes(), 0, None).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_len_beyond_capacity() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk_id = 0x1234_5678u32.to_be_bytes();
+        {
+            let config = EngineConfig {
+                path: dir.path().into(),
+                create: true,
+                prefix_len: 4,
+                ..Default::default()
+            };
+            let engine = Engine::open(&config).unwrap();
+            engine.put(&chunk_id, &[7u8; 100], 1, 1).unwrap();
+            let chunk = engine.get(&chunk_id).unwrap().unwrap();
+            let meta = ChunkMeta { len: chunk.capacity() + 1, ..chunk.meta().clone() };
+            engine.meta_store.add_chunk(&chunk_id, &meta, true).unwrap();
+        }
+
+        let reader = ChunkContentReader::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, 16, None).unwrap();
+        match reader.read(&chunk_id, 0, None) {
+            Err(Error::MetaError(message)) => assert!(message.contains(&format_hex_chunk_id(&chunk_id))),
+            other => panic!("expected a metadata error, got {:?}", other.map(|result| result.map(|result| result.meta))),
+        }
+    }
+
+    #[test]
+    fn test_at_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk_id = 0x1234_5678u32.to_be_bytes();
+        {
+            let config = EngineConfig {
+                path: dir.path().into(),
+                create: true,
+                prefix_len: 4,
+                ..Default::default()
+            };
+            let engine = Engine::open(&config).unwrap();
+            engine.put(&chunk_id, &[5u8; 100], 1, 5).unwrap();
+        }
+
+        let open = |at_version| ChunkContentReader::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, 16, None).unwrap().with_at_version(at_version);
+        // The latest version is the one at or below any version from it on
+        for at_version in [None, Some(5), Some(9)] {
+            let result = open(at_version).read(&chunk_id, 0, None).unwrap().unwrap();
+            assert_eq!((result.meta.chunk_ver, &result.data[..]), (5, &[5u8; 100][..]));
+        }
+        // Older versions are gone, which is an error rather than the newer data
+        let error = open(Some(4)).read(&chunk_id, 0, None).err().unwrap();
+        assert!(matches!(&error, Error::InvalidArg(message) if message.contains("at version 5")), "{}", error);
+        assert!(open(Some(4)).read(&0x1234_5679u32.to_be_bytes(), 0, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_lookup_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let config = EngineConfig {
+                path: dir.path().into(),
+                create: true,
+                prefix_len: 4,
+                ..Default::default()
+            };
+            let engine = Engine::open(&config).unwrap();
+            for i in 0..3u32 {
+                engine.put(&(0x1234_0000 + i).to_be_bytes(), &[1u8; 100], 1, 1).unwrap();
+            }
+        }
+        let ids_path = dir.path().join("ids");
+        std::fs::write(&ids_path, "12340000\n\nabcd0000\n12340002\nabcd0001\n").unwrap();
+
+        let reader = ChunkContentReader::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, 16, None).unwrap();
+        let chunk_ids = read_chunk_id_list(ids_path.to_str().unwrap()).unwrap();
+        assert_eq!(chunk_ids.len(), 4);
+        let membership = ChunkMembership::build(&reader.meta_store).unwrap();
+        let found: Vec<bool> = reader.lookup_chunks(&chunk_ids, Some(&membership)).unwrap().iter().map(Option::is_some).collect();
+        assert_eq!(found, [true, false, true, false]);
+        // The absent IDs never reached RocksDB, and the answers match a plain lookup
+        assert_eq!(membership.stats(), ChunkMembershipStats { lookups: 4, skipped: 2 });
+        assert_eq!(reader.lookup_chunks(&chunk_ids, None).unwrap(), reader.lookup_chunks(&chunk_ids, Some(&membership)).unwrap());
+        
+        // With --quiet a single ID is answered with a single line
+        let reader = reader.with_quiet(true);
+        for (chunk_id, answer) in [(&chunk_ids[0], "yes\n"), (&chunk_ids[1], "no\n")] {
+            let chunk_ids = vec![chunk_id.clone()];
+            assert_eq!(reader.render_lookups(&chunk_ids, &reader.lookup_chunks(&chunk_ids, None).unwrap()), answer);
+        }
+        let chunk_metas = reader.lookup_chunks(&chunk_ids, None).unwrap();
+        assert_eq!(reader.render_lookups(&chunk_ids, &chunk_metas), "yes\nno\nyes\nno\n");
+        let verbose = reader.with_quiet(false).render_lookups(&chunk_ids, &chunk_metas);
+        assert!(verbose.starts_with("12340000  len 100") && verbose.ends_with("\nFound 2 of 4 chunks\n"), "{}", verbose);
+    }
+
+    #[test]
+    fn test_compare_with_file() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let config = EngineConfig {
+                path: dir.path().into(),
+                create: true,
+                prefix_len: 4,
+                ..Default::default()
+            };
+            let engine = Engine::open(&config).unwrap();
+            engine.put(&0x1234_5678u32.to_be_bytes(), &[3u8; 100], 1, 1).unwrap();
+        }
+        let same = dir.path().join("same");
+        std::fs::write(&same, [3u8; 100]).unwrap();
+        let other = dir.path().join("other");
+        std::fs::write(&other, [3u8; 99]).unwrap();
+
+        let reader = ChunkContentReader::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, 16, None).unwrap();
+        assert!(reader.compare_with_file("12345678", same.to_str().unwrap()).unwrap());
+        assert!(!reader.compare_with_file("12345678", other.to_str().unwrap()).unwrap());
+        // A chunk that does not exist is an error rather than a mismatch
+        assert!(matches!(reader.compare_with_file("abcd0000", same.to_str().unwrap()), Err(Error::InvalidArg(_))));
+    }
+
+    #[test]
+    fn test_render_chunk_diff() {
+        let a = [0u8; 256];
+        assert_eq!(render_chunk_diff(&a, &a, 10), "IDENTICAL: 256 bytes\n");
+        
+        // Every differing region is shown, not only the first
+        let mut b = a;
+        for offset in [8, 100, 200] {
+            b[offset] = 1;
+        }
+        let diff = render_chunk_diff(&a, &b, 10);
+        assert!(diff.starts_with("DIFFERENT\nDiffering bytes in the common 256 bytes: 3\nFirst difference at offset 8 (0x8)\n"), "{}", diff);
+        for region in ["Region 1 of 3: offset 8", "Region 2 of 3: offset 100", "Region 3 of 3: offset 200"] {
+            assert!(diff.contains(region), "{}", diff);
+        }
+        assert!(diff.contains("00000060  00 00 00 00 00 00 00 00 | 00 00 00 00 01 00 00 00  <"), "{}", diff);
+        
+        // Past --max-diffs the remaining regions are only counted
+        let diff = render_chunk_diff(&a, &b, 2);
+        assert!(diff.contains("Region 2 of 3") && !diff.contains("Region 3 of 3"), "{}", diff);
+        assert!(diff.ends_with("... 1 more differing regions, raise --max-diffs to show them\n"), "{}", diff);
+        
+        let diff = render_chunk_diff(&a, &a[..250], 10);
+        assert!(diff.contains("Length mismatch: A has 256 bytes, B has 250 bytes\n"), "{}", diff);
+        assert!(diff.contains("Region 1 of 1: offset 250 (0xfa), 6 bytes"), "{}", diff);
+    }
+}
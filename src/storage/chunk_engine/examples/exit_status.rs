@@ -0,0 +1,56 @@
+use chunk_engine::*;
+use std::process::ExitCode;
+
+/// Exit status of the tool, so scripts and CI jobs can gate on the health of a store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Ran to completion and found nothing wrong
+    Ok = 0,
+    /// Invalid arguments, or the tool could not run at all (e.g. the store failed to open)
+    UsageError = 1,
+    /// Two copies of data that should agree differ
+    Mismatch = 2,
+    /// Chunk data does not match its checksum
+    Corruption = 3,
+    /// Metadata refers to data that does not exist, or data is not referenced by any metadata
+    Orphans = 4,
+    /// The chunk data directory does not exist while the metadata does, e.g. an unmounted data volume
+    DataDirMissing = 5,
+    /// Stopped by Ctrl-C before the scan finished, with its progress saved to resume from; 128 + SIGINT as shells report it
+    Interrupted = 130,
+}
+
+impl ExitStatus {
+    /// Exit status for an error that aborted the run
+    pub fn from_error(error: &Error) -> Self {
+        match error {
+            Error::ChecksumMismatch(_) => Self::Corruption,
+            Error::ChainVersionMismatch(_) | Error::ChunkETagMismatch(_) => Self::Mismatch,
+            Error::DataDirMissing(_) => Self::DataDirMissing,
+            _ => Self::UsageError,
+        }
+    }
+}
+
+impl From<ExitStatus> for ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_status_from_error() {
+        assert_eq!(ExitStatus::from_error(&Error::InvalidArg("bad size".into())), ExitStatus::UsageError);
+        assert_eq!(ExitStatus::from_error(&Error::RocksDBError("open fail".into())), ExitStatus::UsageError);
+        assert_eq!(ExitStatus::from_error(&Error::ChecksumMismatch("chunk".into())), ExitStatus::Corruption);
+        assert_eq!(ExitStatus::from_error(&Error::ChainVersionMismatch("chunk".into())), ExitStatus::Mismatch);
+        assert_eq!(ExitStatus::from_error(&Error::DataDirMissing("/data".into())), ExitStatus::DataDirMissing);
+        assert_eq!(ExitStatus::Orphans as u8, 4);
+        assert_eq!(ExitStatus::DataDirMissing as u8, 5);
+        assert_eq!(ExitStatus::Interrupted as u8, 130);
+    }
+}
@@ -0,0 +1,205 @@
+use chunk_engine::*;
+use std::{path::Path, sync::Arc, time::Duration};
+
+use super::exit_status::ExitStatus;
+use super::utils::{engine_config, format_hex_chunk_id, format_size, open_store_with_timeout};
+
+/// Outcome of copying chunks from one store into another
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MigrateReport {
+    pub migrated_chunks: u64,
+    pub migrated_bytes: u64,
+    /// Chunks the destination already holds, left untouched
+    pub existing_chunks: u64,
+    /// Chunks whose data read back from the destination does not match the source checksum
+    pub failed_chunks: Vec<String>,
+}
+
+impl MigrateReport {
+    /// Exit status of the migration, a chunk that failed to verify is a mismatch between the stores
+    pub fn exit_status(&self) -> ExitStatus {
+        if self.failed_chunks.is_empty() {
+            ExitStatus::Ok
+        } else {
+            ExitStatus::Mismatch
+        }
+    }
+}
+
+pub struct ChunkMigrator {
+    source_meta_store: Arc<MetaStore>,
+    source: Engine,
+    /// `None` for a dry run, which never touches the destination
+    dest: Option<Engine>,
+}
+
+impl ChunkMigrator {
+    /// Open the source store of `source_config` read-only and, unless `dry_run`, the destination read-write.
+    /// The destination gets the same chunk meta key layout as the source.
+    pub fn new(source_config: &MetaStoreConfig, source_data_dir: Option<&Path>, dest_path: &Path, dry_run: bool, open_timeout: Option<Duration>) -> Result<Self> {
+        let (source_meta_store, source) = open_store_with_timeout(engine_config(source_config, source_data_dir, false)?, open_timeout)?;
+        let dest = if dry_run {
+            None
+        } else {
+            let dest_config = MetaStoreConfig {
+                rocksdb: RocksDBConfig { path: dest_path.into(), mode: DbOpenMode::ReadWrite, ..source_config.rocksdb.clone() },
+                ..source_config.clone()
+            };
+            let (_, dest) = open_store_with_timeout(engine_config(&dest_config, None, true)?, open_timeout)?;
+            Some(dest)
+        };
+
+        Ok(Self { source_meta_store, source, dest })
+    }
+
+    /// Copy every chunk whose ID starts with `id_prefix`, keeping its versions, and check each copy against the source checksum
+    pub fn migrate(&self, id_prefix: &[u8]) -> Result<MigrateReport> {
+        let mut report = MigrateReport::default();
+
+        // Chunk IDs are stored inverted, the keys of one ID prefix are still contiguous
+        let key_prefix = MetaKey::chunk_meta_key_with(self.source_meta_store.chunk_meta_key_prefix(), id_prefix);
+        let mut it = self.source_meta_store.iterator();
+        it.seek(&key_prefix)?;
+
+        let mut buffer = Vec::new();
+        loop {
+            if !it.valid() {
+                break;
+            }
+
+            let key = it.key().unwrap();
+            if !key.starts_with(key_prefix.as_ref()) {
+                break;
+            }
+
+            let chunk_id = MetaKey::parse_chunk_meta_key(key);
+            let Some(chunk) = self.source.get(&chunk_id)? else {
+                it.next();
+                continue;
+            };
+            let chunk_meta = chunk.meta().clone();
+
+            let Some(dest) = &self.dest else {
+                report.migrated_chunks += 1;
+                report.migrated_bytes += chunk_meta.len as u64;
+                it.next();
+                continue;
+            };
+
+            if dest.get(&chunk_id)?.is_some() {
+                report.existing_chunks += 1;
+                it.next();
+                continue;
+            }
+
+            buffer.resize(chunk_meta.len as usize, 0);
+            chunk.pread(&mut buffer, 0)?;
+            dest.put(&chunk_id, &buffer, chunk_meta.chain_ver, chunk_meta.chunk_ver)?;
+
+            if self.verify_copy(dest, &chunk_id, &chunk_meta, &mut buffer)? {
+                report.migrated_chunks += 1;
+                report.migrated_bytes += chunk_meta.len as u64;
+            } else {
+                println!("FAILED    {}", format_hex_chunk_id(&chunk_id));
+                report.failed_chunks.push(format_hex_chunk_id(&chunk_id));
+            }
+
+            it.next();
+        }
+
+        Ok(report)
+    }
+
+    /// Read the copy back from the destination and compare it with the source meta
+    fn verify_copy(&self, dest: &Engine, chunk_id: &[u8], source_meta: &ChunkMeta, buffer: &mut Vec<u8>) -> Result<bool> {
+        let Some(chunk) = dest.get(chunk_id)? else {
+            return Ok(false);
+        };
+        let dest_meta = chunk.meta();
+        if dest_meta.len != source_meta.len || dest_meta.chain_ver != source_meta.chain_ver || dest_meta.chunk_ver != source_meta.chunk_ver {
+            return Ok(false);
+        }
+
+        buffer.resize(dest_meta.len as usize, 0);
+        chunk.pread(buffer, 0)?;
+        Ok(compute_checksum(buffer) == source_meta.checksum)
+    }
+
+    pub fn display_report(&self, report: &MigrateReport) {
+        if self.dest.is_none() {
+            println!("=== Migration Plan (dry run) ===");
+            println!("Chunks to copy: {} ({})", report.migrated_chunks, format_size(report.migrated_bytes));
+            return;
+        }
+
+        println!("=== Migration Report ===");
+        println!("Copied chunks:  {} ({})", report.migrated_chunks, format_size(report.migrated_bytes));
+        println!("Already there:  {}", report.existing_chunks);
+        println!("Failed to verify: {}", report.failed_chunks.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::utils::meta_store_config;
+
+    fn create_engine(path: &Path) -> Engine {
+        let config = EngineConfig {
+            path: path.into(),
+            create: true,
+            prefix_len: 4,
+            ..Default::default()
+        };
+        Engine::open(&config).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_chunks() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let dest_dir = tempfile::tempdir().unwrap();
+        {
+            let engine = create_engine(source_dir.path());
+            for i in 0..8u32 {
+                // IDs 0x00.. and 0x01.., only the latter match the prefix
+                let chunk_id = ((i % 2) << 24 | i).to_be_bytes();
+                engine.put(&chunk_id, &vec![i as u8; 4096 * (i as usize + 1)], 2, i + 1).unwrap();
+            }
+            let dest = create_engine(dest_dir.path());
+            dest.put(&(1u32 << 24 | 7).to_be_bytes(), &[0u8; 10], 1, 1).unwrap();
+        }
+
+        let source_config = meta_store_config(&source_dir.path().join("meta"), DbOpenMode::ReadOnly);
+        let dest_path = dest_dir.path().join("meta");
+
+        let dry_run = ChunkMigrator::new(&source_config, None, &dest_path, true, None).unwrap();
+        let report = dry_run.migrate(&[1]).unwrap();
+        assert_eq!(report.migrated_chunks, 4);
+        assert_eq!(report.migrated_bytes, 4096 * (2 + 4 + 6 + 8));
+
+        let migrator = ChunkMigrator::new(&source_config, None, &dest_path, false, None).unwrap();
+        let report = migrator.migrate(&[1]).unwrap();
+        assert_eq!(report, MigrateReport {
+            migrated_chunks: 3,
+            migrated_bytes: 4096 * (2 + 4 + 6),
+            existing_chunks: 1,
+            failed_chunks: vec![],
+        });
+        assert_eq!(report.exit_status(), ExitStatus::Ok);
+
+        let dest = migrator.dest.as_ref().unwrap();
+        for i in [1u32, 3, 5] {
+            let chunk_id = (1 << 24 | i).to_be_bytes();
+            let source_meta = migrator.source.get(&chunk_id).unwrap().unwrap().meta().clone();
+            let dest_meta = dest.get(&chunk_id).unwrap().unwrap().meta().clone();
+            assert_eq!((dest_meta.len, dest_meta.checksum), (source_meta.len, source_meta.checksum));
+            assert_eq!((dest_meta.chain_ver, dest_meta.chunk_ver), (2, i + 1));
+        }
+        assert!(dest.get(&0u32.to_be_bytes()).unwrap().is_none());
+
+        // A second run finds everything in place
+        let report = migrator.migrate(&[1]).unwrap();
+        assert_eq!(report.migrated_chunks, 0);
+        assert_eq!(report.existing_chunks, 4);
+    }
+}
@@ -0,0 +1,157 @@
+use chunk_engine::*;
+use parquet::basic::Compression;
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, Int32Type};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use std::{fs::File, path::Path, sync::Arc};
+
+use super::utils::IdRange;
+
+/// The columns of an exported file, unsigned 32-bit values are stored as INT32 with an unsigned logical type
+const SCHEMA: &str = "
+    message chunk_meta {
+        REQUIRED BYTE_ARRAY chunk_id;
+        REQUIRED INT32 size_bucket (INTEGER(32, false));
+        REQUIRED INT32 len (INTEGER(32, false));
+        REQUIRED INT32 chain_ver (INTEGER(32, false));
+        REQUIRED INT32 chunk_ver (INTEGER(32, false));
+        REQUIRED INT32 checksum (INTEGER(32, false));
+        REQUIRED BOOLEAN uncommitted;
+    }
+";
+
+/// Rows buffered before they are written out as one row group
+const ROW_GROUP_ROWS: usize = 64 * 1024;
+
+/// One row group's worth of chunk metadata, column by column
+#[derive(Default)]
+struct RowGroup {
+    chunk_ids: Vec<ByteArray>,
+    /// size_bucket, len, chain_ver, chunk_ver and checksum
+    numbers: [Vec<i32>; 5],
+    uncommitted: Vec<bool>,
+}
+
+impl RowGroup {
+    fn push(&mut self, chunk_id: &[u8], chunk_meta: &ChunkMeta) {
+        self.chunk_ids.push(ByteArray::from(chunk_id.to_vec()));
+        let numbers = [chunk_meta.pos.chunk_size().0 as u32, chunk_meta.len, chunk_meta.chain_ver, chunk_meta.chunk_ver, chunk_meta.checksum];
+        for (column, value) in self.numbers.iter_mut().zip(numbers) {
+            // The bits of the u32, readers take them as unsigned from the logical type
+            column.push(value as i32);
+        }
+        self.uncommitted.push(chunk_meta.uncommitted);
+    }
+
+    fn len(&self) -> usize {
+        self.chunk_ids.len()
+    }
+
+    fn write(&mut self, writer: &mut SerializedFileWriter<File>) -> std::result::Result<(), ParquetError> {
+        let mut row_group = writer.next_row_group()?;
+        let mut index = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            match index {
+                0 => column.typed::<ByteArrayType>().write_batch(&self.chunk_ids, None, None)?,
+                1..=5 => column.typed::<Int32Type>().write_batch(&self.numbers[index - 1], None, None)?,
+                _ => column.typed::<BoolType>().write_batch(&self.uncommitted, None, None)?,
+            };
+            column.close()?;
+            index += 1;
+        }
+        row_group.close()?;
+        *self = Self::default();
+        Ok(())
+    }
+}
+
+/// Write the metadata of every chunk in `id_range` to a Parquet file, a row group at a time so the
+/// store is never held in memory. Returns the number of chunks written.
+pub fn export_parquet(meta_store: &MetaStore, path: &Path, id_range: &IdRange) -> Result<u64> {
+    let parquet_error = |e: ParquetError| Error::IoError(format!("Failed to write {}: {}", path.display(), e));
+    let schema = Arc::new(parse_message_type(SCHEMA).map_err(parquet_error)?);
+    let properties = Arc::new(WriterProperties::builder().set_compression(Compression::UNCOMPRESSED).build());
+    let file = File::create(path).map_err(|e| Error::IoError(format!("Failed to create {}: {}", path.display(), e)))?;
+    let mut writer = SerializedFileWriter::new(file, schema, properties).map_err(parquet_error)?;
+
+    let mut rows = RowGroup::default();
+    let mut chunks = 0u64;
+    for entry in meta_store.chunks() {
+        let (chunk_id, chunk_meta) = entry?;
+        if id_range.is_past(&chunk_id) {
+            break;
+        }
+        if !id_range.contains(&chunk_id) {
+            continue;
+        }
+        rows.push(&chunk_id, &chunk_meta);
+        chunks += 1;
+        if rows.len() == ROW_GROUP_ROWS {
+            rows.write(&mut writer).map_err(parquet_error)?;
+        }
+    }
+    if rows.len() > 0 {
+        rows.write(&mut writer).map_err(parquet_error)?;
+    }
+    writer.close().map_err(parquet_error)?;
+
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
+
+    #[test]
+    fn test_export_parquet() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().join("meta"),
+                create: true,
+                ..Default::default()
+            },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        // More chunks than one row group holds
+        let count = ROW_GROUP_ROWS as u32 + 10;
+        for i in 0..count {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(GroupId::new(CHUNK_SIZE_NORMAL, 0, i / 256), i as u8),
+                len: i,
+                chain_ver: 1,
+                chunk_ver: 2,
+                checksum: u32::MAX - i,
+                uncommitted: i % 2 == 1,
+                ..Default::default()
+            };
+            meta_store.add_chunk(&i.to_be_bytes(), &chunk_meta, false).unwrap();
+        }
+
+        let path = dir.path().join("chunks.parquet");
+        assert_eq!(export_parquet(&meta_store, &path, &IdRange::default()).unwrap(), count as u64);
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), count as i64);
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        // Keys are stored inverted, so the largest ID comes first
+        let row = reader.get_row_iter(None).unwrap().next().unwrap().unwrap();
+        let last = count - 1;
+        assert_eq!(row.get_bytes(0).unwrap().data(), last.to_be_bytes());
+        assert_eq!(row.get_uint(1).unwrap(), CHUNK_SIZE_NORMAL.0 as u32);
+        assert_eq!(row.get_uint(2).unwrap(), last);
+        assert_eq!((row.get_uint(3).unwrap(), row.get_uint(4).unwrap()), (1, 2));
+        // Above i32::MAX, read back unsigned
+        assert_eq!(row.get_uint(5).unwrap(), u32::MAX - last);
+        assert!(row.get_bool(6).unwrap());
+
+        let id_range = IdRange::parse(Some("00000010"), Some("00000020")).unwrap();
+        assert_eq!(export_parquet(&meta_store, &path, &id_range).unwrap(), 16);
+    }
+}
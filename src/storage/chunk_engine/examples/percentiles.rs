@@ -0,0 +1,286 @@
+use serde::Serialize;
+
+/// The percentiles reported for chunk lengths
+pub const PERCENTILES: [f64; 3] = [0.5, 0.9, 0.99];
+
+/// Streaming estimate of one quantile with the P² algorithm (Jain and Chlamtac, 1985).
+/// It keeps five markers instead of the samples, moving the middle ones towards their
+/// ideal positions with a piecewise-parabolic fit as samples arrive.
+#[derive(Debug, Clone)]
+pub struct P2Quantile {
+    p: f64,
+    count: usize,
+    /// Marker heights, the first five samples until the markers are set up
+    heights: [f64; 5],
+    /// Actual marker positions, 1-based
+    positions: [f64; 5],
+    /// Desired marker positions and how much they move per sample
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+impl P2Quantile {
+    pub fn new(p: f64) -> Self {
+        Self {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    pub fn add(&mut self, x: f64) {
+        if self.count < 5 {
+            self.heights[self.count] = x;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(f64::total_cmp);
+            }
+            return;
+        }
+        self.count += 1;
+
+        // Find the cell holding x, extending the extreme markers if it falls outside them
+        let q = &mut self.heights;
+        let k = if x < q[0] {
+            q[0] = x;
+            0
+        } else if x >= q[4] {
+            q[4] = x;
+            3
+        } else {
+            (1..5).find(|&i| x < q[i]).unwrap() - 1
+        };
+        for position in &mut self.positions[k + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(self.increments) {
+            *desired += increment;
+        }
+
+        for i in 1..4 {
+            let n = self.positions;
+            let d = self.desired[i] - n[i];
+            if (d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0) {
+                let d = d.signum();
+                let parabolic = self.parabolic(i, d);
+                self.heights[i] = if self.heights[i - 1] < parabolic && parabolic < self.heights[i + 1] {
+                    parabolic
+                } else {
+                    self.linear(i, d)
+                };
+                self.positions[i] += d;
+            }
+        }
+    }
+
+    fn parabolic(&self, i: usize, d: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + d / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    fn linear(&self, i: usize, d: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        let j = if d > 0.0 { i + 1 } else { i - 1 };
+        q[i] + d * (q[j] - q[i]) / (n[j] - n[i])
+    }
+
+    /// The estimated quantile, exact while there are no more than five samples
+    pub fn estimate(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            1..=5 => {
+                let mut samples = self.heights[..self.count].to_vec();
+                samples.sort_by(f64::total_cmp);
+                Some(samples[nearest_rank(self.p, samples.len())])
+            }
+            _ => Some(self.heights[2]),
+        }
+    }
+}
+
+/// Streaming mean and population standard deviation with Welford's algorithm, which stays accurate over
+/// millions of samples where summing squares would lose precision
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RunningStats {
+    count: u64,
+    mean: f64,
+    /// Sum of squared differences from the mean
+    m2: f64,
+}
+
+impl RunningStats {
+    pub fn add(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The mean, 0 without samples
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// The population standard deviation, 0 without samples
+    pub fn stddev(&self) -> f64 {
+        if self.count == 0 { 0.0 } else { (self.m2 / self.count as f64).sqrt() }
+    }
+}
+
+/// Index of the `p` quantile in `len` sorted samples
+fn nearest_rank(p: f64, len: usize) -> usize {
+    ((p * len as f64).ceil() as usize).clamp(1, len) - 1
+}
+
+/// Percentiles of chunk lengths, in bytes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LengthPercentiles {
+    pub chunks: u64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+    /// Whether p50 to p99 are estimates, `max` is always exact
+    pub approximate: bool,
+}
+
+/// Collects chunk lengths, either into P² estimators in constant memory or into a buffer for exact percentiles
+pub enum LengthCollector {
+    Approximate { estimators: Vec<P2Quantile>, chunks: u64, max: u64 },
+    Exact(Vec<u32>),
+}
+
+impl LengthCollector {
+    pub fn new(exact: bool) -> Self {
+        if exact {
+            Self::Exact(Vec::new())
+        } else {
+            Self::Approximate { estimators: PERCENTILES.iter().map(|&p| P2Quantile::new(p)).collect(), chunks: 0, max: 0 }
+        }
+    }
+
+    pub fn add(&mut self, len: u32) {
+        match self {
+            Self::Approximate { estimators, chunks, max } => {
+                for estimator in estimators {
+                    estimator.add(len as f64);
+                }
+                *chunks += 1;
+                *max = (*max).max(len as u64);
+            }
+            Self::Exact(lengths) => lengths.push(len),
+        }
+    }
+
+    /// The percentiles of the lengths added so far, `None` if there were none
+    pub fn finish(self) -> Option<LengthPercentiles> {
+        let (chunks, values, max, approximate) = match self {
+            Self::Approximate { estimators, chunks, max } => {
+                let values: Option<Vec<u64>> = estimators.iter().map(|estimator| estimator.estimate().map(|value| value.round() as u64)).collect();
+                (chunks, values?, max, true)
+            }
+            Self::Exact(mut lengths) => {
+                lengths.sort_unstable();
+                let max = *lengths.last()? as u64;
+                let values: Vec<u64> = PERCENTILES.iter().map(|&p| lengths[nearest_rank(p, lengths.len())] as u64).collect();
+                (lengths.len() as u64, values, max, false)
+            }
+        };
+        Some(LengthPercentiles { chunks, p50: values[0], p90: values[1], p99: values[2], max, approximate })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fixed permutation of 0..n, so the samples arrive in no particular order
+    fn shuffled(n: u64) -> impl Iterator<Item = u64> {
+        // 7919 is prime and does not divide n, so i * 7919 mod n visits every value once
+        (0..n).map(move |i| i * 7919 % n)
+    }
+
+    fn percentiles(lengths: impl Iterator<Item = u32>) -> (LengthPercentiles, LengthPercentiles) {
+        let mut approximate = LengthCollector::new(false);
+        let mut exact = LengthCollector::new(true);
+        for len in lengths {
+            approximate.add(len);
+            exact.add(len);
+        }
+        (approximate.finish().unwrap(), exact.finish().unwrap())
+    }
+
+    fn assert_close(estimate: u64, exact: u64, tolerance: f64) {
+        let error = (estimate as f64 - exact as f64).abs() / exact as f64;
+        assert!(error <= tolerance, "estimate {} exact {} error {:.4}", estimate, exact, error);
+    }
+
+    #[test]
+    fn test_running_stats() {
+        let samples: Vec<f64> = shuffled(1000).map(|i| (i % 7) as f64 * 12.5 + 1e6).collect();
+        let mut stats = RunningStats::default();
+        samples.iter().for_each(|&x| stats.add(x));
+        
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert_eq!(stats.count(), 1000);
+        assert!((stats.mean() - mean).abs() < 1e-6, "{:?}", stats);
+        assert!((stats.stddev() - variance.sqrt()).abs() < 1e-6, "{:?}", stats);
+        
+        // All at 50% against half at 0% and half at 100%, the same mean
+        let mut even = RunningStats::default();
+        let mut mixed = RunningStats::default();
+        for i in 0..10 {
+            even.add(50.0);
+            mixed.add(if i % 2 == 0 { 0.0 } else { 100.0 });
+        }
+        assert_eq!((even.mean(), even.stddev()), (50.0, 0.0));
+        assert_eq!((mixed.mean(), mixed.stddev()), (50.0, 50.0));
+        assert_eq!(RunningStats::default().stddev(), 0.0);
+    }
+
+    #[test]
+    fn test_uniform_lengths() {
+        let (approximate, exact) = percentiles(shuffled(100_000).map(|i| i as u32));
+        assert_eq!((exact.p50, exact.p90, exact.p99, exact.max), (49_999, 89_999, 98_999, 99_999));
+        assert_eq!((approximate.chunks, approximate.max), (100_000, 99_999));
+        assert!(approximate.approximate && !exact.approximate);
+        for (estimate, exact) in [(approximate.p50, exact.p50), (approximate.p90, exact.p90), (approximate.p99, exact.p99)] {
+            assert_close(estimate, exact, 0.01);
+        }
+    }
+
+    #[test]
+    fn test_skewed_lengths() {
+        // Mostly small chunks with a long tail of large ones, like a store of many small files
+        let lengths = shuffled(50_000).map(|i| {
+            let u = (i as f64 + 0.5) / 50_000.0;
+            (-u.ln() * 64.0 * 1024.0) as u32
+        });
+        let (approximate, exact) = percentiles(lengths);
+        for (estimate, exact) in [(approximate.p50, exact.p50), (approximate.p90, exact.p90), (approximate.p99, exact.p99)] {
+            assert_close(estimate, exact, 0.05);
+        }
+        assert_eq!(approximate.max, exact.max);
+    }
+
+    #[test]
+    fn test_few_lengths() {
+        assert!(LengthCollector::new(false).finish().is_none());
+        assert!(LengthCollector::new(true).finish().is_none());
+
+        // Up to five samples the estimate is exact
+        let (approximate, exact) = percentiles([30, 10, 20].into_iter());
+        assert_eq!((approximate.p50, approximate.p90, approximate.max), (20, 30, 30));
+        assert_eq!((exact.p50, exact.p90, exact.max), (20, 30, 30));
+    }
+}
@@ -0,0 +1,92 @@
+use chunk_engine::*;
+use serde::Serialize;
+
+use super::args::OutputFormat;
+use super::utils::{format_size, to_json, to_json_line};
+
+/// Properties shown by default, and whether each one is a size in bytes
+const DEFAULT_PROPERTIES: &[(&str, bool)] = &[
+    ("rocksdb.estimate-num-keys", false),
+    ("rocksdb.total-sst-files-size", true),
+    ("rocksdb.live-sst-files-size", true),
+    ("rocksdb.estimate-live-data-size", true),
+    ("rocksdb.num-files-at-level0", false),
+    ("rocksdb.num-files-at-level1", false),
+    ("rocksdb.num-files-at-level2", false),
+    ("rocksdb.num-files-at-level3", false),
+    ("rocksdb.num-files-at-level4", false),
+    ("rocksdb.num-files-at-level5", false),
+    ("rocksdb.num-files-at-level6", false),
+    ("rocksdb.cur-size-all-mem-tables", true),
+    ("rocksdb.estimate-table-readers-mem", true),
+    ("rocksdb.block-cache-capacity", true),
+    ("rocksdb.block-cache-usage", true),
+    ("rocksdb.block-cache-pinned-usage", true),
+];
+
+/// Multi-line property with per-level file counts and sizes
+const LEVEL_STATS_PROPERTY: &str = "rocksdb.levelstats";
+
+#[derive(Serialize)]
+struct Property {
+    name: String,
+    /// `None` when RocksDB does not know the property
+    value: Option<String>,
+}
+
+/// Print the curated RocksDB properties, or only `names` when any are given
+pub fn show_rocksdb_stats(meta_store: &MetaStore, names: &[String], output_format: OutputFormat) -> Result<()> {
+    let curated = names.is_empty();
+    let properties: Vec<(Property, bool)> = if curated {
+        DEFAULT_PROPERTIES.iter().map(|&(name, is_size)| (read_property(meta_store, name), is_size)).collect()
+    } else {
+        names.iter().map(|name| (read_property(meta_store, name), false)).collect()
+    };
+
+    match output_format {
+        OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {}
+        OutputFormat::Json => {
+            let properties: Vec<_> = properties.into_iter().map(|(property, _)| property).collect();
+            println!("{}", to_json(&properties)?);
+            return Ok(());
+        }
+        OutputFormat::JsonLines => {
+            for (property, _) in &properties {
+                println!("{}", to_json_line(property)?);
+            }
+            return Ok(());
+        }
+    }
+
+    println!("=== RocksDB Statistics ===");
+    let width = properties.iter().map(|(property, _)| property.name.len()).max().unwrap_or(0);
+    for (property, is_size) in &properties {
+        let value = match (&property.value, is_size) {
+            (None, _) => "(unknown property)".to_string(),
+            (Some(value), true) => match value.parse::<u64>() {
+                Ok(bytes) => format!("{} ({} bytes)", format_size(bytes), bytes),
+                Err(_) => value.clone(),
+            },
+            (Some(value), false) => value.trim_end().to_string(),
+        };
+        println!("  {:<width$}  {}", property.name, value, width = width);
+    }
+
+    if curated {
+        if let Some(level_stats) = meta_store.property(LEVEL_STATS_PROPERTY) {
+            println!("\nLevel statistics:");
+            for line in level_stats.lines() {
+                println!("  {}", line);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_property(meta_store: &MetaStore, name: &str) -> Property {
+    Property {
+        name: name.to_string(),
+        value: meta_store.property(name),
+    }
+}
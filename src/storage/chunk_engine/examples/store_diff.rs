@@ -0,0 +1,191 @@
+use chunk_engine::*;
+use derse::Deserialize;
+
+use super::exit_status::ExitStatus;
+use super::utils::{format_checksum, format_hex_chunk_id};
+
+/// A chunk both stores hold with different metadata
+#[derive(Debug, PartialEq, Eq)]
+pub struct DifferingChunk {
+    pub chunk_id: String,
+    pub meta_a: ChunkMeta,
+    pub meta_b: ChunkMeta,
+}
+
+impl DifferingChunk {
+    /// The fields that differ, as "name a -> b"
+    fn changes(&self) -> Vec<String> {
+        let (a, b) = (&self.meta_a, &self.meta_b);
+        let mut changes = vec![];
+        if a.len != b.len {
+            changes.push(format!("len {} -> {}", a.len, b.len));
+        }
+        if a.checksum != b.checksum {
+            changes.push(format!("checksum {} -> {}", format_checksum(a.checksum), format_checksum(b.checksum)));
+        }
+        if a.chain_ver != b.chain_ver {
+            changes.push(format!("chain_ver {} -> {}", a.chain_ver, b.chain_ver));
+        }
+        if a.chunk_ver != b.chunk_ver {
+            changes.push(format!("chunk_ver {} -> {}", a.chunk_ver, b.chunk_ver));
+        }
+        changes
+    }
+}
+
+/// Differences between the chunk metadata of two stores, each list in store order
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct StoreDiff {
+    pub common_chunks: u64,
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    pub differing: Vec<DifferingChunk>,
+}
+
+impl StoreDiff {
+    pub fn exit_status(&self) -> ExitStatus {
+        if self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.differing.is_empty() {
+            ExitStatus::Ok
+        } else {
+            ExitStatus::Mismatch
+        }
+    }
+}
+
+/// Walk the chunk metas of both stores side by side, both are sorted by key so one pass finds every difference
+pub fn diff_stores(a: &MetaStore, b: &MetaStore) -> Result<StoreDiff> {
+    let mut diff = StoreDiff::default();
+    let mut it_a = ChunkKeyCursor::new(a)?;
+    let mut it_b = ChunkKeyCursor::new(b)?;
+
+    loop {
+        match (it_a.peek(), it_b.peek()) {
+            (None, None) => break,
+            (Some(_), None) => diff.only_in_a.push(it_a.take_id()),
+            (None, Some(_)) => diff.only_in_b.push(it_b.take_id()),
+            (Some(key_a), Some(key_b)) => match key_a.cmp(key_b) {
+                std::cmp::Ordering::Less => diff.only_in_a.push(it_a.take_id()),
+                std::cmp::Ordering::Greater => diff.only_in_b.push(it_b.take_id()),
+                std::cmp::Ordering::Equal => {
+                    let (chunk_id, meta_a) = it_a.take()?;
+                    let (_, meta_b) = it_b.take()?;
+                    diff.common_chunks += 1;
+                    if (meta_a.len, meta_a.checksum, meta_a.chain_ver, meta_a.chunk_ver)
+                        != (meta_b.len, meta_b.checksum, meta_b.chain_ver, meta_b.chunk_ver)
+                    {
+                        diff.differing.push(DifferingChunk { chunk_id, meta_a, meta_b });
+                    }
+                }
+            },
+        }
+    }
+
+    Ok(diff)
+}
+
+pub fn display_store_diff(diff: &StoreDiff, name_a: &str, name_b: &str) {
+    println!("=== Store Diff ===");
+    println!("A: {}", name_a);
+    println!("B: {}", name_b);
+
+    println!("\nOnly in A ({}):", diff.only_in_a.len());
+    for chunk_id in &diff.only_in_a {
+        println!("  {}", chunk_id);
+    }
+
+    println!("\nOnly in B ({}):", diff.only_in_b.len());
+    for chunk_id in &diff.only_in_b {
+        println!("  {}", chunk_id);
+    }
+
+    println!("\nDiffering ({}):", diff.differing.len());
+    for chunk in &diff.differing {
+        println!("  {}  {}", chunk.chunk_id, chunk.changes().join(", "));
+    }
+
+    println!("\nTotal: {} chunks in both, {} only in A, {} only in B, {} differing",
+        diff.common_chunks, diff.only_in_a.len(), diff.only_in_b.len(), diff.differing.len());
+}
+
+/// Cursor over the chunk meta keys of a store that can look at the next key without consuming or decoding it
+struct ChunkKeyCursor<'a> {
+    it: RocksDBIterator<'a>,
+    /// First byte of the store's chunk meta keys
+    mark: u8,
+}
+
+impl<'a> ChunkKeyCursor<'a> {
+    fn new(meta_store: &'a MetaStore) -> Result<Self> {
+        let mut it = meta_store.iterator();
+        let mark = meta_store.chunk_meta_key_prefix();
+        let end_key = MetaKey::chunk_meta_key_with(mark, &[]);
+        it.seek(&end_key)?;
+        if it.key() == Some(end_key.as_ref()) {
+            it.next(); // [begin, end)
+        }
+        Ok(Self { it, mark })
+    }
+
+    fn peek(&self) -> Option<&[u8]> {
+        self.it.key().filter(|key| key[0] == self.mark)
+    }
+
+    fn take_id(&mut self) -> String {
+        let chunk_id = format_hex_chunk_id(&MetaKey::parse_chunk_meta_key(self.it.key().unwrap()));
+        self.it.next();
+        chunk_id
+    }
+
+    fn take(&mut self) -> Result<(String, ChunkMeta)> {
+        let chunk_meta = ChunkMeta::deserialize(self.it.value().unwrap()).map_err(Error::SerializationError)?;
+        Ok((self.take_id(), chunk_meta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_meta_store(path: &std::path::Path, chunks: &[(u32, ChunkMeta)]) -> MetaStore {
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: path.into(),
+                create: true,
+                ..Default::default()
+            },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        for (i, (chunk_id, chunk_meta)) in chunks.iter().enumerate() {
+            let chunk_meta = ChunkMeta { pos: Position::new(GroupId::default(), i as u8), ..chunk_meta.clone() };
+            meta_store.add_chunk(&chunk_id.to_be_bytes(), &chunk_meta, false).unwrap();
+        }
+        meta_store
+    }
+
+    #[test]
+    fn test_diff_stores() {
+        let meta = |len, checksum, chunk_ver| ChunkMeta { len, checksum, chain_ver: 1, chunk_ver, ..Default::default() };
+        let dir_a = tempfile::tempdir().unwrap();
+        let dir_b = tempfile::tempdir().unwrap();
+        let a = create_meta_store(dir_a.path(), &[(1, meta(10, 1, 1)), (2, meta(20, 2, 1)), (3, meta(30, 3, 1)), (5, meta(50, 5, 1))]);
+        let b = create_meta_store(dir_b.path(), &[(2, meta(20, 2, 1)), (3, meta(31, 4, 1)), (4, meta(40, 4, 1)), (5, meta(50, 5, 2))]);
+
+        let diff = diff_stores(&a, &b).unwrap();
+        assert_eq!(diff.common_chunks, 3);
+        assert_eq!(diff.only_in_a, [format_hex_chunk_id(&1u32.to_be_bytes())]);
+        assert_eq!(diff.only_in_b, [format_hex_chunk_id(&4u32.to_be_bytes())]);
+        let differing: Vec<_> = diff.differing.iter().map(|chunk| (chunk.chunk_id.clone(), chunk.changes())).collect();
+        // Keys are stored inverted, so larger IDs come first
+        assert_eq!(differing, [
+            (format_hex_chunk_id(&5u32.to_be_bytes()), vec!["chunk_ver 1 -> 2".to_string()]),
+            (format_hex_chunk_id(&3u32.to_be_bytes()), vec!["len 30 -> 31".to_string(), "checksum 0x00000003 -> 0x00000004".to_string()]),
+        ]);
+        assert_eq!(diff.exit_status(), ExitStatus::Mismatch);
+
+        let diff = diff_stores(&a, &a).unwrap();
+        assert_eq!(diff, StoreDiff { common_chunks: 4, ..Default::default() });
+        assert_eq!(diff.exit_status(), ExitStatus::Ok);
+    }
+}
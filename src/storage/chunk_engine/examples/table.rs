@@ -0,0 +1,100 @@
+/// Horizontal alignment of a table column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// A table column: header, alignment, and the widest a cell may get before it is truncated
+pub struct Column {
+    pub header: &'static str,
+    pub align: Align,
+    pub max_width: Option<usize>,
+}
+
+impl Column {
+    pub fn new(header: &'static str, align: Align) -> Self {
+        Self { header, align, max_width: None }
+    }
+
+    pub fn with_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+}
+
+/// Render rows with Unicode box-drawing borders, each column sized to its widest cell
+pub fn render_box_table(columns: &[Column], rows: &[Vec<String>]) -> String {
+    render_box_table_with(columns, rows, &|_, _, cell| cell)
+}
+
+/// Like `render_box_table`, passing each padded body cell with its row and column index through `paint`,
+/// so escape codes can be added without counting towards the column widths
+pub fn render_box_table_with(columns: &[Column], rows: &[Vec<String>], paint: &dyn Fn(usize, usize, String) -> String) -> String {
+    let cells: Vec<Vec<String>> = rows.iter()
+        .map(|row| row.iter().zip(columns).map(|(cell, column)| truncate(cell, column.max_width)).collect())
+        .collect();
+
+    // First pass: size every column to fit its header and cells
+    let widths: Vec<usize> = columns.iter().enumerate()
+        .map(|(i, column)| cells.iter().map(|row| row[i].chars().count()).chain([column.header.chars().count()]).max().unwrap_or(0))
+        .collect();
+
+    let border = |left: &str, middle: &str, right: &str| {
+        let segments: Vec<String> = widths.iter().map(|width| "─".repeat(width + 2)).collect();
+        format!("{}{}{}\n", left, segments.join(middle), right)
+    };
+    let line = |row: &[String], aligns: &mut dyn Iterator<Item = Align>, paint: &dyn Fn(usize, String) -> String| {
+        let cells: Vec<String> = row.iter().zip(&widths).zip(aligns).enumerate()
+            .map(|(i, ((cell, &width), align))| match align {
+                Align::Left => format!(" {} ", paint(i, format!("{:<width$}", cell, width = width))),
+                Align::Right => format!(" {} ", paint(i, format!("{:>width$}", cell, width = width))),
+            })
+            .collect();
+        format!("│{}│\n", cells.join("│"))
+    };
+
+    let headers: Vec<String> = columns.iter().map(|column| column.header.to_string()).collect();
+    let mut output = border("┌", "┬", "┐");
+    output.push_str(&line(&headers, &mut std::iter::repeat(Align::Left), &|_, cell| cell));
+    output.push_str(&border("├", "┼", "┤"));
+    for (i, row) in cells.iter().enumerate() {
+        output.push_str(&line(row, &mut columns.iter().map(|column| column.align), &|column, cell| paint(i, column, cell)));
+    }
+    output.push_str(&border("└", "┴", "┘"));
+    output
+}
+
+fn truncate(cell: &str, max_width: Option<usize>) -> String {
+    match max_width {
+        Some(max_width) if cell.chars().count() > max_width => {
+            let kept: String = cell.chars().take(max_width.saturating_sub(1)).collect();
+            format!("{}…", kept)
+        }
+        _ => cell.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_box_table() {
+        let columns = [Column::new("ID", Align::Left).with_max_width(6), Column::new("Len", Align::Right)];
+        let rows = vec![
+            vec!["abcdef0123".to_string(), "4096".to_string()],
+            vec!["ab".to_string(), "1".to_string()],
+        ];
+        let output = render_box_table(&columns, &rows);
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines, [
+            "┌────────┬──────┐",
+            "│ ID     │ Len  │",
+            "├────────┼──────┤",
+            "│ abcde… │ 4096 │",
+            "│ ab     │    1 │",
+            "└────────┴──────┘",
+        ]);
+    }
+}
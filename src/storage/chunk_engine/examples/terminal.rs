@@ -0,0 +1,97 @@
+use chunk_engine::*;
+use std::io::{IsTerminal, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Check whether stdout is attached to a terminal
+pub fn is_stdout_tty() -> bool {
+    std::io::stdout().is_terminal()
+}
+
+/// Check whether stdin is attached to a terminal
+pub fn is_stdin_tty() -> bool {
+    std::io::stdin().is_terminal()
+}
+
+/// ANSI foreground colors used to highlight values in tables and warnings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+}
+
+/// Wrap `text` in the escape codes of `color`, callers check `--color` first
+pub fn paint(text: &str, color: Color) -> String {
+    let code = match color {
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+/// Clear the screen and move the cursor to the top-left corner
+pub fn clear_screen() {
+    print!("\x1b[2J\x1b[H");
+    let _ = std::io::stdout().flush();
+}
+
+/// Hide the cursor while a screen is redrawn in place
+pub fn hide_cursor() {
+    print!("\x1b[?25l");
+    let _ = std::io::stdout().flush();
+}
+
+/// Show the cursor again
+pub fn show_cursor() {
+    print!("\x1b[?25h");
+    let _ = std::io::stdout().flush();
+}
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Catch Ctrl-C so long running loops can stop and restore the terminal
+pub fn install_interrupt_handler() {
+    let handler = on_interrupt as extern "C" fn(libc::c_int);
+    unsafe { libc::signal(libc::SIGINT, handler as libc::sighandler_t) };
+}
+
+/// Check whether Ctrl-C was pressed since the handler was installed
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Read a single keypress from stdin without waiting for Enter
+pub fn read_key() -> Result<u8> {
+    let fd = libc::STDIN_FILENO;
+    let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+    if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+        return Err(Error::IoError(format!(
+            "Failed to read terminal attributes: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    // Switch to non-canonical mode without echo, restore afterwards.
+    let mut raw = original;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 1;
+    raw.c_cc[libc::VTIME] = 0;
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+        return Err(Error::IoError(format!(
+            "Failed to set terminal attributes: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    let mut buf = [0u8; 1];
+    let result = std::io::stdin().read_exact(&mut buf);
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original) };
+
+    result.map_err(|e| Error::IoError(format!("Failed to read key: {}", e)))?;
+    Ok(buf[0])
+}
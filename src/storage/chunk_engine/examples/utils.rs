@@ -1,4 +1,14 @@
 use chunk_engine::*;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Read, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 /// Parse a size string like "64KB", "8MB", "1GB" into bytes
 pub fn parse_size_string(size_str: &str) -> Result<u32> {
@@ -35,26 +45,332 @@ pub fn parse_size_string(size_str: &str) -> Result<u32> {
     Ok(bytes)
 }
 
+/// Check that a parsed size is exactly one of the allocator's size buckets
+pub fn bucket_size(size: u32) -> Result<Size> {
+    let size = Size::from(size);
+    if !size.is_valid_bucket() {
+        let buckets: Vec<String> = Size::all_buckets().map(|bucket| format_size_with(bucket.0, SizeFormat { trim_exact: true, ..Default::default() })).collect();
+        return Err(Error::InvalidArg(format!("{} is not a chunk size bucket, valid buckets are: {}", format_size(size.0), buckets.join(", "))));
+    }
+    Ok(size)
+}
+
+/// Round a parsed size up to the smallest size bucket that can hold it
+pub fn nearest_bucket(size: u32) -> Result<Size> {
+    Size::all_buckets()
+        .find(|bucket| *bucket >= Size::from(size))
+        .ok_or_else(|| Error::InvalidArg(format!("{} is larger than the largest size bucket {}", format_size(size as u64), format_size(CHUNK_SIZE_ULTRA.0))))
+}
+
+/// Resolve the engine's data directory and metadata path from the RocksDB path given on the
+/// command line. The data directory defaults to the parent of the canonicalized RocksDB path.
+pub fn engine_paths(rocksdb_path: &Path, data_dir: Option<&Path>) -> Result<(PathBuf, PathBuf)> {
+    // A store that is about to be created does not exist yet and cannot be canonicalized
+    let meta_path = std::fs::canonicalize(rocksdb_path)
+        .or_else(|_| std::path::absolute(rocksdb_path))
+        .map_err(|e| Error::InvalidArg(format!("Invalid RocksDB path {}: {}", rocksdb_path.display(), e)))?;
+    
+    let data_dir = match data_dir {
+        Some(data_dir) => data_dir.to_path_buf(),
+        None => meta_path.parent()
+            .ok_or_else(|| Error::InvalidArg(format!("RocksDB path {} has no parent directory, use --data-dir", meta_path.display())))?
+            .to_path_buf(),
+    };
+    
+    Ok((data_dir, meta_path))
+}
+
+/// Chunk ID prefix length of the stores this tool opens
+pub const PREFIX_LEN: usize = 4;
+
+/// Meta store config for the RocksDB at `rocksdb_path` with the default key layout
+pub fn meta_store_config(rocksdb_path: &Path, mode: DbOpenMode) -> MetaStoreConfig {
+    MetaStoreConfig {
+        rocksdb: RocksDBConfig { path: rocksdb_path.into(), mode, ..Default::default() },
+        prefix_len: PREFIX_LEN,
+        ..Default::default()
+    }
+}
+
+/// Engine config for the meta store of `meta_config`, see `engine_paths`. Its chunk meta key layout is kept.
+pub fn engine_config(meta_config: &MetaStoreConfig, data_dir: Option<&Path>, create: bool) -> Result<EngineConfig> {
+    let (path, meta_path) = engine_paths(&meta_config.rocksdb.path, data_dir)?;
+    Ok(EngineConfig {
+        path,
+        create,
+        prefix_len: meta_config.prefix_len,
+        mode: meta_config.rocksdb.mode.clone(),
+        meta_path: Some(meta_path),
+        chunk_meta_key_prefix: Some(meta_config.chunk_meta_key_prefix),
+        rocksdb_tuning: meta_config.rocksdb.tuning.clone(),
+    })
+}
+
+/// Run `open`, an open of the store in `mode`, on its own thread and return `Error::Timeout` if it has not
+/// finished within `timeout`. Without a timeout `open` runs inline.
+/// A timed-out open is left running detached and its result is dropped when it finishes. Only read-only and
+/// secondary opens may time out, as they write nothing to the store. A read-write open left running would
+/// keep the RocksDB lock and could still create the store's directories after the tool gave up, so a timeout
+/// for one is rejected.
+pub fn open_with_timeout<T: Send + 'static>(what: &str, mode: &DbOpenMode, timeout: Option<Duration>, open: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    let Some(timeout) = timeout else {
+        return open();
+    };
+    if *mode == DbOpenMode::ReadWrite {
+        return Err(Error::InvalidArg(format!("--open-timeout only applies to read-only opens, {} is opened for writing", what)));
+    }
+    
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        // The receiver is gone if we already gave up
+        let _ = sender.send(open());
+    });
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(_) => Err(Error::Timeout(format!("opening {} did not finish within {}s", what, timeout.as_secs_f64()))),
+    }
+}
+
+/// Open the meta store, giving up after `timeout`, see `open_with_timeout`
+pub fn open_meta_store(config: &MetaStoreConfig, timeout: Option<Duration>) -> Result<MetaStore> {
+    let (what, mode) = (config.rocksdb.path.display().to_string(), config.rocksdb.mode.clone());
+    let config = config.clone();
+    open_with_timeout(&what, &mode, timeout, move || MetaStore::open(&config))
+}
+
+/// The first bytes of every gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// A file written by `--output-file` or `--corrupt-manifest`, compressed on the fly with `--gzip`. Call `finish`
+/// when done, a gzip stream is only complete once its trailer is written
+pub struct OutputWriter {
+    path: String,
+    inner: OutputInner,
+}
+
+enum OutputInner {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl OutputWriter {
+    /// Create the file at `path`, and its missing parent directories with `mkdir_parents`
+    pub fn create(path: &str, gzip: bool, mkdir_parents: bool) -> Result<Self> {
+        prepare_output_path(path, mkdir_parents)?;
+        let file = BufWriter::new(File::create(path).map_err(|e| create_error(path, e))?);
+        let inner = if gzip { OutputInner::Gzip(GzEncoder::new(file, Compression::default())) } else { OutputInner::Plain(file) };
+        Ok(Self { path: path.to_string(), inner })
+    }
+    
+    /// An error for a failed write to this file
+    pub fn error(&self, e: std::io::Error) -> Error {
+        Error::IoError(format!("Failed to write to {}: {}", self.path, e))
+    }
+    
+    /// Write the gzip trailer if compressing and flush everything to the file
+    pub fn finish(self) -> Result<()> {
+        let path = self.path;
+        let result = match self.inner {
+            OutputInner::Plain(mut file) => file.flush(),
+            OutputInner::Gzip(encoder) => encoder.finish().and_then(|mut file| file.flush()),
+        };
+        result.map_err(|e| Error::IoError(format!("Failed to write to {}: {}", path, e)))
+    }
+}
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.inner {
+            OutputInner::Plain(file) => file.write(buf),
+            OutputInner::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.inner {
+            OutputInner::Plain(file) => file.flush(),
+            OutputInner::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// An error for a file or directory that could not be created, a permission problem is named as such
+fn create_error(path: &str, e: std::io::Error) -> Error {
+    if e.kind() == std::io::ErrorKind::PermissionDenied {
+        Error::IoError(format!("Cannot create {}: permission denied", path))
+    } else {
+        Error::IoError(format!("Failed to create {}: {}", path, e))
+    }
+}
+
+/// Check that a file can be created at `path` before creating it, so a directory or a missing parent is reported as
+/// such rather than as a bare OS error. Missing parent directories are created with `mkdir_parents`
+fn prepare_output_path(path: &str, mkdir_parents: bool) -> Result<()> {
+    let target = Path::new(path);
+    if target.is_dir() {
+        return Err(Error::IoError(format!("Cannot write to {}: it is a directory, give a file name", path)));
+    }
+    
+    // A bare file name has an empty parent, the current directory
+    let parent = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => parent,
+        _ => return Ok(()),
+    };
+    if parent.exists() {
+        return Err(Error::IoError(format!("Cannot create {}: {} is not a directory", path, parent.display())));
+    }
+    if !mkdir_parents {
+        return Err(Error::IoError(format!(
+            "Cannot create {}: directory {} does not exist, pass --mkdir-parents to create it", path, parent.display())));
+    }
+    std::fs::create_dir_all(parent).map_err(|e| create_error(&parent.display().to_string(), e))
+}
+
+/// Read a text file that may have been written with `--gzip`, told apart by the gzip magic bytes
+pub fn read_text_file(path: &str) -> std::io::Result<String> {
+    let bytes = std::fs::read(path)?;
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return String::from_utf8(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+    }
+    let mut text = String::new();
+    GzDecoder::new(&bytes[..]).read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Open the meta store and the engine on top of it from one config with `open_store_with`, giving up after
+/// `timeout`, see `open_with_timeout`
+pub fn open_store_with_timeout(config: EngineConfig, timeout: Option<Duration>) -> Result<(Arc<MetaStore>, Engine)> {
+    let (what, mode) = (config.meta_path.as_ref().unwrap_or(&config.path).display().to_string(), config.mode.clone());
+    open_with_timeout(&what, &mode, timeout, move || open_store_with(&config))
+}
+
+/// How `format_size_with` renders a size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SizeFormat {
+    /// Drop the ".00" of exact multiples of a unit, e.g. "4 MB" instead of "4.00 MB"
+    pub trim_exact: bool,
+    /// Spell out the base unit, e.g. "1 byte" and "2 bytes" instead of "1 B" and "2 B"
+    pub spell_bytes: bool,
+    /// Decimal places of sizes above the base unit, 2 if not given
+    pub precision: Option<usize>,
+}
+
 /// Format bytes into a friendly size string
 pub fn format_size(bytes: u64) -> String {
+    format_size_with(bytes, SizeFormat::default())
+}
+
+/// Format bytes into a friendly size string, see `SizeFormat` for the options
+pub fn format_size_with(bytes: u64, format: SizeFormat) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
-    const THRESHOLD: f64 = 1024.0;
+    const THRESHOLD: u64 = 1024;
+    let precision = format.precision.unwrap_or(2);
     
-    let mut size = bytes as f64;
+    // Move up a unit once the value would print as 1024.00, so 1048575 bytes is "1.00 MB"
+    let rounding = 0.5 / 10f64.powi(precision as i32);
     let mut unit_index = 0;
-    
-    while size >= THRESHOLD && unit_index < UNITS.len() - 1 {
-        size /= THRESHOLD;
+    let mut unit = 1u64;
+    while unit_index < UNITS.len() - 1 && bytes as f64 / unit as f64 >= THRESHOLD as f64 - rounding {
+        unit *= THRESHOLD;
         unit_index += 1;
     }
     
     if unit_index == 0 {
-        format!("{} {}", bytes, UNITS[unit_index])
+        if format.spell_bytes {
+            format!("{} {}", bytes, if bytes == 1 { "byte" } else { "bytes" })
+        } else {
+            format!("{} {}", bytes, UNITS[unit_index])
+        }
+    } else if format.trim_exact && bytes % unit == 0 {
+        format!("{} {}", bytes / unit, UNITS[unit_index])
     } else {
-        format!("{:.2} {}", size, UNITS[unit_index])
+        format!("{:.*} {}", precision, bytes as f64 / unit as f64, UNITS[unit_index])
     }
 }
 
+/// Version of the JSON documents and records this tool writes. Bump it when a field is renamed, removed or changes
+/// meaning; new fields are added without a bump, parsers ignore fields they do not know. Version 2 wraps the JSON
+/// detailed listing in an object with the page numbers
+pub const JSON_SCHEMA_VERSION: u32 = 2;
+
+/// Oldest version `from_json` still reads. The manifests and checkpoints it reads are unchanged since version 1
+const OLDEST_READABLE_SCHEMA_VERSION: u32 = 1;
+
+fn json_error(e: serde_json::Error) -> Error {
+    Error::IoError(format!("Failed to serialize JSON: {}", e))
+}
+
+/// Tag a report with `JSON_SCHEMA_VERSION` as its first field. A report that is an array tags each of its
+/// objects instead, so every record carries the version just like the JSON lines records
+fn versioned<T: serde::Serialize>(value: &T) -> Result<serde_json::Value> {
+    fn tag(map: &mut serde_json::Map<String, serde_json::Value>) {
+        let mut tagged = serde_json::Map::new();
+        tagged.insert("schema_version".to_string(), JSON_SCHEMA_VERSION.into());
+        tagged.extend(std::mem::take(map));
+        *map = tagged;
+    }
+    
+    let mut value = serde_json::to_value(value).map_err(json_error)?;
+    match &mut value {
+        serde_json::Value::Object(map) => tag(map),
+        serde_json::Value::Array(items) => items.iter_mut().filter_map(serde_json::Value::as_object_mut).for_each(tag),
+        _ => {}
+    }
+    Ok(value)
+}
+
+/// Serialize a report into pretty-printed JSON
+pub fn to_json<T: serde::Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string_pretty(&versioned(value)?).map_err(json_error)
+}
+
+/// Serialize a report into a single line of JSON
+pub fn to_json_line<T: serde::Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string(&versioned(value)?).map_err(json_error)
+}
+
+/// Parse a document written by `to_json` or `to_json_line`. One with a version this tool cannot read is
+/// rejected rather than misread, the error says which version it has
+pub fn from_json<T: serde::de::DeserializeOwned>(text: &str) -> std::result::Result<T, String> {
+    let readable = OLDEST_READABLE_SCHEMA_VERSION..=JSON_SCHEMA_VERSION;
+    let mut value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    match value.as_object_mut().and_then(|map| map.remove("schema_version")) {
+        Some(version) if version.as_u64().and_then(|v| u32::try_from(v).ok()).is_some_and(|v| readable.contains(&v)) => {}
+        Some(version) => return Err(format!(
+            "schema_version {} is not supported, this chunk_viewer reads versions {} to {}",
+            version, readable.start(), readable.end())),
+        None => return Err(format!(
+            "no schema_version, written by an older chunk_viewer, this one reads versions {} to {}",
+            readable.start(), readable.end())),
+    }
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// Format a stored chunk checksum as hex padded to the full width of its type. `ChunkMeta::checksum` is
+/// a u32, so this is 8 digits; the width follows the type, and `{:x}` never drops high digits anyway
+pub fn format_checksum(checksum: u32) -> String {
+    format!("0x{:0width$x}", checksum, width = std::mem::size_of_val(&checksum) * 2)
+}
+
+/// Format microseconds since the Unix epoch as a UTC date and time
+pub fn format_timestamp(micros: u64) -> String {
+    let secs = micros / 1_000_000;
+    let (days, rem) = (secs / 86400, secs % 86400);
+
+    // Convert days since 1970-01-01 to a civil date
+    let z = days as i64 + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
 /// Parse a hex chunk ID string into bytes
 pub fn parse_hex_chunk_id(hex_str: &str) -> Result<Vec<u8>> {
     let hex_str = hex_str.trim().to_lowercase();
@@ -74,41 +390,275 @@ pub fn parse_hex_chunk_id(hex_str: &str) -> Result<Vec<u8>> {
     Ok(bytes)
 }
 
+/// Length of the chunk IDs 3FS storage writes, the engine itself accepts any length of at least the prefix
+pub const TYPICAL_CHUNK_ID_LEN: usize = 16;
+
+/// Chunk IDs in `[from, to)`, either bound may be left open, see `--id-from` and `--id-to`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdRange {
+    pub from: Option<Vec<u8>>,
+    pub to: Option<Vec<u8>>,
+}
+
+impl IdRange {
+    /// Parse the hex bounds, rejecting a range that holds no ID
+    pub fn parse(from: Option<&str>, to: Option<&str>) -> Result<Self> {
+        let range = Self { from: from.map(parse_hex_chunk_id).transpose()?, to: to.map(parse_hex_chunk_id).transpose()? };
+        if let (Some(from), Some(to)) = (&range.from, &range.to) {
+            if from >= to {
+                return Err(Error::InvalidArg(format!("--id-from {} must be below --id-to {}", format_hex_chunk_id(from), format_hex_chunk_id(to))));
+            }
+        }
+        Ok(range)
+    }
+    
+    pub fn is_full(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+    
+    pub fn contains(&self, chunk_id: &[u8]) -> bool {
+        self.from.as_deref().is_none_or(|from| chunk_id >= from) && self.to.as_deref().is_none_or(|to| chunk_id < to)
+    }
+    
+    /// Chunk IDs are stored inverted, so a scan in key order visits the largest ID first and is done with the
+    /// range once it reaches an ID below `from`
+    pub fn is_past(&self, chunk_id: &[u8]) -> bool {
+        self.from.as_deref().is_some_and(|from| chunk_id < from)
+    }
+    
+    /// The meta key a scan of the range starts at, the key of `to` which is itself excluded, for chunk meta keys
+    /// starting with `key_prefix`
+    pub fn start_key(&self, key_prefix: u8) -> MetaKey {
+        MetaKey::chunk_meta_key_with(key_prefix, self.to.as_deref().unwrap_or_default())
+    }
+    
+    /// Position `it` at the first chunk meta of the range, in key order
+    pub fn seek(&self, it: &mut RocksDBIterator, key_prefix: u8) -> Result<()> {
+        let start_key = self.start_key(key_prefix);
+        it.seek(&start_key)?;
+        if it.key() == Some(start_key.as_ref()) {
+            it.next(); // [begin, end)
+        }
+        Ok(())
+    }
+}
+
+/// Check a parsed chunk ID against the length given with --id-len. Without one, IDs shorter than
+/// `TYPICAL_CHUNK_ID_LEN` only get a warning, as stores written by other tools may use short IDs
+pub fn check_chunk_id_len(chunk_id: &[u8], id_len: Option<usize>) -> Result<()> {
+    match id_len {
+        Some(id_len) if chunk_id.len() != id_len => Err(Error::InvalidArg(format!(
+            "Chunk ID {} is {} bytes, expected {} bytes ({} hex digits, see --id-len)",
+            format_hex_chunk_id(chunk_id), chunk_id.len(), id_len, id_len * 2))),
+        None if chunk_id.len() < TYPICAL_CHUNK_ID_LEN => {
+            eprintln!("Warning: chunk ID {} is only {} bytes, chunk IDs are usually {}, was it truncated?",
+                format_hex_chunk_id(chunk_id), chunk_id.len(), TYPICAL_CHUNK_ID_LEN);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Format chunk ID bytes as a lowercase hex string
+pub fn format_hex_chunk_id(chunk_id: &[u8]) -> String {
+    chunk_id.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Lines of hex dump formatted before each write, bounding the memory of `write_hex_output` to about 300KB
+const HEX_BLOCK_LINES: usize = 4096;
+
+/// Length of a full hex dump line: offset, 16 bytes in two groups, the ASCII column and the newline
+const HEX_LINE_LEN: usize = 10 + 16 * 3 + 1 + 2 + 16 + 2;
+
 /// Format data as hex dump output (like xxd)
 pub fn format_hex_output(data: &[u8]) -> String {
-    let mut output = String::new();
+    format_hex_output_at(data, 0)
+}
+
+/// Format data as hex dump output, labelling lines from `base_offset`. Meant for small inputs, large
+/// ones should go straight to their destination with `write_hex_output`
+pub fn format_hex_output_at(data: &[u8], base_offset: usize) -> String {
+    let mut output = Vec::with_capacity(data.len().div_ceil(16) * HEX_LINE_LEN);
+    write_hex_output(&mut output, data, base_offset).expect("writing to a Vec cannot fail");
+    String::from_utf8(output).expect("hex dumps are ASCII")
+}
+
+/// Write data to `out` as hex dump output, labelling lines from `base_offset`. Lines are formatted a
+/// block at a time, so memory stays bounded however large `data` is
+pub fn write_hex_output(out: &mut impl Write, data: &[u8], base_offset: usize) -> std::io::Result<()> {
+    let mut block = String::with_capacity(HEX_BLOCK_LINES * HEX_LINE_LEN);
+    for (block_index, lines) in data.chunks(16 * HEX_BLOCK_LINES).enumerate() {
+        block.clear();
+        let block_offset = base_offset + block_index * 16 * HEX_BLOCK_LINES;
+        for (i, line) in lines.chunks(16).enumerate() {
+            format_hex_line(&mut block, line, block_offset + i * 16);
+        }
+        out.write_all(block.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Append one hex dump line of up to 16 bytes at `offset` to `output`
+fn format_hex_line(output: &mut String, chunk: &[u8], offset: usize) {
+    // Offset
+    let _ = write!(output, "{:08x}  ", offset);
     
-    for (i, chunk) in data.chunks(16).enumerate() {
-        // Offset
-        output.push_str(&format!("{:08x}  ", i * 16));
-        
-        // Hex bytes
-        for (j, byte) in chunk.iter().enumerate() {
-            if j == 8 {
-                output.push(' '); // Extra space after 8 bytes
-            }
-            output.push_str(&format!("{:02x} ", byte));
+    // Hex bytes
+    for (j, byte) in chunk.iter().enumerate() {
+        if j == 8 {
+            output.push(' '); // Extra space after 8 bytes
         }
+        let _ = write!(output, "{:02x} ", byte);
+    }
         
-        // Padding for incomplete lines
-        if chunk.len() < 16 {
-            let padding = (16 - chunk.len()) * 3 + if chunk.len() <= 8 { 1 } else { 0 };
-            for _ in 0..padding {
-                output.push(' ');
-            }
+    // Padding for incomplete lines
+    if chunk.len() < 16 {
+        let padding = (16 - chunk.len()) * 3 + if chunk.len() <= 8 { 1 } else { 0 };
+        for _ in 0..padding {
+            output.push(' ');
         }
-        
-        // ASCII representation
-        output.push_str(" |");
-        for byte in chunk {
-            let ch = if byte.is_ascii_graphic() || *byte == b' ' {
-                *byte as char
-            } else {
-                '.'
-            };
-            output.push(ch);
+    }
+    
+    // ASCII representation
+    output.push_str(" |");
+    for byte in chunk {
+        let ch = if byte.is_ascii_graphic() || *byte == b' ' {
+            *byte as char
+        } else {
+            '.'
+        };
+        output.push(ch);
+    }
+    output.push_str("|\n");
+}
+
+/// Source language of `format_byte_array`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayStyle {
+    C,
+    Rust,
+}
+
+/// Format data as a C initializer or Rust array literal, 12 bytes per line, led by a length comment
+pub fn format_byte_array(data: &[u8], style: ArrayStyle) -> String {
+    const BYTES_PER_LINE: usize = 12;
+    let length = format!("{} {}", data.len(), if data.len() == 1 { "byte" } else { "bytes" });
+    let (comment, open, close) = match style {
+        ArrayStyle::C => (format!("/* {} */", length), '{', '}'),
+        ArrayStyle::Rust => (format!("// {}", length), '[', ']'),
+    };
+    
+    let mut output = format!("{}\n{}\n", comment, open);
+    for line in data.chunks(BYTES_PER_LINE) {
+        let bytes: Vec<String> = line.iter().map(|byte| format!("0x{:02x},", byte)).collect();
+        output.push_str(&format!("    {}\n", bytes.join(" ")));
+    }
+    output.push(close);
+    output.push('\n');
+    output
+}
+
+/// Byte-level statistics of a buffer, see `content_stats`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentStats {
+    /// Shannon entropy in bits per byte, from 0.0 (one repeated value) to 8.0 (all values equally likely)
+    pub entropy: f64,
+    pub zero_bytes: usize,
+    /// Length and offset of the longest run of zero bytes, the first one on a tie, `(0, 0)` without zero bytes
+    pub longest_zero_run: usize,
+    pub longest_zero_run_offset: usize,
+    /// Smallest and largest byte value, `None` for an empty buffer
+    pub min: Option<u8>,
+    pub max: Option<u8>,
+    /// The most frequent byte value and its count, the smallest value on a tie
+    pub most_common: Option<(u8, usize)>,
+}
+
+/// Entropy and byte histogram summary of `data`
+pub fn content_stats(data: &[u8]) -> ContentStats {
+    let mut histogram = [0usize; 256];
+    let (mut longest_zero_run, mut longest_zero_run_offset, mut run_start) = (0, 0, 0);
+    for (offset, &byte) in data.iter().enumerate() {
+        histogram[byte as usize] += 1;
+        if byte != 0 {
+            run_start = offset + 1;
+        } else if offset + 1 - run_start > longest_zero_run {
+            longest_zero_run = offset + 1 - run_start;
+            longest_zero_run_offset = run_start;
+        }
+    }
+    
+    let present = || histogram.iter().enumerate().filter(|(_, &count)| count > 0);
+    let most_common = present().fold(None, |best: Option<(u8, usize)>, (value, &count)| match best {
+        Some((_, best_count)) if best_count >= count => best,
+        _ => Some((value as u8, count)),
+    });
+    
+    ContentStats {
+        entropy: shannon_entropy(&histogram, data.len()),
+        zero_bytes: histogram[0],
+        longest_zero_run,
+        longest_zero_run_offset,
+        min: present().next().map(|(value, _)| value as u8),
+        max: present().next_back().map(|(value, _)| value as u8),
+        most_common,
+    }
+}
+
+/// Shannon entropy in bits per byte of a byte histogram over `total` bytes
+fn shannon_entropy(histogram: &[usize; 256], total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    histogram.iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Find the first offset where two buffers differ, including where the shorter one ends
+pub fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    match a.iter().zip(b).position(|(x, y)| x != y) {
+        Some(offset) => Some(offset),
+        None if a.len() != b.len() => Some(a.len().min(b.len())),
+        None => None,
+    }
+}
+
+/// The byte ranges where two buffers differ, bytes past the end of the shorter one count as differing.
+/// Differences less than 16 bytes apart are merged into one range
+pub fn differing_ranges(a: &[u8], b: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for offset in 0..a.len().max(b.len()) {
+        if a.get(offset) == b.get(offset) {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(range) if offset - range.end < 16 => range.end = offset + 1,
+            _ => ranges.push(offset..offset + 1),
         }
-        output.push_str("|\n");
+    }
+    ranges
+}
+
+/// Format two buffers side by side as hex, 8 bytes per line, marking lines that differ
+pub fn format_hex_side_by_side(a: &[u8], b: &[u8], start: usize, end: usize) -> String {
+    let mut output = String::new();
+    let hex_line = |data: &[u8], offset: usize| {
+        (offset..offset + 8)
+            .map(|i| data.get(i).map_or("  ".to_string(), |byte| format!("{:02x}", byte)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    
+    for offset in (start / 8 * 8..end).step_by(8) {
+        let left = hex_line(a, offset);
+        let right = hex_line(b, offset);
+        let marker = if left != right { "  <" } else { "" };
+        output.push_str(&format!("{:08x}  {} | {}{}\n", offset, left, right, marker));
     }
     
     output
@@ -127,6 +677,26 @@ mod tests {
         assert_eq!(parse_size_string("1.5MB").unwrap(), (1.5 * 1024.0 * 1024.0) as u32);
     }
 
+    #[test]
+    fn test_bucket_size() {
+        assert_eq!(bucket_size(4 << 20).unwrap(), CHUNK_SIZE_LARGE);
+        assert_eq!(bucket_size(64 << 10).unwrap(), CHUNK_SIZE_SMALL);
+        
+        let Err(Error::InvalidArg(message)) = bucket_size(5 << 20) else { panic!("5MB is not a bucket") };
+        assert!(message.starts_with("5.00 MB is not a chunk size bucket"));
+        assert!(message.contains("64 KB, 128 KB,") && message.ends_with("64 MB"));
+        assert!(bucket_size(0).is_err());
+        assert!(bucket_size(128 << 20).is_err());
+    }
+
+    #[test]
+    fn test_nearest_bucket() {
+        assert_eq!(nearest_bucket(5 << 20).unwrap(), Size::mebibyte(8));
+        assert_eq!(nearest_bucket(4 << 20).unwrap(), CHUNK_SIZE_LARGE);
+        assert_eq!(nearest_bucket(1).unwrap(), CHUNK_SIZE_SMALL);
+        assert!(nearest_bucket((64 << 20) + 1).is_err());
+    }
+
     #[test]
     fn test_format_size() {
         assert_eq!(format_size(1024), "1.00 KB");
@@ -134,6 +704,299 @@ mod tests {
         assert_eq!(format_size(1024 * 1024 * 1024), "1.00 GB");
     }
 
+    #[test]
+    fn test_format_size_boundaries() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1), "1 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1024), "1.00 KB");
+        assert_eq!(format_size(1025), "1.00 KB");
+        assert_eq!(format_size(1024 * 1024 - 1), "1.00 MB");
+        assert_eq!(format_size(1024 * 1024 - 1024), "1023.00 KB");
+    }
+
+    #[test]
+    fn test_format_size_with() {
+        let format = SizeFormat { trim_exact: true, spell_bytes: true, ..Default::default() };
+        assert_eq!(format_size_with(0, format), "0 bytes");
+        assert_eq!(format_size_with(1, format), "1 byte");
+        assert_eq!(format_size_with(1023, format), "1023 bytes");
+        assert_eq!(format_size_with(1024, format), "1 KB");
+        assert_eq!(format_size_with(1025, format), "1.00 KB");
+        assert_eq!(format_size_with(4 << 20, format), "4 MB");
+        assert_eq!(format_size_with(1536, format), "1.50 KB");
+    }
+
+    #[test]
+    fn test_format_size_precision() {
+        let precision = |precision| SizeFormat { precision: Some(precision), ..Default::default() };
+        assert_eq!(format_size_with(1536, precision(0)), "2 KB");
+        assert_eq!(format_size_with(1536, precision(4)), "1.5000 KB");
+        assert_eq!(format_size_with(1000, precision(0)), "1000 B");
+        assert_eq!(format_size_with(1234567, precision(3)), "1.177 MB");
+        // The unit moves up where the value would round to 1024 at that precision
+        assert_eq!(format_size_with(1024 * 1024 - 400, precision(0)), "1 MB");
+        assert_eq!(format_size_with(1024 * 1024 - 400, precision(4)), "1023.6094 KB");
+    }
+
+    #[test]
+    fn test_id_range() {
+        let range = IdRange::parse(Some("1000"), Some("2000")).unwrap();
+        assert!(!range.is_full());
+        assert!(range.contains(&[0x10, 0x00]) && range.contains(&[0x1f, 0xff]));
+        assert!(!range.contains(&[0x20, 0x00]) && !range.contains(&[0x0f, 0xff]));
+        assert!(range.is_past(&[0x0f, 0xff]) && !range.is_past(&[0x20, 0x00]));
+        
+        let open = IdRange::parse(None, Some("2000")).unwrap();
+        assert!(open.contains(&[0x00, 0x00]) && !open.is_past(&[0x00, 0x00]));
+        assert!(IdRange::parse(None, None).unwrap().is_full());
+        
+        // The range must hold at least one ID
+        assert!(matches!(IdRange::parse(Some("2000"), Some("2000")), Err(Error::InvalidArg(_))));
+        assert!(matches!(IdRange::parse(Some("3000"), Some("2000")), Err(Error::InvalidArg(_))));
+        assert!(IdRange::parse(Some("xyz0"), None).is_err());
+    }
+
+    #[test]
+    fn test_first_difference() {
+        assert_eq!(first_difference(b"abcd", b"abcd"), None);
+        assert_eq!(first_difference(b"", b""), None);
+        assert_eq!(first_difference(b"abcd", b"abxd"), Some(2));
+        assert_eq!(first_difference(b"abcd", b"ab"), Some(2));
+        assert_eq!(first_difference(b"", b"a"), Some(0));
+    }
+
+    #[test]
+    fn test_differing_ranges() {
+        assert!(differing_ranges(b"abcd", b"abcd").is_empty());
+        assert_eq!(differing_ranges(b"abcd", b"abxd"), vec![2..3]);
+        assert_eq!(differing_ranges(b"abcd", b"ab"), vec![2..4]);
+        
+        // Nearby differences share a range, distant ones get their own
+        let a = [0u8; 64];
+        let mut b = a;
+        b[1] = 1;
+        b[10] = 1;
+        b[40] = 1;
+        assert_eq!(differing_ranges(&a, &b), [1..11, 40..41]);
+    }
+
+    #[test]
+    fn test_engine_paths_relative() {
+        let cwd = std::env::current_dir().unwrap();
+        let (data_dir, meta_path) = engine_paths(Path::new("meta"), None).unwrap();
+        assert_eq!(meta_path, cwd.join("meta"));
+        assert_eq!(data_dir, cwd);
+    }
+
+    #[test]
+    fn test_engine_paths_absolute() {
+        let dir = tempfile::tempdir().unwrap();
+        let rocksdb_path = dir.path().join("meta");
+        std::fs::create_dir(&rocksdb_path).unwrap();
+        let canonical = std::fs::canonicalize(dir.path()).unwrap();
+        
+        let (data_dir, meta_path) = engine_paths(&rocksdb_path, None).unwrap();
+        assert_eq!(meta_path, canonical.join("meta"));
+        assert_eq!(data_dir, canonical);
+        
+        // The data directory can live anywhere when it is given explicitly
+        let (data_dir, meta_path) = engine_paths(&rocksdb_path, Some(Path::new("/data/chunks"))).unwrap();
+        assert_eq!(meta_path, canonical.join("meta"));
+        assert_eq!(data_dir, Path::new("/data/chunks"));
+    }
+
+    #[test]
+    fn test_engine_paths_root() {
+        let (data_dir, meta_path) = engine_paths(Path::new("/meta"), None).unwrap();
+        assert_eq!(meta_path, Path::new("/meta"));
+        assert_eq!(data_dir, Path::new("/"));
+        
+        assert!(engine_paths(Path::new("/"), None).is_err());
+        let (data_dir, _) = engine_paths(Path::new("/"), Some(Path::new("/data"))).unwrap();
+        assert_eq!(data_dir, Path::new("/data"));
+    }
+
+    #[test]
+    fn test_format_hex_side_by_side() {
+        let output = format_hex_side_by_side(b"0123456789", b"0123456x", 0, 10);
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "00000000  30 31 32 33 34 35 36 37 | 30 31 32 33 34 35 36 78  <");
+        assert_eq!(lines[1], format!("00000008  38 39{} | {}  <", " ".repeat(18), " ".repeat(23)));
+    }
+
+    #[test]
+    fn test_format_hex_output_at() {
+        assert_eq!(format_hex_output(b"AB"), format_hex_output_at(b"AB", 0));
+        assert!(format_hex_output_at(b"AB", 0x20).starts_with("00000020  41 42"));
+        let line = format_hex_output(&[b'x'; 16]);
+        assert_eq!(line.len(), HEX_LINE_LEN);
+        assert_eq!(line, "00000000  78 78 78 78 78 78 78 78  78 78 78 78 78 78 78 78  |xxxxxxxxxxxxxxxx|\n");
+        
+        // Lines keep counting across blocks
+        let data = vec![0u8; 16 * HEX_BLOCK_LINES + 20];
+        let dump = format_hex_output_at(&data, 0x100);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), HEX_BLOCK_LINES + 2);
+        assert!(lines[HEX_BLOCK_LINES].starts_with(&format!("{:08x}  00", 0x100 + 16 * HEX_BLOCK_LINES)));
+        assert!(lines[HEX_BLOCK_LINES + 1].starts_with(&format!("{:08x}  00 00 00 00  ", 0x100 + 16 * HEX_BLOCK_LINES + 16)));
+    }
+
+    #[test]
+    fn test_json_schema_version() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Record {
+            name: String,
+        }
+        let record = Record { name: "a".to_string() };
+        
+        assert_eq!(to_json_line(&record).unwrap(), r#"{"schema_version":2,"name":"a"}"#);
+        assert_eq!(to_json_line(&vec![&record, &record]).unwrap(), r#"[{"schema_version":2,"name":"a"},{"schema_version":2,"name":"a"}]"#);
+        assert!(to_json(&record).unwrap().starts_with("{\n  \"schema_version\": 2,"));
+        assert_eq!(from_json::<Record>(&to_json(&record).unwrap()).unwrap(), record);
+        
+        // Version 1 documents are still read, the formats from_json reads have not changed since
+        assert_eq!(from_json::<Record>(r#"{"schema_version":1,"name":"a"}"#).unwrap(), record);
+        assert_eq!(from_json::<Record>(r#"{"schema_version":0,"name":"a"}"#).unwrap_err(),
+            "schema_version 0 is not supported, this chunk_viewer reads versions 1 to 2");
+        assert!(from_json::<Record>(r#"{"schema_version":3,"name":"a"}"#).is_err());
+        assert!(from_json::<Record>(r#"{"name":"a"}"#).unwrap_err().starts_with("no schema_version"));
+
+        // A serializer error keeps its message
+        let map = std::collections::BTreeMap::from([((1, 2), 3)]);
+        assert_eq!(to_json(&map), Err(Error::IoError("Failed to serialize JSON: key must be a string".to_string())));
+    }
+
+    #[test]
+    fn test_output_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 7) as u8).collect();
+        let write = |name: &str, gzip: bool| {
+            let path = dir.path().join(name);
+            let mut file = OutputWriter::create(path.to_str().unwrap(), gzip, false).unwrap();
+            write_hex_output(&mut file, &data, 0).unwrap();
+            file.finish().unwrap();
+            path
+        };
+        let plain = write("chunk.hex", false);
+        let compressed = write("chunk.hex.gz", true);
+        
+        // The compressed file is a complete gzip stream of the same dump, and far smaller
+        let mut decompressed = String::new();
+        GzDecoder::new(File::open(&compressed).unwrap()).read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, format_hex_output(&data));
+        assert_eq!(decompressed, std::fs::read_to_string(&plain).unwrap());
+        assert!(std::fs::metadata(&compressed).unwrap().len() * 10 < std::fs::metadata(&plain).unwrap().len());
+        assert_eq!(read_text_file(compressed.to_str().unwrap()).unwrap(), decompressed);
+        assert_eq!(read_text_file(plain.to_str().unwrap()).unwrap(), decompressed);
+        
+        assert!(matches!(OutputWriter::create("/nonexistent/chunk.hex", true, false), Err(Error::IoError(_))));
+    }
+
+    #[test]
+    fn test_output_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let error = |path: &std::path::Path, mkdir_parents: bool| match OutputWriter::create(path.to_str().unwrap(), false, mkdir_parents) {
+            Err(Error::IoError(message)) => message,
+            Err(e) => panic!("unexpected error {:?}", e),
+            Ok(_) => panic!("{} was created", path.display()),
+        };
+        
+        // A directory is rejected even with --mkdir-parents, and left alone
+        assert!(error(dir.path(), true).ends_with("it is a directory, give a file name"));
+        assert!(dir.path().is_dir());
+        
+        // A missing parent is only created with --mkdir-parents
+        let nested = dir.path().join("a/b/chunk.hex");
+        let message = error(&nested, false);
+        assert!(message.contains(&format!("directory {} does not exist", dir.path().join("a/b").display())), "{}", message);
+        assert!(message.ends_with("pass --mkdir-parents to create it"));
+        assert!(!dir.path().join("a").exists());
+        let mut file = OutputWriter::create(nested.to_str().unwrap(), false, true).unwrap();
+        file.write_all(b"data").unwrap();
+        file.finish().unwrap();
+        assert_eq!(std::fs::read(&nested).unwrap(), b"data");
+        
+        // A parent that is a file cannot be created either way
+        assert!(error(&nested.join("chunk.hex"), true).ends_with(&format!("{} is not a directory", nested.display())));
+    }
+
+    #[test]
+    fn test_format_byte_array() {
+        let data: Vec<u8> = (0..14).collect();
+        assert_eq!(format_byte_array(&data, ArrayStyle::C), "/* 14 bytes */\n{\n    \
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,\n    \
+            0x0c, 0x0d,\n}\n");
+        assert_eq!(format_byte_array(&[0xff], ArrayStyle::Rust), "// 1 byte\n[\n    0xff,\n]\n");
+        assert_eq!(format_byte_array(&[], ArrayStyle::Rust), "// 0 bytes\n[\n]\n");
+    }
+
+    #[test]
+    fn test_content_stats() {
+        let stats = content_stats(&[0u8; 4096]);
+        assert_eq!(stats.entropy, 0.0);
+        assert_eq!(stats.zero_bytes, 4096);
+        assert_eq!((stats.min, stats.max, stats.most_common), (Some(0), Some(0), Some((0, 4096))));
+        
+        // Every byte value equally often is the maximum of 8 bits per byte
+        let uniform: Vec<u8> = (0..4096).map(|i| (i * 7) as u8).collect();
+        let stats = content_stats(&uniform);
+        assert!((stats.entropy - 8.0).abs() < 1e-9, "{}", stats.entropy);
+        assert_eq!(stats.zero_bytes, 16);
+        assert_eq!((stats.min, stats.max, stats.most_common), (Some(0), Some(255), Some((0, 16))));
+        
+        let stats = content_stats(b"aab");
+        assert!((stats.entropy - 0.9183).abs() < 1e-4, "{}", stats.entropy);
+        assert_eq!((stats.min, stats.max, stats.most_common), (Some(b'a'), Some(b'b'), Some((b'a', 2))));
+        
+        let stats = content_stats(&[]);
+        assert_eq!(stats.entropy, 0.0);
+        assert_eq!((stats.min, stats.max, stats.most_common), (None, None, None));
+        assert_eq!((stats.longest_zero_run, stats.longest_zero_run_offset), (0, 0));
+    }
+
+    #[test]
+    fn test_zero_runs() {
+        // Runs of 3 at 1, 1000 at 10 and 1000 at 1020, the first of the longest wins
+        let mut data = vec![1u8; 3000];
+        for range in [1..4, 10..1010, 1020..2020] {
+            data[range].fill(0);
+        }
+        let stats = content_stats(&data);
+        assert_eq!(stats.zero_bytes, 2003);
+        assert_eq!((stats.longest_zero_run, stats.longest_zero_run_offset), (1000, 10));
+        
+        // A run reaching the end of the buffer
+        data[2500..].fill(0);
+        let stats = content_stats(&data);
+        assert_eq!((stats.longest_zero_run, stats.longest_zero_run_offset), (1000, 10));
+        data[2000..].fill(0);
+        let stats = content_stats(&data);
+        assert_eq!((stats.longest_zero_run, stats.longest_zero_run_offset), (1980, 1020));
+        
+        let stats = content_stats(&[0u8; 4096]);
+        assert_eq!((stats.longest_zero_run, stats.longest_zero_run_offset), (4096, 0));
+        assert_eq!(content_stats(b"abc").longest_zero_run, 0);
+    }
+
+    #[test]
+    fn test_format_checksum() {
+        // Fails to compile if the field widens, so the width here is revisited with it
+        let checksum: u32 = ChunkMeta::default().checksum;
+        assert_eq!(format_checksum(checksum), "0x00000000");
+        assert_eq!(format_checksum(0x1a), "0x0000001a");
+        assert_eq!(format_checksum(0xdead_beef), "0xdeadbeef");
+        assert_eq!(format_checksum(u32::MAX), "0xffffffff");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400_000_000), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_700_000_000_123_456), "2023-11-14 22:13:20 UTC");
+    }
+
     #[test]
     fn test_parse_hex_chunk_id() {
         let result = parse_hex_chunk_id("a1b2c3d4").unwrap();
@@ -141,5 +1004,60 @@ mod tests {
         
         assert!(parse_hex_chunk_id("a1b2c").is_err()); // Odd length
         assert!(parse_hex_chunk_id("a1b2g3d4").is_err()); // Invalid hex
+        
+        assert_eq!(format_hex_chunk_id(&result), "a1b2c3d4");
+    }
+
+    #[test]
+    fn test_check_chunk_id_len() {
+        let full = parse_hex_chunk_id("01076a831f22a988ad999bd7544073ec").unwrap();
+        let truncated = parse_hex_chunk_id("01076a831f22a988ad999bd7").unwrap();
+        assert!(check_chunk_id_len(&full, Some(16)).is_ok());
+        let err = check_chunk_id_len(&truncated, Some(16)).unwrap_err();
+        assert!(err.to_string().contains("is 12 bytes, expected 16 bytes (32 hex digits"), "{}", err);
+        assert!(check_chunk_id_len(&full, Some(12)).is_err());
+
+        // Without --id-len a short ID is only a warning
+        assert!(check_chunk_id_len(&truncated, None).is_ok());
+        assert!(check_chunk_id_len(&full, None).is_ok());
+    }
+
+    #[test]
+    fn test_open_with_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig { path: dir.path().join("meta"), create: true, ..Default::default() },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        drop(MetaStore::open(&config).unwrap());
+        let read_only = MetaStoreConfig { rocksdb: RocksDBConfig { mode: DbOpenMode::ReadOnly, ..config.rocksdb.clone() }, ..config.clone() };
+        
+        // Stands in for a store on a hung filesystem
+        let slow_open = |config: MetaStoreConfig| move || {
+            std::thread::sleep(Duration::from_secs(2));
+            MetaStore::open(&config)
+        };
+        let err = open_with_timeout("slow store", &DbOpenMode::ReadOnly, Some(Duration::from_millis(100)), slow_open(read_only.clone())).err().unwrap();
+        assert!(matches!(&err, Error::Timeout(message) if message.contains("slow store")), "{}", err);
+        
+        assert!(open_with_timeout("slow store", &DbOpenMode::ReadOnly, None, slow_open(read_only.clone())).is_ok());
+        assert!(open_meta_store(&read_only, Some(Duration::from_secs(10))).is_ok());
+        
+        // A read-write open left running could still write to the store, so it never times out
+        assert!(matches!(open_meta_store(&config, Some(Duration::from_secs(10))), Err(Error::InvalidArg(_))));
+        assert!(open_meta_store(&config, None).is_ok());
+    }
+
+    #[test]
+    fn test_engine_config_tuning() {
+        let mut meta_config = meta_store_config(Path::new("/data/meta"), DbOpenMode::ReadOnly);
+        meta_config.rocksdb.tuning.bloom_filter = false;
+        meta_config.rocksdb.tuning.readahead_size = Size::mebibyte(16);
+        
+        // The engine opens the meta store with the same RocksDB tuning as the metadata scans
+        let engine_config = engine_config(&meta_config, None, false).unwrap();
+        assert_eq!(engine_config.meta_store_config().rocksdb.tuning, meta_config.rocksdb.tuning);
+        assert_eq!(engine_config.meta_store_config().rocksdb.path, meta_config.rocksdb.path);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,840 @@
+use chunk_engine::*;
+use derse::Deserialize;
+use serde::Serialize;
+use std::{collections::BTreeMap, io::Write, ops::RangeInclusive, path::Path, sync::{Arc, Mutex}, time::Duration};
+
+use super::args::OutputFormat;
+use super::exit_status::ExitStatus;
+use super::utils::{engine_config, format_checksum, format_hex_chunk_id, format_size, from_json, open_store_with_timeout, parse_hex_chunk_id, read_text_file, to_json, to_json_line, IdRange, OutputWriter};
+#[cfg(test)]
+use super::utils::JSON_SCHEMA_VERSION;
+
+/// Chunks a thread verifies between two checkpoint saves
+const CHECKPOINT_INTERVAL: u64 = 1024;
+
+/// Where a chunk's data lives
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, serde::Deserialize)]
+pub struct ChunkPosition {
+    pub cluster: u8,
+    pub group: u32,
+    pub index: u8,
+}
+
+/// One line of the corrupt chunk manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, serde::Deserialize)]
+pub struct CorruptChunk {
+    pub chunk_id: String,
+    pub stored_checksum: u32,
+    pub computed_checksum: u32,
+    pub chunk_size: u64,
+    pub position: ChunkPosition,
+}
+
+/// Outcome of verifying every chunk of a store
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, serde::Deserialize)]
+pub struct VerifyReport {
+    pub verified_chunks: u64,
+    pub verified_bytes: u64,
+    /// Chunks whose stored checksum matches another supported algorithm
+    pub other_algorithm: u64,
+    /// Chunks whose stored checksum matches no supported algorithm
+    pub corrupt_chunks: u64,
+    /// Chunks listed in the metadata whose data could not be read
+    pub missing_chunks: u64,
+    /// Details of every corrupt chunk, sorted by chunk ID
+    pub corrupt: Vec<CorruptChunk>,
+}
+
+impl VerifyReport {
+    pub fn mismatched_chunks(&self) -> u64 {
+        self.other_algorithm + self.corrupt_chunks + self.missing_chunks
+    }
+
+    /// Most severe finding first: corrupt data, then metadata without data, then chunks that
+    /// are intact but checksummed with another algorithm
+    pub fn exit_status(&self) -> ExitStatus {
+        if self.corrupt_chunks > 0 {
+            ExitStatus::Corruption
+        } else if self.missing_chunks > 0 {
+            ExitStatus::Orphans
+        } else if self.other_algorithm > 0 {
+            ExitStatus::Mismatch
+        } else {
+            ExitStatus::Ok
+        }
+    }
+
+    fn merge(&mut self, other: VerifyReport) {
+        self.verified_chunks += other.verified_chunks;
+        self.verified_bytes += other.verified_bytes;
+        self.other_algorithm += other.other_algorithm;
+        self.corrupt_chunks += other.corrupt_chunks;
+        self.missing_chunks += other.missing_chunks;
+        self.corrupt.extend(other.corrupt);
+    }
+
+    /// Write the corrupt chunks as JSON lines, gzip compressed with `gzip`, for a follow-up repair run. Missing parent
+    /// directories are created with `mkdir_parents`
+    pub fn write_corrupt_manifest(&self, path: &str, gzip: bool, mkdir_parents: bool) -> Result<()> {
+        let mut file = OutputWriter::create(path, gzip, mkdir_parents)?;
+        for chunk in &self.corrupt {
+            writeln!(file, "{}", to_json_line(chunk)?).map_err(|e| file.error(e))?;
+        }
+        file.finish()
+    }
+}
+
+/// The IDs of the chunks listed in a manifest written by `write_corrupt_manifest`, plain or gzip compressed, in
+/// manifest order without duplicates
+pub fn read_corrupt_manifest(path: &str) -> Result<Vec<Vec<u8>>> {
+    let content = read_text_file(path)
+        .map_err(|e| Error::IoError(format!("Failed to read manifest {}: {}", path, e)))?;
+    let mut chunk_ids: Vec<Vec<u8>> = Vec::new();
+    for (line_no, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let chunk: CorruptChunk = from_json(line)
+            .map_err(|e| Error::InvalidArg(format!("Invalid manifest {} line {}: {}", path, line_no + 1, e)))?;
+        let chunk_id = parse_hex_chunk_id(&chunk.chunk_id)?;
+        if !chunk_ids.contains(&chunk_id) {
+            chunk_ids.push(chunk_id);
+        }
+    }
+    Ok(chunk_ids)
+}
+
+/// A chunk whose metadata length does not fit the slot it is stored in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImplausibleLength {
+    pub chunk_id: String,
+    pub len: u32,
+    pub capacity: u64,
+}
+
+/// Outcome of checking every chunk's length without reading its payload
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct LengthReport {
+    pub checked_chunks: u64,
+    /// Chunks whose first or last byte could not be read, the data is truncated
+    pub unreadable_chunks: u64,
+    /// Chunks listed in the metadata whose data could not be found
+    pub missing_chunks: u64,
+    /// Chunks whose length exceeds their capacity, in store order
+    pub implausible: Vec<ImplausibleLength>,
+}
+
+impl LengthReport {
+    pub fn exit_status(&self) -> ExitStatus {
+        if !self.implausible.is_empty() || self.unreadable_chunks > 0 {
+            ExitStatus::Corruption
+        } else if self.missing_chunks > 0 {
+            ExitStatus::Orphans
+        } else {
+            ExitStatus::Ok
+        }
+    }
+}
+
+/// A chunk holding data whose stored checksum is 0, see `--zero-checksum`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ZeroChecksumChunk {
+    pub chunk_id: String,
+    pub chunk_size: u64,
+    pub len: u32,
+    /// Checksum of the data read back with `--recompute`, 0 if the data really hashes to 0
+    pub computed_checksum: Option<u32>,
+}
+
+/// Zero checksums of one size bucket
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ZeroChecksumBucket {
+    pub chunk_size: u64,
+    /// Chunks holding data, an empty chunk's checksum is always 0 so empty ones are not counted
+    pub checked_chunks: u64,
+    pub zero_checksum_chunks: u64,
+    /// Zero checksum chunks whose data was read with `--recompute` and really hashes to 0
+    pub genuine_zero_chunks: u64,
+}
+
+/// Outcome of `--zero-checksum`, buckets without chunks holding data are left out
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ZeroChecksumReport {
+    pub buckets: Vec<ZeroChecksumBucket>,
+    pub recomputed: bool,
+    /// Zero checksum chunks whose data could not be found with `--recompute`
+    pub missing_chunks: u64,
+    /// Every zero checksum chunk, in store order
+    pub chunks: Vec<ZeroChecksumChunk>,
+}
+
+impl ZeroChecksumReport {
+    /// Zero checksums not shown to be genuine, most likely never computed by a buggy writer
+    pub fn suspect_chunks(&self) -> u64 {
+        self.buckets.iter().map(|bucket| bucket.zero_checksum_chunks - bucket.genuine_zero_chunks).sum()
+    }
+
+    pub fn exit_status(&self) -> ExitStatus {
+        if self.suspect_chunks() > self.missing_chunks {
+            ExitStatus::Corruption
+        } else if self.missing_chunks > 0 {
+            ExitStatus::Orphans
+        } else {
+            ExitStatus::Ok
+        }
+    }
+}
+
+/// Progress of an interrupted verification, so a rerun can resume where it stopped
+#[derive(Debug, Default, Serialize, serde::Deserialize)]
+struct Checkpoint {
+    algorithm: String,
+    /// One shard per thread, each covering a range of chunk IDs
+    shards: Vec<ShardCheckpoint>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, serde::Deserialize)]
+struct ShardCheckpoint {
+    /// Last chunk meta key verified by this shard, in hex
+    last_key: Option<String>,
+    finished: bool,
+    report: VerifyReport,
+}
+
+impl Checkpoint {
+    fn new(algo: ChunkChecksum, threads: usize) -> Self {
+        Self { algorithm: algo.name().to_string(), shards: vec![ShardCheckpoint::default(); threads] }
+    }
+
+    /// Load the checkpoint at `path`, or start a new one if there is none
+    fn load(path: &Path, algo: ChunkChecksum, threads: usize) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::new(algo, threads));
+        }
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| Error::IoError(format!("Failed to read checkpoint {}: {}", path.display(), e)))?;
+        let checkpoint: Self = from_json(&content)
+            .map_err(|e| Error::InvalidArg(format!("Invalid checkpoint {}: {}", path.display(), e)))?;
+        if checkpoint.algorithm != algo.name() || checkpoint.shards.len() != threads {
+            return Err(Error::InvalidArg(format!(
+                "Checkpoint {} was written by --checksum-algo {} --threads {}, resume with the same options",
+                path.display(), checkpoint.algorithm, checkpoint.shards.len())));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Replace the file atomically so an interruption never leaves a partial checkpoint behind
+    fn save(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, to_json(self)?)
+            .and_then(|_| std::fs::rename(&tmp_path, path))
+            .map_err(|e| Error::IoError(format!("Failed to save checkpoint {}: {}", path.display(), e)))
+    }
+}
+
+/// Shared by the verify threads, which record their progress in it
+struct Progress<'a> {
+    checkpoint: Mutex<Checkpoint>,
+    path: Option<&'a Path>,
+    should_stop: &'a (dyn Fn() -> bool + Sync),
+}
+
+impl Progress<'_> {
+    fn record(&self, shard: usize, state: ShardCheckpoint) -> Result<()> {
+        let mut checkpoint = self.checkpoint.lock().unwrap();
+        checkpoint.shards[shard] = state;
+        match self.path {
+            Some(path) => checkpoint.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+pub struct ChunkVerifier {
+    meta_store: Arc<MetaStore>,
+    engine: Engine,
+    /// Chunk IDs to verify, the rest of the store is skipped
+    id_range: IdRange,
+}
+
+impl ChunkVerifier {
+    /// Open the engine of the meta store `meta_config` describes, with its chunk meta key layout
+    pub fn new(meta_config: &MetaStoreConfig, data_dir: Option<&Path>, open_timeout: Option<Duration>) -> Result<Self> {
+        let engine_config = engine_config(meta_config, data_dir, false)?;
+        let (meta_store, engine) = open_store_with_timeout(engine_config, open_timeout)?;
+
+        Ok(Self { meta_store, engine, id_range: IdRange::default() })
+    }
+
+    pub fn with_id_range(mut self, id_range: IdRange) -> Self {
+        self.id_range = id_range;
+        self
+    }
+
+    /// Read every chunk and compare its data against the stored checksum. The chunk ID space
+    /// is split by its first byte into `threads` ranges that are verified in parallel.
+    ///
+    /// With a `checkpoint` path, progress is saved there periodically and a previous run's
+    /// progress is resumed. Returns `None` if `should_stop` ended the scan early, the checkpoint
+    /// then holds everything needed to resume, it is removed once the scan completes.
+    pub fn verify_checksums(
+        &self,
+        algo: ChunkChecksum,
+        threads: usize,
+        checkpoint: Option<&Path>,
+        should_stop: &(dyn Fn() -> bool + Sync),
+    ) -> Result<Option<VerifyReport>> {
+        let threads = threads.clamp(1, 256);
+        let progress = Progress {
+            checkpoint: Mutex::new(match checkpoint {
+                Some(path) => Checkpoint::load(path, algo, threads)?,
+                None => Checkpoint::new(algo, threads),
+            }),
+            path: checkpoint,
+            should_stop,
+        };
+        let ranges = (0..threads).map(|i| {
+            let begin = 256 * i / threads;
+            let end = 256 * (i + 1) / threads - 1;
+            (i, begin as u8..=end as u8)
+        });
+
+        // Each thread collects its own shard of the report, merged once all of them finish
+        let shards: Vec<Result<Option<VerifyReport>>> = std::thread::scope(|s| {
+            let progress = &progress;
+            let handles: Vec<_> = ranges.map(|(shard, range)| s.spawn(move || self.verify_range(algo, shard, range, progress))).collect();
+            handles.into_iter().map(|handle| handle.join().expect("verify thread panicked")).collect()
+        });
+
+        let mut report = VerifyReport::default();
+        let mut stopped = false;
+        for shard in shards {
+            match shard? {
+                Some(shard) => report.merge(shard),
+                None => stopped = true,
+            }
+        }
+        if stopped {
+            return Ok(None);
+        }
+        report.corrupt.sort_by(|a, b| a.chunk_id.cmp(&b.chunk_id));
+
+        if let Some(path) = checkpoint {
+            std::fs::remove_file(path)
+                .map_err(|e| Error::IoError(format!("Failed to remove checkpoint {}: {}", path.display(), e)))?;
+        }
+
+        Ok(Some(report))
+    }
+
+    /// Verify the chunks whose meta key continues with a byte in `range` after the prefix,
+    /// picking up after the shard's checkpointed key if there is one
+    fn verify_range(&self, algo: ChunkChecksum, shard: usize, range: RangeInclusive<u8>, progress: &Progress) -> Result<Option<VerifyReport>> {
+        let ShardCheckpoint { last_key, finished, mut report } = progress.checkpoint.lock().unwrap().shards[shard].clone();
+        if finished {
+            return Ok(Some(report));
+        }
+
+        let mark = self.meta_store.chunk_meta_key_prefix();
+        let mut it = self.meta_store.iterator();
+        match &last_key {
+            Some(last_key) => {
+                let last_key = parse_hex_chunk_id(last_key)?;
+                it.seek(&last_key)?;
+                if it.key() == Some(last_key.as_ref()) {
+                    it.next();
+                }
+            }
+            // Start at whichever comes later of the shard and the ID range, an exact match of the range's
+            // excluded end is skipped below
+            None => {
+                let range_key = self.id_range.start_key(mark);
+                let shard_key = [mark, *range.start()];
+                it.seek(std::cmp::max(range_key.as_ref(), shard_key.as_slice()))?;
+            }
+        }
+
+        let mut buffer = Vec::new();
+        let mut since_checkpoint = 0u64;
+        let mut last_key = last_key;
+        loop {
+            if !it.valid() {
+                break;
+            }
+
+            let key = it.key().unwrap();
+            if key[0] != mark || key.len() < 2 || key[1] > *range.end() {
+                break;
+            }
+
+            if (progress.should_stop)() {
+                progress.record(shard, ShardCheckpoint { last_key, finished: false, report })?;
+                return Ok(None);
+            }
+
+            let chunk_id = MetaKey::parse_chunk_meta_key(key);
+            if self.id_range.is_past(&chunk_id) {
+                break;
+            }
+            if !self.id_range.contains(&chunk_id) {
+                it.next();
+                continue;
+            }
+            let chunk_meta =
+                ChunkMeta::deserialize(it.value().unwrap()).map_err(Error::SerializationError)?;
+            let chunk_key = format_hex_chunk_id(key);
+
+            match self.engine.get(&chunk_id)? {
+                Some(chunk) => {
+                    buffer.resize(chunk_meta.len as usize, 0);
+                    chunk.pread(&mut buffer, 0)?;
+                    self.verify_chunk(algo, &chunk_id, &chunk_meta, &buffer, &mut report);
+                }
+                None => {
+                    println!("MISSING   {}", format_hex_chunk_id(&chunk_id));
+                    report.missing_chunks += 1;
+                }
+            }
+
+            last_key = Some(chunk_key);
+            since_checkpoint += 1;
+            if since_checkpoint == CHECKPOINT_INTERVAL && progress.path.is_some() {
+                progress.record(shard, ShardCheckpoint { last_key: last_key.clone(), finished: false, report: report.clone() })?;
+                since_checkpoint = 0;
+            }
+
+            it.next();
+        }
+
+        progress.record(shard, ShardCheckpoint { last_key, finished: true, report: report.clone() })?;
+        Ok(Some(report))
+    }
+
+    fn verify_chunk(&self, algo: ChunkChecksum, chunk_id: &[u8], chunk_meta: &ChunkMeta, data: &[u8], report: &mut VerifyReport) {
+        report.verified_chunks += 1;
+        report.verified_bytes += chunk_meta.len as u64;
+
+        let computed = algo.compute(data);
+        if computed == chunk_meta.checksum {
+            return;
+        }
+
+        match ChunkChecksum::detect(data, chunk_meta.checksum) {
+            Some(other) => {
+                println!("ALGORITHM {} stored {} matches {} instead of {}", format_hex_chunk_id(chunk_id), format_checksum(chunk_meta.checksum), other, algo);
+                report.other_algorithm += 1;
+            }
+            None => {
+                println!("CORRUPT   {} stored {} computed {}, no supported algorithm matches", format_hex_chunk_id(chunk_id), format_checksum(chunk_meta.checksum), format_checksum(computed));
+                report.corrupt_chunks += 1;
+                report.corrupt.push(CorruptChunk {
+                    chunk_id: format_hex_chunk_id(chunk_id),
+                    stored_checksum: chunk_meta.checksum,
+                    computed_checksum: computed,
+                    chunk_size: chunk_meta.pos.chunk_size().0,
+                    position: ChunkPosition {
+                        cluster: chunk_meta.pos.cluster(),
+                        group: chunk_meta.pos.group(),
+                        index: chunk_meta.pos.index(),
+                    },
+                });
+            }
+        }
+    }
+
+    /// Check every chunk's metadata length against the capacity of its slot and read only its
+    /// first and last byte, which catches gross truncation without reading the whole payload
+    pub fn verify_lengths(&self) -> Result<LengthReport> {
+        let mut report = LengthReport::default();
+        let mut byte = [0u8; 1];
+        for entry in self.meta_store.chunks() {
+            let (chunk_id, chunk_meta) = entry?;
+            if self.id_range.is_past(&chunk_id) {
+                break;
+            }
+            if !self.id_range.contains(&chunk_id) {
+                continue;
+            }
+            report.checked_chunks += 1;
+
+            let capacity = chunk_meta.pos.chunk_size().0;
+            if chunk_meta.len as u64 > capacity {
+                println!("LENGTH    {} len {} exceeds capacity {}", format_hex_chunk_id(&chunk_id), chunk_meta.len, capacity);
+                report.implausible.push(ImplausibleLength { chunk_id: format_hex_chunk_id(&chunk_id), len: chunk_meta.len, capacity });
+                continue;
+            }
+
+            let Some(chunk) = self.engine.get(&chunk_id)? else {
+                println!("MISSING   {}", format_hex_chunk_id(&chunk_id));
+                report.missing_chunks += 1;
+                continue;
+            };
+            if chunk_meta.len == 0 {
+                continue;
+            }
+            if let Err(e) = chunk.pread(&mut byte, 0).and_then(|_| chunk.pread(&mut byte, chunk_meta.len - 1)) {
+                println!("TRUNCATED {} len {}: {}", format_hex_chunk_id(&chunk_id), chunk_meta.len, e);
+                report.unreadable_chunks += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Find the chunks holding data whose stored checksum is 0, per size bucket. With `recompute` their data is read
+    /// and checksummed, which tells a checksum that was never computed from data that really hashes to 0
+    pub fn zero_checksum_audit(&self, recompute: bool) -> Result<ZeroChecksumReport> {
+        let mut report = ZeroChecksumReport { recomputed: recompute, ..Default::default() };
+        let mut buckets: BTreeMap<u64, ZeroChecksumBucket> = BTreeMap::new();
+        let mut buffer = Vec::new();
+        for entry in self.meta_store.chunks() {
+            let (chunk_id, chunk_meta) = entry?;
+            if self.id_range.is_past(&chunk_id) {
+                break;
+            }
+            if !self.id_range.contains(&chunk_id) || chunk_meta.len == 0 {
+                continue;
+            }
+            let chunk_size = chunk_meta.pos.chunk_size().0;
+            let bucket = buckets.entry(chunk_size).or_insert_with(|| ZeroChecksumBucket { chunk_size, ..Default::default() });
+            bucket.checked_chunks += 1;
+            if chunk_meta.checksum != 0 {
+                continue;
+            }
+            bucket.zero_checksum_chunks += 1;
+
+            let mut computed_checksum = None;
+            if recompute {
+                match self.engine.get(&chunk_id)? {
+                    Some(chunk) => {
+                        buffer.resize(chunk_meta.len as usize, 0);
+                        chunk.pread(&mut buffer, 0)?;
+                        let computed = compute_checksum(&buffer);
+                        if computed == 0 {
+                            bucket.genuine_zero_chunks += 1;
+                        }
+                        computed_checksum = Some(computed);
+                    }
+                    None => report.missing_chunks += 1,
+                }
+            }
+            report.chunks.push(ZeroChecksumChunk { chunk_id: format_hex_chunk_id(&chunk_id), chunk_size, len: chunk_meta.len, computed_checksum });
+        }
+        report.buckets = buckets.into_values().collect();
+        Ok(report)
+    }
+
+    /// Print the zero checksum counts per size bucket, with `show_chunks` every zero checksum chunk as well
+    pub fn display_zero_checksum_report(&self, report: &ZeroChecksumReport, show_chunks: bool, output_format: OutputFormat) -> Result<()> {
+        match output_format {
+            OutputFormat::Json => println!("{}", to_json(report)?),
+            OutputFormat::JsonLines => {
+                for chunk in &report.chunks {
+                    println!("{}", to_json_line(chunk)?);
+                }
+            }
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {
+                println!("=== Zero Checksum Audit ===");
+                for bucket in &report.buckets {
+                    let genuine = if report.recomputed { format!(", {} of them hash to 0", bucket.genuine_zero_chunks) } else { String::new() };
+                    println!("  {:<10} ({} bytes): {} of {} chunks with data have checksum 0{}",
+                        format_size(bucket.chunk_size), bucket.chunk_size, bucket.zero_checksum_chunks, bucket.checked_chunks, genuine);
+                }
+                if show_chunks && !report.chunks.is_empty() {
+                    println!();
+                    for chunk in &report.chunks {
+                        match chunk.computed_checksum {
+                            Some(computed) => println!("ZERO      {} len {} computed {}", chunk.chunk_id, chunk.len, format_checksum(computed)),
+                            None => println!("ZERO      {} len {}", chunk.chunk_id, chunk.len),
+                        }
+                    }
+                }
+                println!("Suspect chunks: {}", report.suspect_chunks());
+                if report.missing_chunks > 0 {
+                    println!("Missing data: {}", report.missing_chunks);
+                }
+                if report.suspect_chunks() > 0 && !report.recomputed {
+                    println!("(add --recompute to read their data and tell them from data that really hashes to 0)");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn display_length_report(&self, report: &LengthReport) {
+        println!("=== Length Verification ===");
+        println!("Checked chunks: {}", report.checked_chunks);
+        println!("Length exceeds capacity: {}", report.implausible.len());
+        println!("Truncated data: {}", report.unreadable_chunks);
+        println!("Missing data: {}", report.missing_chunks);
+    }
+
+    pub fn display_report(&self, algo: ChunkChecksum, report: &VerifyReport) {
+        println!("=== Checksum Verification ({}) ===", algo);
+        println!("Verified chunks: {} ({})", report.verified_chunks, format_size(report.verified_bytes));
+        println!("Matching: {}", report.verified_chunks - report.other_algorithm - report.corrupt_chunks);
+        println!("Matching another algorithm: {}", report.other_algorithm);
+        println!("Corrupt: {}", report.corrupt_chunks);
+        println!("Missing data: {}", report.missing_chunks);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::utils::meta_store_config;
+    use derse::Serialize;
+
+    fn create_engine(path: &Path) -> Engine {
+        let config = EngineConfig {
+            path: path.into(),
+            create: true,
+            prefix_len: 4,
+            ..Default::default()
+        };
+        Engine::open(&config).unwrap()
+    }
+
+    /// Rewrite the stored meta of a chunk, leaving its data as it is
+    fn update_chunk_meta(engine: &Engine, chunk_id: &[u8], update: impl FnOnce(&mut ChunkMeta)) -> ChunkMeta {
+        let mut chunk_meta = engine.meta_store.get_chunk_meta(chunk_id).unwrap().unwrap();
+        update(&mut chunk_meta);
+        let bytes: derse::DownwardBytes = chunk_meta.serialize().unwrap();
+        let mut write_batch = RocksDB::new_write_batch();
+        write_batch.put(MetaKey::chunk_meta_key_with(engine.meta_store.chunk_meta_key_prefix(), chunk_id), &bytes[..]);
+        engine.meta_store.write(write_batch, true).unwrap();
+        chunk_meta
+    }
+
+    /// Overwrite the stored checksum of a chunk so its data no longer matches
+    fn corrupt_checksum(engine: &Engine, chunk_id: &[u8]) -> u32 {
+        update_chunk_meta(engine, chunk_id, |chunk_meta| chunk_meta.checksum ^= 0xdeadbeef).checksum
+    }
+
+    #[test]
+    fn test_verify_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let engine = create_engine(dir.path());
+            for i in 0..8u32 {
+                engine.put(&i.to_be_bytes(), &[i as u8; 4096], 1, 1).unwrap();
+            }
+        }
+
+        let verifier = ChunkVerifier::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, None).unwrap();
+        let report = verifier.verify_checksums(ChunkChecksum::Crc32c, 1, None, &|| false).unwrap().unwrap();
+        assert_eq!(report, VerifyReport { verified_chunks: 8, verified_bytes: 8 * 4096, ..Default::default() });
+        assert_eq!(report.exit_status(), ExitStatus::Ok);
+
+        let report = verifier.verify_checksums(ChunkChecksum::Crc32, 1, None, &|| false).unwrap().unwrap();
+        assert_eq!(report.other_algorithm, 8);
+        assert_eq!(report.mismatched_chunks(), 8);
+        assert_eq!(report.exit_status(), ExitStatus::Mismatch);
+    }
+
+    #[test]
+    fn test_zero_checksum_audit() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let engine = create_engine(dir.path());
+            for i in 0..6u32 {
+                engine.put(&i.to_be_bytes(), &[i as u8; 4096], 1, 1).unwrap();
+            }
+            // Empty data checksums to 0 anyway, it is not counted
+            engine.put(&6u32.to_be_bytes(), &[], 1, 1).unwrap();
+            for i in [1u32, 3] {
+                update_chunk_meta(&engine, &i.to_be_bytes(), |chunk_meta| chunk_meta.checksum = 0);
+            }
+        }
+
+        let verifier = ChunkVerifier::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, None).unwrap();
+        let report = verifier.zero_checksum_audit(false).unwrap();
+        assert_eq!(report.buckets, [ZeroChecksumBucket { chunk_size: 65536, checked_chunks: 6, zero_checksum_chunks: 2, genuine_zero_chunks: 0 }]);
+        // Store order visits the largest chunk ID first
+        let chunk_ids: Vec<_> = report.chunks.iter().map(|chunk| chunk.chunk_id.clone()).collect();
+        assert_eq!(chunk_ids, [format_hex_chunk_id(&3u32.to_be_bytes()), format_hex_chunk_id(&1u32.to_be_bytes())]);
+        assert!(report.chunks.iter().all(|chunk| chunk.len == 4096 && chunk.computed_checksum.is_none()));
+        assert_eq!(report.suspect_chunks(), 2);
+        assert_eq!(report.exit_status(), ExitStatus::Corruption);
+
+        // Reading the data shows it does not hash to 0, the checksum was never computed
+        let report = verifier.zero_checksum_audit(true).unwrap();
+        assert_eq!(report.chunks[1].computed_checksum, Some(compute_checksum(&[1u8; 4096])));
+        assert_eq!((report.buckets[0].genuine_zero_chunks, report.suspect_chunks()), (0, 2));
+        let genuine = ZeroChecksumReport { buckets: vec![ZeroChecksumBucket { genuine_zero_chunks: 2, ..report.buckets[0].clone() }], ..report };
+        assert_eq!(genuine.exit_status(), ExitStatus::Ok);
+    }
+
+    #[test]
+    fn test_verify_lengths() {
+        let dir = tempfile::tempdir().unwrap();
+        let capacity = {
+            let engine = create_engine(dir.path());
+            for i in 0..4u32 {
+                engine.put(&i.to_be_bytes(), &[i as u8; 4096], 1, 1).unwrap();
+            }
+            let chunk_meta = update_chunk_meta(&engine, &2u32.to_be_bytes(), |chunk_meta| chunk_meta.len = chunk_meta.pos.chunk_size().0 as u32 + 1);
+            chunk_meta.pos.chunk_size().0
+        };
+
+        let verifier = ChunkVerifier::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, None).unwrap();
+        let report = verifier.verify_lengths().unwrap();
+        assert_eq!(report, LengthReport {
+            checked_chunks: 4,
+            implausible: vec![ImplausibleLength { chunk_id: format_hex_chunk_id(&2u32.to_be_bytes()), len: capacity as u32 + 1, capacity }],
+            ..Default::default()
+        });
+        assert_eq!(report.exit_status(), ExitStatus::Corruption);
+    }
+
+    /// Write 64 chunks spread over the whole first byte range, corrupting four of them
+    fn create_corrupted_store(path: &Path) -> Vec<(String, u32)> {
+        let engine = create_engine(path);
+        for i in 0..64u32 {
+            engine.put(&(i << 26 | i).to_be_bytes(), &[i as u8; 4096], 1, 1).unwrap();
+        }
+        [3u32, 17, 40, 63].iter()
+            .map(|i| {
+                let chunk_id = (i << 26 | i).to_be_bytes();
+                (format_hex_chunk_id(&chunk_id), corrupt_checksum(&engine, &chunk_id))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_corrupt_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let corrupted = create_corrupted_store(dir.path());
+
+        let verifier = ChunkVerifier::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, None).unwrap();
+        let single = verifier.verify_checksums(ChunkChecksum::Crc32c, 1, None, &|| false).unwrap().unwrap();
+        let report = verifier.verify_checksums(ChunkChecksum::Crc32c, 7, None, &|| false).unwrap().unwrap();
+        assert_eq!(report, single);
+        assert_eq!(report.verified_chunks, 64);
+        assert_eq!(report.corrupt_chunks, 4);
+        assert_eq!(report.exit_status(), ExitStatus::Corruption);
+
+        let manifest = dir.path().join("corrupt.jsonl");
+        report.write_corrupt_manifest(manifest.to_str().unwrap(), false, false).unwrap();
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&manifest).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let found: Vec<(String, u32)> = lines.iter()
+            .map(|line| (line["chunk_id"].as_str().unwrap().to_string(), line["stored_checksum"].as_u64().unwrap() as u32))
+            .collect();
+        assert_eq!(found, corrupted);
+        assert!(lines.iter().all(|line| line["chunk_size"] == 65536 && line["position"]["index"].is_u64()));
+        assert!(lines.iter().all(|line| line["schema_version"] == JSON_SCHEMA_VERSION));
+        
+        // A manifest of another schema version is rejected instead of misread
+        let old = dir.path().join("old.jsonl");
+        std::fs::write(&old, std::fs::read_to_string(&manifest).unwrap().replace(&format!("\"schema_version\":{}", JSON_SCHEMA_VERSION), "\"schema_version\":0")).unwrap();
+        let err = read_corrupt_manifest(old.to_str().unwrap()).unwrap_err().to_string();
+        assert!(err.contains("line 1: schema_version 0 is not supported"), "{}", err);
+        
+        // A compressed manifest holds the same lines and is read back the same way
+        let compressed = dir.path().join("corrupt.jsonl.gz");
+        report.write_corrupt_manifest(compressed.to_str().unwrap(), true, false).unwrap();
+        assert!(std::fs::read(&compressed).unwrap().starts_with(&[0x1f, 0x8b]));
+        assert_eq!(read_text_file(compressed.to_str().unwrap()).unwrap(), std::fs::read_to_string(&manifest).unwrap());
+        assert_eq!(read_corrupt_manifest(compressed.to_str().unwrap()).unwrap(), read_corrupt_manifest(manifest.to_str().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_verify_id_range() {
+        let dir = tempfile::tempdir().unwrap();
+        create_corrupted_store(dir.path());
+
+        // Chunks 10 to 40, holding corrupt chunks 17 and 40
+        let id_range = IdRange { from: Some((10u32 << 26).to_be_bytes().to_vec()), to: Some((41u32 << 26).to_be_bytes().to_vec()) };
+        let verifier = ChunkVerifier::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, None).unwrap().with_id_range(id_range);
+        for threads in [1, 7] {
+            let report = verifier.verify_checksums(ChunkChecksum::Crc32c, threads, None, &|| false).unwrap().unwrap();
+            assert_eq!(report.verified_chunks, 31);
+            let corrupt: Vec<_> = report.corrupt.iter().map(|chunk| chunk.chunk_id.clone()).collect();
+            assert_eq!(corrupt, [17u32, 40].map(|i| format_hex_chunk_id(&(i << 26 | i).to_be_bytes())));
+        }
+        assert_eq!(verifier.verify_lengths().unwrap().checked_chunks, 31);
+    }
+
+    #[test]
+    fn test_delete_from_manifest() {
+        use super::super::chunk_writer::ChunkWriter;
+
+        let dir = tempfile::tempdir().unwrap();
+        let corrupted = create_corrupted_store(dir.path());
+        let manifest = dir.path().join("corrupt.jsonl");
+        {
+            let verifier = ChunkVerifier::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, None).unwrap();
+            let report = verifier.verify_checksums(ChunkChecksum::Crc32c, 1, None, &|| false).unwrap().unwrap();
+            report.write_corrupt_manifest(manifest.to_str().unwrap(), false, false).unwrap();
+        }
+
+        let chunk_ids = read_corrupt_manifest(manifest.to_str().unwrap()).unwrap();
+        let listed: Vec<String> = chunk_ids.iter().map(|chunk_id| format_hex_chunk_id(chunk_id)).collect();
+        assert_eq!(listed, corrupted.iter().map(|(chunk_id, _)| chunk_id.clone()).collect::<Vec<_>>());
+        {
+            let writer = ChunkWriter::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadWrite), None, None).unwrap();
+            assert_eq!(writer.delete_chunks(&chunk_ids).unwrap(), 4);
+            // A second run finds them all gone
+            assert_eq!(writer.delete_chunks(&chunk_ids).unwrap(), 0);
+        }
+
+        let verifier = ChunkVerifier::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, None).unwrap();
+        let report = verifier.verify_checksums(ChunkChecksum::Crc32c, 1, None, &|| false).unwrap().unwrap();
+        assert_eq!(report.verified_chunks, 60);
+        assert_eq!(report.exit_status(), ExitStatus::Ok);
+    }
+
+    #[test]
+    fn test_verify_resume_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        create_corrupted_store(dir.path());
+        let verifier = ChunkVerifier::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, None).unwrap();
+        let expected = verifier.verify_checksums(ChunkChecksum::Crc32c, 3, None, &|| false).unwrap().unwrap();
+
+        // Stop the scan after 20 chunks across all threads, as if interrupted
+        let checkpoint = dir.path().join("verify.checkpoint");
+        let visited = std::sync::atomic::AtomicU64::new(0);
+        let should_stop = || visited.fetch_add(1, std::sync::atomic::Ordering::SeqCst) >= 20;
+        let report = verifier.verify_checksums(ChunkChecksum::Crc32c, 3, Some(&checkpoint), &should_stop).unwrap();
+        assert!(report.is_none());
+        assert!(checkpoint.exists());
+
+        // Resuming with other options is refused
+        assert!(verifier.verify_checksums(ChunkChecksum::Crc32c, 2, Some(&checkpoint), &|| false).is_err());
+
+        let report = verifier.verify_checksums(ChunkChecksum::Crc32c, 3, Some(&checkpoint), &|| false).unwrap().unwrap();
+        assert_eq!(report, expected);
+        assert!(!checkpoint.exists());
+    }
+
+    #[test]
+    fn test_verify_chunk_meta_key_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let config = EngineConfig {
+                path: dir.path().into(),
+                create: true,
+                prefix_len: 4,
+                chunk_meta_key_prefix: Some(10),
+                ..Default::default()
+            };
+            let engine = Engine::open(&config).unwrap();
+            for i in 0..8u32 {
+                engine.put(&(i << 24).to_be_bytes(), &[i as u8; 4096], 1, 1).unwrap();
+            }
+            corrupt_checksum(&engine, &(3u32 << 24).to_be_bytes());
+        }
+
+        let meta_config = MetaStoreConfig { chunk_meta_key_prefix: 10, ..meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly) };
+        let verifier = ChunkVerifier::new(&meta_config, None, None).unwrap();
+        for threads in [1, 4] {
+            let report = verifier.verify_checksums(ChunkChecksum::Crc32c, threads, None, &|| false).unwrap().unwrap();
+            assert_eq!((report.verified_chunks, report.mismatched_chunks()), (8, 1));
+        }
+
+        // The default layout finds none of them
+        let verifier = ChunkVerifier::new(&meta_store_config(&dir.path().join("meta"), DbOpenMode::ReadOnly), None, None).unwrap();
+        let report = verifier.verify_checksums(ChunkChecksum::Crc32c, 4, None, &|| false).unwrap().unwrap();
+        assert_eq!(report.verified_chunks, 0);
+    }
+}
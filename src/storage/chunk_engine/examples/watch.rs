@@ -0,0 +1,204 @@
+use chunk_engine::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use super::args::OutputFormat;
+use super::chunk_lister::{ChunkLister, Summary};
+use super::terminal::{clear_screen, hide_cursor, install_interrupt_handler, interrupted, show_cursor};
+use super::utils::{format_hex_chunk_id, format_size, format_timestamp, open_meta_store, to_json_line};
+
+/// One refresh of the summary, emitted as a single JSON line in watch mode
+#[derive(Serialize)]
+struct SummarySnapshot<'a> {
+    /// Microseconds since the Unix epoch
+    timestamp: u64,
+    #[serde(flatten)]
+    summary: &'a Summary,
+}
+
+/// Re-run the summary every `interval` until interrupted with Ctrl-C
+pub fn watch_summary(meta_config: &MetaStoreConfig, output_format: OutputFormat, interval: Duration, color: bool, open_timeout: Option<Duration>) -> Result<()> {
+    install_interrupt_handler();
+
+    let table = output_format == OutputFormat::Table;
+    if table {
+        hide_cursor();
+    }
+    let result = watch_loop(meta_config, output_format, interval, color, open_timeout);
+    if table {
+        show_cursor();
+        println!();
+    }
+    result
+}
+
+fn watch_loop(meta_config: &MetaStoreConfig, output_format: OutputFormat, interval: Duration, color: bool, open_timeout: Option<Duration>) -> Result<()> {
+    let mut chunk_lister = ChunkLister::new(open_meta_store(meta_config, open_timeout)?).with_color(color);
+
+    while !interrupted() {
+        let summary = chunk_lister.collect_summary()?;
+        let timestamp = ChunkMeta::now();
+
+        match output_format {
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Tsv => {
+                clear_screen();
+                println!("Every {}s: {}    (Ctrl-C to quit)\n", interval.as_secs(), format_timestamp(timestamp));
+                chunk_lister.display_summary(&summary);
+            }
+            // A snapshot per line either way, so the output can be appended to a log
+            OutputFormat::Json | OutputFormat::JsonLines => {
+                println!("{}", to_json_line(&SummarySnapshot { timestamp, summary: &summary })?);
+            }
+        }
+
+        if !sleep_unless_interrupted(interval) {
+            break;
+        }
+
+        // A secondary follows the primary in place, a read-only snapshot has to be reopened
+        match meta_config.rocksdb.mode {
+            DbOpenMode::Secondary { .. } => chunk_lister.catch_up_with_primary()?,
+            _ => chunk_lister = ChunkLister::new(open_meta_store(meta_config, open_timeout)?).with_color(color),
+        }
+    }
+
+    Ok(())
+}
+
+/// A chunk that appeared since the previous poll in follow mode
+#[derive(Debug, PartialEq, Serialize)]
+struct NewChunk {
+    /// Microseconds since the Unix epoch, when the chunk was last written
+    timestamp: u64,
+    chunk_id: String,
+    chunk_size: u64,
+    len: u32,
+}
+
+/// Print the chunks a live primary creates, polling every `interval` until interrupted with Ctrl-C
+pub fn follow_chunks(meta_config: &MetaStoreConfig, output_format: OutputFormat, interval: Duration, open_timeout: Option<Duration>) -> Result<()> {
+    if !matches!(meta_config.rocksdb.mode, DbOpenMode::Secondary { .. }) {
+        return Err(Error::InvalidArg("--follow needs --mode secondary to see the chunks a running primary writes".to_string()));
+    }
+    install_interrupt_handler();
+
+    let meta_store = open_meta_store(meta_config, open_timeout)?;
+    let mut follower = ChunkFollower::new(&meta_store)?;
+    let table = !matches!(output_format, OutputFormat::Json | OutputFormat::JsonLines);
+    if table {
+        println!("Following {} chunks, new ones are printed as they appear    (Ctrl-C to quit)", follower.seen.len());
+    }
+
+    while sleep_unless_interrupted(interval) {
+        meta_store.catch_up_with_primary()?;
+        for chunk in follower.poll(&meta_store)? {
+            if table {
+                println!("{}  {}  {:>10}  len {}", format_timestamp(chunk.timestamp), chunk.chunk_id, format_size(chunk.chunk_size), chunk.len);
+            } else {
+                println!("{}", to_json_line(&chunk)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The chunks seen so far, and the newest timestamp among them that the next poll starts from
+struct ChunkFollower {
+    seen: HashSet<Vec<u8>>,
+    since: u64,
+}
+
+impl ChunkFollower {
+    /// Every chunk already in the store counts as seen
+    fn new(meta_store: &MetaStore) -> Result<Self> {
+        let mut follower = Self { seen: HashSet::new(), since: 0 };
+        for entry in meta_store.chunks() {
+            let (chunk_id, chunk_meta) = entry?;
+            follower.since = follower.since.max(chunk_meta.timestamp);
+            follower.seen.insert(chunk_id.to_vec());
+        }
+        Ok(follower)
+    }
+
+    /// The chunks written since the last poll that were never seen, oldest first. Only the timestamp index
+    /// from `since` on is read, a rewrite of a known chunk moves its timestamp but is not reported.
+    fn poll(&mut self, meta_store: &MetaStore) -> Result<Vec<NewChunk>> {
+        let mut new_chunks = vec![];
+        for (timestamp, chunk_id) in meta_store.query_chunks_since(self.since)? {
+            self.since = self.since.max(timestamp);
+            if self.seen.contains(&chunk_id[..]) {
+                continue;
+            }
+            let Some(chunk_meta) = meta_store.get_chunk_meta(&chunk_id)? else {
+                continue;
+            };
+            new_chunks.push(NewChunk {
+                timestamp,
+                chunk_id: format_hex_chunk_id(&chunk_id),
+                chunk_size: chunk_meta.pos.chunk_size().0,
+                len: chunk_meta.len,
+            });
+            self.seen.insert(chunk_id.to_vec());
+        }
+        new_chunks.sort_by_key(|chunk| chunk.timestamp);
+        Ok(new_chunks)
+    }
+}
+
+/// Sleep for `duration`, returning false as soon as Ctrl-C is pressed
+fn sleep_unless_interrupted(duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while !interrupted() {
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        std::thread::sleep((deadline - now).min(Duration::from_millis(100)));
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follow_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().into(),
+                create: true,
+                ..Default::default()
+            },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        let add = |chunk_id: u64, index: u8, timestamp: u64| {
+            let chunk_meta = ChunkMeta { pos: Position::new(GroupId::default(), index), timestamp, len: index as u32, ..Default::default() };
+            meta_store.add_chunk(&chunk_id.to_be_bytes(), &chunk_meta, false).unwrap();
+        };
+        add(1 << 32, 0, 100);
+        add(2 << 32, 1, 200);
+
+        let mut follower = ChunkFollower::new(&meta_store).unwrap();
+        assert_eq!(follower.seen.len(), 2);
+        assert!(follower.poll(&meta_store).unwrap().is_empty());
+
+        // New chunks under new and known prefixes, reported oldest first and only once
+        add(3 << 32, 2, 300);
+        add(1 << 32 | 1, 3, 250);
+        let new_chunks = follower.poll(&meta_store).unwrap();
+        let ids: Vec<_> = new_chunks.iter().map(|chunk| (chunk.timestamp, chunk.chunk_id.clone(), chunk.len)).collect();
+        assert_eq!(ids, [
+            (250, format_hex_chunk_id(&(1u64 << 32 | 1).to_be_bytes()), 3),
+            (300, format_hex_chunk_id(&(3u64 << 32).to_be_bytes()), 2),
+        ]);
+        assert_eq!(new_chunks[0].chunk_size, GroupId::default().chunk_size().0);
+        assert!(follower.poll(&meta_store).unwrap().is_empty());
+        assert_eq!(follower.since, 300);
+    }
+}
@@ -87,15 +87,39 @@ impl Allocator {
         Ok(task)
     }
 
+    pub fn taken_positions(&self) -> Vec<Position> {
+        let allocator = self.allocator.lock().unwrap();
+        allocator.taken_positions()
+    }
+
+    /// Free a taken position that no chunk references, in the meta store first and then in memory.
+    pub fn release_position(&self, pos: Position, meta_store: &MetaStore) -> Result<()> {
+        let mut allocator = self.allocator.lock().unwrap();
+        allocator.check_unreferenced(pos)?;
+        meta_store.release_position(pos)?;
+        allocator.deallocate(pos);
+        Ok(())
+    }
+
     pub fn get_compact_task(&self, max_reserved: u64) -> Option<GroupId> {
         let mut allocator = self.allocator.lock().unwrap();
         allocator.get_compact_task(max_reserved)
     }
 
-    pub fn finish_compact_task(&self, group_id: GroupId) {
+    pub fn finish_compact_task(&self, group_id: GroupId) -> bool {
         let mut allocator = self.allocator.lock().unwrap();
         allocator.finish_compact_task(group_id)
     }
+
+    pub fn defrag_plan(&self) -> (Vec<GroupId>, DefragStats) {
+        let allocator = self.allocator.lock().unwrap();
+        allocator.defrag_plan()
+    }
+
+    pub fn freeze_group(&self, group_id: GroupId) -> bool {
+        let mut allocator = self.allocator.lock().unwrap();
+        allocator.freeze_group(group_id)
+    }
 }
 
 impl Drop for Allocator {
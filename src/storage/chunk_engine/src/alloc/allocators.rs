@@ -11,8 +11,7 @@ pub struct Allocators {
 impl Allocators {
     pub fn new(path: &Path, create: bool, meta_store: Arc<MetaStore>) -> Result<Self> {
         let mut allocators = vec![];
-        for i in 0..CHUNK_SIZE_NUMBER {
-            let chunk_size = CHUNK_SIZE_SMALL * (1 << i);
+        for chunk_size in Size::all_buckets() {
             let allocator = Self::create(path, create, &meta_store, chunk_size)?;
             allocators.push(allocator);
         }
@@ -42,10 +41,7 @@ impl Allocators {
 
     pub fn select_by_pos(&self, pos: Position) -> Result<&Arc<Allocator>> {
         let chunk_size = pos.chunk_size();
-        if chunk_size.is_power_of_two()
-            && CHUNK_SIZE_SMALL <= chunk_size
-            && chunk_size <= CHUNK_SIZE_ULTRA
-        {
+        if chunk_size.is_valid_bucket() {
             Ok(&self.vec[chunk_size.trailing_zeros() as usize - CHUNK_SIZE_SHIFT])
         } else {
             Err(Error::InvalidArg(format!(
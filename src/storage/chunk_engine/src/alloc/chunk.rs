@@ -63,8 +63,21 @@ impl Chunk {
     }
 
     pub fn copy_chunk(&self) -> Result<Chunk> {
+        self.copy_chunk_to(&self.allocator)
+    }
+
+    /// Copy the chunk into a new slot of `allocator`, whose size bucket may differ from this one
+    /// as long as the data fits.
+    pub fn copy_chunk_to(&self, allocator: &Arc<Allocator>) -> Result<Chunk> {
+        if self.meta.len as u64 > allocator.clusters.chunk_size.0 {
+            return Err(Error::InvalidArg(format!(
+                "chunk of {} bytes does not fit in size {:?}",
+                self.meta.len, allocator.clusters.chunk_size
+            )));
+        }
+
         // 1. allocate new chunk.
-        let mut new_chunk = self.allocator.allocate(true)?;
+        let mut new_chunk = allocator.allocate(true)?;
 
         // 2. copy meta.
         new_chunk.meta = ChunkMeta {
@@ -86,6 +99,7 @@ impl Chunk {
         Ok(new_chunk)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn copy_on_write(
         &self,
         data: &[u8],
@@ -154,7 +168,7 @@ impl Chunk {
                 checksum
             } else {
                 metrics.checksum_recalculate.fetch_add(1, Ordering::AcqRel);
-                crc32c::crc32c(&vec[..new_len as usize])
+                compute_checksum(&vec[..new_len as usize])
             })
         })?;
         let latency = std::time::Instant::now().duration_since(begin2).as_micros() as _;
@@ -192,7 +206,7 @@ impl Chunk {
                 let len = offset.next_multiple_of(ALIGN_SIZE.into());
                 self.pread(&mut vec[..len as usize], 0)?;
                 self.meta.len = offset;
-                self.meta.checksum = crc32c::crc32c(&vec[..offset as usize]);
+                self.meta.checksum = compute_checksum(&vec[..offset as usize]);
                 Result::Ok(())
             });
         }
@@ -288,6 +302,36 @@ impl Chunk {
         self.allocator.clusters.pwrite(self.meta.pos, buf, offset)
     }
 
+    /// Map the data of the chunk read-only, an alternative to `pread` that does not copy it
+    /// into a buffer.
+    ///
+    /// # Safety
+    ///
+    /// The mapping does not hold a reference to the chunk and shares its pages with the data
+    /// file. While the region is alive, the chunk's slot must not be written, by this engine or
+    /// any other process, e.g. through an update or a new chunk allocated at the released
+    /// position. See `MmapRegion::map`.
+    pub unsafe fn mmap(&self) -> Result<MmapRegion> {
+        self.mmap_range(0, self.meta.len)
+    }
+
+    /// Map `len` bytes of the chunk starting at `offset`, which must lie within its capacity.
+    ///
+    /// # Safety
+    ///
+    /// See `mmap`.
+    pub unsafe fn mmap_range(&self, offset: u32, len: u32) -> Result<MmapRegion> {
+        if offset as u64 + len as u64 > self.capacity() as u64 {
+            return Err(Error::InvalidArg(format!(
+                "mmap offset {} len {} is beyond the chunk capacity {}",
+                offset,
+                len,
+                self.capacity()
+            )));
+        }
+        self.allocator.clusters.mmap(self.meta.pos, offset, len)
+    }
+
     pub fn fd_and_offset(&self) -> FdAndOffset {
         self.allocator.clusters.fd_and_offset(self.meta.pos)
     }
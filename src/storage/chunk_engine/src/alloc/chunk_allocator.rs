@@ -1,9 +1,57 @@
 use super::super::*;
-use std::collections::hash_map::Entry;
+use std::collections::{hash_map::Entry, HashMap};
 
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DefragStats {
+    pub moved_chunks: u64,
+    pub freed_groups: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupOccupancy {
+    pub group_id: GroupId,
+    pub used: u32,
+    pub free: u32,
+    pub full: bool,
+}
+
+/// Slots of all full and active groups against the slots they have taken.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PackingStats {
+    pub groups: u64,
+    pub capacity: u64,
+    pub occupied: u64,
+}
+
+impl PackingStats {
+    /// Ratio of occupied slots to capacity, or 0 without groups.
+    pub fn efficiency(&self) -> f64 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+        self.occupied as f64 / self.capacity as f64
+    }
+}
+
+/// Everything a `ChunkAllocator` tracks, each group list sorted, for reproducing allocator bugs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllocatorState {
+    pub chunk_size: Size,
+    pub allocated_chunks: u64,
+    pub reserved_chunks: u64,
+    pub full_groups: Vec<GroupId>,
+    pub active_groups: Vec<(GroupId, GroupState)>,
+    pub frozen_groups: Vec<(GroupId, GroupState)>,
+    /// Groups holding no chunk that are kept for the next allocations.
+    pub empty_groups: Vec<GroupId>,
+    /// Gaps below `next_group_id` whose files were never allocated.
+    pub unallocated_groups: Vec<GroupId>,
+    pub next_group_id: GroupId,
+}
+
 pub struct ChunkAllocator {
     pub full_groups: ShardsSet<GroupId>,
     pub active_groups: ShardsMap<GroupId, GroupState>,
@@ -33,65 +81,39 @@ impl ChunkAllocator {
         counter: Arc<AllocatorCounter>,
         chunk_size: Size,
     ) -> Result<Self> {
-        let mut full_groups = ShardsSet::with_capacity(4096);
-        let mut active_groups = ShardsMap::with_capacity(4096);
-        let frozen_groups = ShardsMap::with_capacity(4096);
-        let mut active_levels = std::array::from_fn(|_| ShardsSet::with_capacity(4096));
-
-        let mut allocated_groups = ShardsSet::with_capacity(4096);
-        let mut unallocated_groups = ShardsSet::with_capacity(4096);
-        let mut current = GroupId::new(chunk_size, 0, 0);
-
-        let mut allocated_count: u64 = 0;
-        let mut reserved_count: u64 = 0;
-
-        let prefix = MetaKey::group_bits_chunk_size_prefix(current);
+        let mut loader = AllocatorLoader::new(chunk_size);
+        let prefix = MetaKey::group_bits_chunk_size_prefix(GroupId::new(chunk_size, 0, 0));
         it.iterate(prefix, |key, value| {
-            let group_id = MetaKey::parse_group_bits_key(key)?;
-            let group_state = GroupState::from(value)?;
-
-            assert!(
-                current <= group_id,
-                "current {current:?} > next {group_id:?}"
+            loader.add(
+                MetaKey::parse_group_bits_key(key)?,
+                GroupState::from(value)?,
             );
-            while current < group_id {
-                unallocated_groups.insert(current);
-                current.next();
-            }
-            current.next();
-
-            allocated_count += GroupState::TOTAL_BITS as u64;
-            if group_state.is_empty() {
-                allocated_groups.insert(group_id);
-                reserved_count += GroupState::TOTAL_BITS as u64;
-            } else if group_state.is_full() {
-                full_groups.insert(group_id);
-            } else {
-                reserved_count += GroupState::TOTAL_BITS as u64 - group_state.count() as u64;
-                active_levels[group_state.level() as usize].insert(group_id);
-                active_groups.insert(group_id, group_state);
-            }
-
             Ok(())
         })?;
+        Ok(loader.finish(counter))
+    }
 
-        counter.init(allocated_count, reserved_count);
-        let chunk_allocator = ChunkAllocator {
-            full_groups,
-            active_groups,
-            active_levels,
-            frozen_groups,
-            counter: counter.clone(),
-            group_allocator: GroupAllocator {
-                allocated_groups,
-                unallocated_groups,
-                next_group_id: current,
-                counter,
-            },
-            position_rc: ShardsMap::with_capacity(1 << 20),
-        };
-
-        Ok(chunk_allocator)
+    /// `load` for several distinct chunk sizes in a single pass over the group bits, one allocator per
+    /// counter in the same order. Group bits of other chunk sizes are skipped.
+    pub fn load_all(
+        mut it: RocksDBIterator,
+        counters: Vec<Arc<AllocatorCounter>>,
+    ) -> Result<Vec<Self>> {
+        let mut loaders = counters
+            .iter()
+            .map(|counter| (counter.chunk_size, AllocatorLoader::new(counter.chunk_size)))
+            .collect::<HashMap<_, _>>();
+        it.iterate(MetaKey::group_bits_key_prefix(), |key, value| {
+            let group_id = MetaKey::parse_group_bits_key(key)?;
+            if let Some(loader) = loaders.get_mut(&group_id.chunk_size()) {
+                loader.add(group_id, GroupState::from(value)?);
+            }
+            Ok(())
+        })?;
+        Ok(counters
+            .into_iter()
+            .map(|counter| loaders.remove(&counter.chunk_size).unwrap().finish(counter))
+            .collect())
     }
 
     pub fn allocate(&mut self, clusters: &Clusters, allow_to_allocate: bool) -> Result<Position> {
@@ -204,6 +226,175 @@ impl ChunkAllocator {
         self.counter.deallocate_chunk();
     }
 
+    /// Every position whose bit is set in a full, active or frozen group, sorted.
+    pub fn taken_positions(&self) -> Vec<Position> {
+        let all = |group_id: GroupId| {
+            (0..GroupState::TOTAL_BITS).map(move |index| Position::new(group_id, index as u8))
+        };
+        let mut positions = self
+            .full_groups
+            .iter()
+            .flat_map(|&group_id| all(group_id))
+            .collect::<Vec<_>>();
+        for (&group_id, state) in self.active_groups.iter().chain(self.frozen_groups.iter()) {
+            positions.extend(all(group_id).filter(|pos| state.check(pos.index())));
+        }
+        positions.sort();
+        positions
+    }
+
+    /// Check that `pos` is taken but not referenced by any chunk, so it can be deallocated.
+    pub fn check_unreferenced(&self, pos: Position) -> Result<()> {
+        let group_id = pos.group_id();
+        let taken = match self
+            .active_groups
+            .get(&group_id)
+            .or_else(|| self.frozen_groups.get(&group_id))
+        {
+            Some(state) => state.check(pos.index()),
+            None => self.full_groups.contains(&group_id),
+        };
+        if !taken {
+            return Err(Error::InvalidArg(format!("position {pos:?} is not taken")));
+        }
+        if self.position_rc.get(&pos).is_some() {
+            return Err(Error::InvalidArg(format!(
+                "position {pos:?} is referenced by a chunk"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Free slot count of every active group.
+    pub fn active_group_free_slots(&self) -> impl Iterator<Item = (GroupId, u32)> + '_ {
+        self.active_groups
+            .iter()
+            .map(|(&group_id, state)| (group_id, GroupState::TOTAL_BITS as u32 - state.count()))
+    }
+
+    /// Occupancy of every full and active group, sorted by group index.
+    pub fn groups(&self) -> impl Iterator<Item = GroupOccupancy> {
+        let full = self.full_groups.iter().map(|&group_id| GroupOccupancy {
+            group_id,
+            used: GroupState::TOTAL_BITS as u32,
+            free: 0,
+            full: true,
+        });
+        let active = self.active_groups.iter().map(|(&group_id, state)| {
+            let used = state.count();
+            GroupOccupancy {
+                group_id,
+                used,
+                free: GroupState::TOTAL_BITS as u32 - used,
+                full: false,
+            }
+        });
+        let mut groups = full.chain(active).collect::<Vec<_>>();
+        groups.sort_by_key(|g| g.group_id.index());
+        groups.into_iter()
+    }
+
+    /// Ratio of free slots to total slots across active groups, or 0 without active groups.
+    pub fn fragmentation(&self) -> f64 {
+        let total_slots = self.active_groups.len() as u64 * GroupState::TOTAL_BITS as u64;
+        if total_slots == 0 {
+            return 0.0;
+        }
+        let free_slots: u64 = self
+            .active_group_free_slots()
+            .map(|(_, free)| free as u64)
+            .sum();
+        free_slots as f64 / total_slots as f64
+    }
+
+    /// A snapshot of the groups and counters.
+    pub fn state(&self) -> AllocatorState {
+        let sorted = |groups: &ShardsSet<GroupId>| {
+            let mut groups = groups.iter().copied().collect::<Vec<_>>();
+            groups.sort();
+            groups
+        };
+        let sorted_states = |groups: &ShardsMap<GroupId, GroupState>| {
+            let mut groups = groups
+                .iter()
+                .map(|(&group_id, &state)| (group_id, state))
+                .collect::<Vec<_>>();
+            groups.sort_by_key(|&(group_id, _)| group_id);
+            groups
+        };
+        AllocatorState {
+            chunk_size: self.counter.chunk_size,
+            allocated_chunks: self.counter.allocated_chunks(),
+            reserved_chunks: self.counter.reserved_chunks(),
+            full_groups: sorted(&self.full_groups),
+            active_groups: sorted_states(&self.active_groups),
+            frozen_groups: sorted_states(&self.frozen_groups),
+            empty_groups: sorted(&self.group_allocator.allocated_groups),
+            unallocated_groups: sorted(&self.group_allocator.unallocated_groups),
+            next_group_id: self.group_allocator.next_group_id,
+        }
+    }
+
+    /// Number of chunk slots in one group.
+    pub const fn group_capacity() -> u32 {
+        GroupState::TOTAL_BITS as u32
+    }
+
+    /// How tightly the full and active groups are packed.
+    pub fn packing(&self) -> PackingStats {
+        let groups = (self.full_groups.len() + self.active_groups.len()) as u64;
+        let occupied = self.full_groups.len() as u64 * Self::group_capacity() as u64
+            + self
+                .active_groups
+                .iter()
+                .map(|(_, state)| state.count() as u64)
+                .sum::<u64>();
+        PackingStats {
+            groups,
+            capacity: groups * Self::group_capacity() as u64,
+            occupied,
+        }
+    }
+
+    /// Pick the sparsest active groups whose chunks fit into the free slots of the remaining ones.
+    pub fn defrag_plan(&self) -> (Vec<GroupId>, DefragStats) {
+        let mut groups = self
+            .active_groups
+            .iter()
+            .map(|(&group_id, state)| (state.count(), group_id))
+            .collect::<Vec<_>>();
+        groups.sort();
+
+        let mut free_slots: u64 = self
+            .active_group_free_slots()
+            .map(|(_, free)| free as u64)
+            .sum();
+        let mut stats = DefragStats::default();
+        let mut group_ids = vec![];
+        for (used, group_id) in groups {
+            let free = GroupState::TOTAL_BITS as u64 - used as u64;
+            if stats.moved_chunks + used as u64 > free_slots - free {
+                break;
+            }
+            free_slots -= free;
+            stats.moved_chunks += used as u64;
+            stats.freed_groups += 1;
+            group_ids.push(group_id);
+        }
+        (group_ids, stats)
+    }
+
+    pub fn freeze_group(&mut self, group_id: GroupId) -> bool {
+        match self.active_groups.remove(&group_id) {
+            Some(state) => {
+                self.active_levels[state.level() as usize].remove(&group_id);
+                self.frozen_groups.insert(group_id, state);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn get_compact_task(&mut self, max_reserved: u64) -> Option<GroupId> {
         let reserved = self.counter.reserved_chunks();
         if reserved <= max_reserved {
@@ -222,13 +413,85 @@ impl ChunkAllocator {
         None
     }
 
-    pub fn finish_compact_task(&mut self, group_id: GroupId) {
+    pub fn finish_compact_task(&mut self, group_id: GroupId) -> bool {
         if let Some(state) = self.frozen_groups.remove(&group_id) {
             self.active_levels[state.level() as usize].insert(group_id);
             self.active_groups.insert(group_id, state);
             tracing::info!("finish compact task and move back {:?}", group_id);
+            false
         } else {
             tracing::info!("finish compact task successful!");
+            true
+        }
+    }
+}
+
+/// Rebuilds the groups of one chunk size from its group bits, which arrive in group order.
+struct AllocatorLoader {
+    full_groups: ShardsSet<GroupId>,
+    active_groups: ShardsMap<GroupId, GroupState>,
+    active_levels: [ShardsSet<GroupId>; GroupState::LEVELS],
+    allocated_groups: ShardsSet<GroupId>,
+    unallocated_groups: ShardsSet<GroupId>,
+    current: GroupId,
+    allocated_count: u64,
+    reserved_count: u64,
+}
+
+impl AllocatorLoader {
+    fn new(chunk_size: Size) -> Self {
+        Self {
+            full_groups: ShardsSet::with_capacity(4096),
+            active_groups: ShardsMap::with_capacity(4096),
+            active_levels: std::array::from_fn(|_| ShardsSet::with_capacity(4096)),
+            allocated_groups: ShardsSet::with_capacity(4096),
+            unallocated_groups: ShardsSet::with_capacity(4096),
+            current: GroupId::new(chunk_size, 0, 0),
+            allocated_count: 0,
+            reserved_count: 0,
+        }
+    }
+
+    fn add(&mut self, group_id: GroupId, group_state: GroupState) {
+        let current = self.current;
+        assert!(
+            current <= group_id,
+            "current {current:?} > next {group_id:?}"
+        );
+        while self.current < group_id {
+            self.unallocated_groups.insert(self.current);
+            self.current.next();
+        }
+        self.current.next();
+
+        self.allocated_count += GroupState::TOTAL_BITS as u64;
+        if group_state.is_empty() {
+            self.allocated_groups.insert(group_id);
+            self.reserved_count += GroupState::TOTAL_BITS as u64;
+        } else if group_state.is_full() {
+            self.full_groups.insert(group_id);
+        } else {
+            self.reserved_count += GroupState::TOTAL_BITS as u64 - group_state.count() as u64;
+            self.active_levels[group_state.level() as usize].insert(group_id);
+            self.active_groups.insert(group_id, group_state);
+        }
+    }
+
+    fn finish(self, counter: Arc<AllocatorCounter>) -> ChunkAllocator {
+        counter.init(self.allocated_count, self.reserved_count);
+        ChunkAllocator {
+            full_groups: self.full_groups,
+            active_groups: self.active_groups,
+            active_levels: self.active_levels,
+            frozen_groups: ShardsMap::with_capacity(4096),
+            counter: counter.clone(),
+            group_allocator: GroupAllocator {
+                allocated_groups: self.allocated_groups,
+                unallocated_groups: self.unallocated_groups,
+                next_group_id: self.current,
+                counter,
+            },
+            position_rc: ShardsMap::with_capacity(1 << 20),
         }
     }
 }
@@ -301,4 +564,235 @@ mod tests {
         let mut allocator = ChunkAllocator::with_chunk_size(CHUNK_SIZE_NORMAL);
         allocator.deallocate(Position::default());
     }
+
+    #[test]
+    fn test_chunk_allocator_fragmentation() {
+        let mut allocator = ChunkAllocator::with_chunk_size(CHUNK_SIZE_NORMAL);
+        assert_eq!(allocator.fragmentation(), 0.0);
+        assert_eq!(allocator.active_group_free_slots().count(), 0);
+
+        // a quarter used and three quarters used.
+        let quarter = GroupState::TOTAL_BITS / 4;
+        for (index, used) in [quarter, 3 * quarter].into_iter().enumerate() {
+            let mut state = GroupState::empty();
+            for _ in 0..used {
+                state.allocate().unwrap();
+            }
+            let group_id = GroupId::new(CHUNK_SIZE_NORMAL, 0, index as _);
+            allocator.active_groups.insert(group_id, state);
+        }
+        allocator
+            .full_groups
+            .insert(GroupId::new(CHUNK_SIZE_NORMAL, 0, 2));
+
+        let mut free_slots = allocator
+            .active_group_free_slots()
+            .map(|(_, free)| free)
+            .collect::<Vec<_>>();
+        free_slots.sort();
+        assert_eq!(free_slots, vec![quarter as u32, 3 * quarter as u32]);
+        assert_eq!(allocator.fragmentation(), 0.5);
+    }
+
+    #[test]
+    fn test_chunk_allocator_packing() {
+        let mut allocator = ChunkAllocator::with_chunk_size(CHUNK_SIZE_NORMAL);
+        assert_eq!(allocator.packing(), PackingStats::default());
+        assert_eq!(allocator.packing().efficiency(), 0.0);
+
+        // one full group, a quarter used and three quarters used.
+        let capacity = ChunkAllocator::group_capacity() as u64;
+        let quarter = GroupState::TOTAL_BITS / 4;
+        for (index, used) in [quarter, 3 * quarter].into_iter().enumerate() {
+            let mut state = GroupState::empty();
+            for _ in 0..used {
+                state.allocate().unwrap();
+            }
+            let group_id = GroupId::new(CHUNK_SIZE_NORMAL, 0, index as _);
+            allocator.active_groups.insert(group_id, state);
+        }
+        allocator
+            .full_groups
+            .insert(GroupId::new(CHUNK_SIZE_NORMAL, 0, 2));
+
+        let packing = allocator.packing();
+        assert_eq!(
+            packing,
+            PackingStats {
+                groups: 3,
+                capacity: 3 * capacity,
+                occupied: 2 * capacity,
+            }
+        );
+        assert!((packing.efficiency() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_chunk_allocator_groups() {
+        let mut allocator = ChunkAllocator::with_chunk_size(CHUNK_SIZE_NORMAL);
+        assert_eq!(allocator.groups().count(), 0);
+
+        let total = GroupState::TOTAL_BITS as u32;
+        for (index, used) in [(3, 10), (0, total - 1), (1, 1)] {
+            let mut state = GroupState::empty();
+            for _ in 0..used {
+                state.allocate().unwrap();
+            }
+            let group_id = GroupId::new(CHUNK_SIZE_NORMAL, 0, index);
+            allocator.active_groups.insert(group_id, state);
+        }
+        allocator
+            .full_groups
+            .insert(GroupId::new(CHUNK_SIZE_NORMAL, 0, 2));
+
+        let groups = allocator.groups().collect::<Vec<_>>();
+        assert_eq!(
+            groups
+                .iter()
+                .map(|g| g.group_id.group())
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+        assert_eq!(
+            groups
+                .iter()
+                .map(|g| (g.used, g.free, g.full))
+                .collect::<Vec<_>>(),
+            vec![
+                (total - 1, 1, false),
+                (1, total - 1, false),
+                (total, 0, true),
+                (10, total - 10, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunk_allocator_defrag_plan() {
+        let mut allocator = ChunkAllocator::with_chunk_size(CHUNK_SIZE_NORMAL);
+        assert_eq!(allocator.defrag_plan(), (vec![], DefragStats::default()));
+
+        let quarter = GroupState::TOTAL_BITS / 4;
+        for (index, used) in [3 * quarter, 10, quarter, 20].into_iter().enumerate() {
+            let mut state = GroupState::empty();
+            for _ in 0..used {
+                state.allocate().unwrap();
+            }
+            let group_id = GroupId::new(CHUNK_SIZE_NORMAL, 0, index as _);
+            allocator.active_levels[state.level() as usize].insert(group_id);
+            allocator.active_groups.insert(group_id, state);
+        }
+
+        // 10 and 20 fit into the other groups, the quarter used group does not fit into the last one.
+        let (group_ids, stats) = allocator.defrag_plan();
+        assert_eq!(
+            group_ids,
+            vec![
+                GroupId::new(CHUNK_SIZE_NORMAL, 0, 1),
+                GroupId::new(CHUNK_SIZE_NORMAL, 0, 3)
+            ]
+        );
+        assert_eq!(
+            stats,
+            DefragStats {
+                moved_chunks: 30,
+                freed_groups: 2,
+            }
+        );
+
+        assert!(allocator.freeze_group(group_ids[0]));
+        assert!(!allocator.freeze_group(group_ids[0]));
+        assert_eq!(allocator.active_groups.len(), 3);
+        assert!(allocator
+            .active_levels
+            .iter()
+            .all(|s| !s.contains(&group_ids[0])));
+        assert!(!allocator.finish_compact_task(group_ids[0]));
+        assert_eq!(allocator.active_groups.len(), 4);
+    }
+
+    #[test]
+    fn test_chunk_allocator_state() {
+        let mut allocator = ChunkAllocator::with_chunk_size(CHUNK_SIZE_NORMAL);
+        let group = |index| GroupId::new(CHUNK_SIZE_NORMAL, 0, index);
+        let mut state = GroupState::empty();
+        state.allocate().unwrap();
+        allocator.active_groups.insert(group(3), state);
+        allocator
+            .active_groups
+            .insert(group(1), GroupState::empty());
+        allocator.full_groups.insert(group(2));
+        allocator.full_groups.insert(group(0));
+        assert!(allocator.freeze_group(group(1)));
+        allocator.group_allocator.allocated_groups.insert(group(4));
+        allocator.group_allocator.next_group_id = group(5);
+
+        let state_dump = allocator.state();
+        assert_eq!(state_dump.chunk_size, CHUNK_SIZE_NORMAL);
+        assert_eq!(state_dump.full_groups, vec![group(0), group(2)]);
+        assert_eq!(state_dump.active_groups, vec![(group(3), state)]);
+        assert_eq!(
+            state_dump.frozen_groups,
+            vec![(group(1), GroupState::empty())]
+        );
+        assert_eq!(state_dump.empty_groups, vec![group(4)]);
+        assert!(state_dump.unallocated_groups.is_empty());
+        assert_eq!(state_dump.next_group_id, group(5));
+    }
+
+    #[test]
+    fn test_chunk_allocator_load_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().into(),
+                create: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        let sizes = Size::all_buckets().collect::<Vec<_>>();
+        let (a, b) = (sizes[0], sizes[2]);
+
+        // a: an active group, a gap and an empty group. b: one full group.
+        let mut chunk_id = 0u64;
+        let mut add_chunks = |group_id: GroupId, count: usize| {
+            meta_store.allocate_group(group_id).unwrap();
+            for index in 0..count {
+                let chunk_meta = ChunkMeta {
+                    pos: Position::new(group_id, index as u8),
+                    ..Default::default()
+                };
+                meta_store
+                    .add_chunk(&chunk_id.to_be_bytes(), &chunk_meta, false)
+                    .unwrap();
+                chunk_id += 1;
+            }
+        };
+        add_chunks(GroupId::new(a, 0, 0), 2);
+        add_chunks(GroupId::new(a, 2, 0), 0);
+        add_chunks(GroupId::new(b, 0, 0), GroupState::TOTAL_BITS);
+
+        let counters = sizes
+            .iter()
+            .map(|&size| Arc::new(AllocatorCounter::new(size)))
+            .collect::<Vec<_>>();
+        let all = ChunkAllocator::load_all(meta_store.iterator(), counters.clone()).unwrap();
+        assert_eq!(all.len(), sizes.len());
+        for ((&size, allocator), counter) in sizes.iter().zip(&all).zip(&counters) {
+            let single_counter = Arc::new(AllocatorCounter::new(size));
+            let single =
+                ChunkAllocator::load(meta_store.iterator(), single_counter.clone(), size).unwrap();
+            assert_eq!(allocator.state(), single.state());
+            assert_eq!(counter.used_size(), single_counter.used_size());
+        }
+
+        let state_a = all[0].state();
+        assert_eq!(state_a.active_groups.len(), 1);
+        assert_eq!(state_a.empty_groups, vec![GroupId::new(a, 2, 0)]);
+        assert_eq!(state_a.unallocated_groups, vec![GroupId::new(a, 1, 0)]);
+        assert_eq!(all[2].state().full_groups, vec![GroupId::new(b, 0, 0)]);
+        assert_eq!(all[1].state().next_group_id, GroupId::new(sizes[1], 0, 0));
+    }
 }
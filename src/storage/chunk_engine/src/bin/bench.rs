@@ -54,12 +54,11 @@ fn main() -> Result<()> {
             running.fetch_add(1, Ordering::SeqCst);
 
             Ok(std::thread::spawn(move || {
-                let mut chunk_id: usize = i << 32;
-                for _ in 0..config.count {
+                let begin: usize = i << 32;
+                for chunk_id in begin..begin + config.count {
                     engine
                         .write(&chunk_id.to_be_bytes(), &vec, 0, checksum)
                         .unwrap();
-                    chunk_id += 1;
                     bytes.fetch_add(vec.len(), Ordering::SeqCst);
                 }
                 running.fetch_sub(1, Ordering::SeqCst);
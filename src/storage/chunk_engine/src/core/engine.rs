@@ -3,16 +3,76 @@ use lockmap::{EntryByRef, LockMap};
 use serde::Deserialize;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize)]
 pub struct EngineConfig {
     pub path: PathBuf,
     pub create: bool,
     pub prefix_len: usize,
+    #[serde(default)]
+    pub mode: DbOpenMode,
+    /// Directory of the metadata RocksDB, defaults to `path/meta`.
+    #[serde(default)]
+    pub meta_path: Option<PathBuf>,
+    /// First byte of the chunk meta keys, defaults to `MetaKey::CHUNK_META_KEY_PREFIX`.
+    #[serde(default)]
+    pub chunk_meta_key_prefix: Option<u8>,
+    /// RocksDB tuning of the meta store, the defaults suit a long-running engine.
+    #[serde(skip)]
+    pub rocksdb_tuning: RocksDBTuning,
+}
+
+impl EngineConfig {
+    pub fn meta_path(&self) -> PathBuf {
+        self.meta_path
+            .clone()
+            .unwrap_or_else(|| self.path.join("meta"))
+    }
+
+    /// The config of the meta store the engine opens, with the same prefix length, mode, meta path,
+    /// chunk meta key layout and RocksDB tuning.
+    pub fn meta_store_config(&self) -> MetaStoreConfig {
+        MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: self.meta_path(),
+                create: self.create,
+                mode: self.mode.clone(),
+                tuning: self.rocksdb_tuning.clone(),
+            },
+            prefix_len: self.prefix_len,
+            chunk_meta_key_prefix: self
+                .chunk_meta_key_prefix
+                .unwrap_or(MetaKey::CHUNK_META_KEY_PREFIX),
+        }
+    }
+}
+
+/// Open the store at `path`, whose metadata is in `path/meta`, and return its meta store with the
+/// engine on top of it. Both are configured from the same `EngineConfig`, see `open_store_with`.
+pub fn open_store(
+    path: &Path,
+    mode: DbOpenMode,
+    prefix_len: usize,
+) -> Result<(Arc<MetaStore>, Engine)> {
+    open_store_with(&EngineConfig {
+        path: path.into(),
+        prefix_len,
+        mode,
+        ..Default::default()
+    })
+}
+
+/// Open the meta store and the engine of one store from a single config, so they agree on the
+/// prefix length and on where the metadata lives. A read-only or secondary open fails with
+/// `Error::InvalidArg` if the store groups its used sizes by another prefix length, only a
+/// read-write open regroups them.
+pub fn open_store_with(config: &EngineConfig) -> Result<(Arc<MetaStore>, Engine)> {
+    let engine = Engine::open(config)?;
+    Ok((engine.meta_store.clone(), engine))
 }
 
 #[derive(Clone)]
@@ -29,31 +89,17 @@ pub struct Engine {
 
 impl Engine {
     pub fn open(config: &EngineConfig) -> Result<Self> {
-        let meta_config = MetaStoreConfig {
-            rocksdb: RocksDBConfig {
-                path: config.path.join("meta"),
-                create: config.create,
-                ..Default::default()
-            },
-            prefix_len: config.prefix_len,
-        };
+        let meta_config = config.meta_store_config();
 
+        // only a writer recovers uncommitted chunks and upgrades the meta version.
+        let writable = config.mode == DbOpenMode::ReadWrite;
         let mut meta_store = MetaStore::open(&meta_config)?;
-        let uncommitted_chunks = meta_store.occupy_uncommitted_positions()?;
-        let meta_store = Arc::new(meta_store);
-        let allocators = Allocators::new(&config.path, config.create, meta_store.clone())?;
-        let meta_cache = Arc::new(LockMap::with_capacity_and_shard_amount(1 << 20, 256));
-
-        let engine = Self {
-            meta_store: meta_store.clone(),
-            allocators,
-            meta_cache,
-            workers: Default::default(),
-            allow_to_allocate: Arc::new(AtomicBool::new(true)),
-            metrics: Default::default(),
-            prefix_len: config.prefix_len,
-            writing_list: Default::default(),
+        let uncommitted_chunks = if writable {
+            meta_store.occupy_uncommitted_positions()?
+        } else {
+            vec![]
         };
+        let engine = Self::with_meta_store(config, Arc::new(meta_store))?;
 
         if !uncommitted_chunks.is_empty() {
             // resume writing chunks in memory.
@@ -66,14 +112,50 @@ impl Engine {
                 let chunk = allocator.reference(meta.clone(), old_chunk.is_none());
                 writing_list.insert(chunk_id.clone(), WritingHolder { chunk, abort: true });
             }
-            meta_store.vacate_uncommitted_positions(uncommitted_chunks)?;
+            engine
+                .meta_store
+                .vacate_uncommitted_positions(uncommitted_chunks)?;
         }
 
-        engine.upgrade_version()?;
+        if writable {
+            engine.upgrade_version()?;
+        }
 
         Ok(engine)
     }
 
+    /// Open the engine on top of a meta store that is already open, so it can be shared with
+    /// other readers. Unlike `open`, it neither recovers uncommitted chunks nor upgrades the meta
+    /// version, so it is meant for read-only and secondary meta stores. The meta store must use the
+    /// prefix length of `config`.
+    pub fn with_meta_store(config: &EngineConfig, meta_store: Arc<MetaStore>) -> Result<Self> {
+        if meta_store.prefix_len() != config.prefix_len {
+            return Err(Error::InvalidArg(format!(
+                "meta store prefix length {} does not match engine prefix length {}",
+                meta_store.prefix_len(),
+                config.prefix_len
+            )));
+        }
+        // Checked up front so a data directory that was moved or never mounted is told apart from
+        // a failure to open one of its cluster files.
+        if !config.create && std::fs::read_dir(&config.path).is_err() {
+            return Err(Error::DataDirMissing(config.path.clone()));
+        }
+        let allocators = Allocators::new(&config.path, config.create, meta_store.clone())?;
+        let meta_cache = Arc::new(LockMap::with_capacity_and_shard_amount(1 << 20, 256));
+
+        Ok(Self {
+            meta_store,
+            allocators,
+            meta_cache,
+            workers: Default::default(),
+            allow_to_allocate: Arc::new(AtomicBool::new(true)),
+            metrics: Default::default(),
+            prefix_len: config.prefix_len,
+            writing_list: Default::default(),
+        })
+    }
+
     pub fn used_size(&self) -> UsedSize {
         self.allocators.used_size()
     }
@@ -137,6 +219,55 @@ impl Engine {
         finish
     }
 
+    /// Positions of the `size` bucket that are taken in the group bits but held by neither a chunk
+    /// meta nor a chunk in memory, such as allocations that crashed before their chunk was committed.
+    pub fn stale_positions(&self, size: Size) -> Result<Vec<Position>> {
+        let allocator = self.allocators.select_by_size(size)?;
+        let in_use = self
+            .meta_store
+            .chunk_positions(allocator.clusters.chunk_size)?;
+        let mut positions = allocator.taken_positions();
+        positions.retain(|pos| !in_use.contains(pos));
+        Ok(positions)
+    }
+
+    /// Release a position found by `stale_positions`, it fails if a chunk holds the position.
+    pub fn release_position(&self, pos: Position) -> Result<()> {
+        let allocator = self.allocators.select_by_pos(pos)?;
+        allocator.release_position(pos, &self.meta_store)
+    }
+
+    /// Move chunks out of the sparsest active groups of the `size` bucket so that those groups
+    /// can be freed. Each chunk's meta is switched only after its data has been copied.
+    pub fn defragment(&self, size: Size) -> Result<DefragStats> {
+        let allocator = self.allocators.select_by_size(size)?;
+        let (group_ids, _) = allocator.defrag_plan();
+
+        let mut stats = DefragStats::default();
+        for group_id in group_ids {
+            if !allocator.freeze_group(group_id) {
+                continue;
+            }
+
+            let mut it = self.meta_store.iterator();
+            let result = it.iterate(
+                MetaKey::group_to_chunks_key_prefix(group_id),
+                |_, chunk_id| {
+                    if self.move_chunk(chunk_id)?.is_some() {
+                        stats.moved_chunks += 1;
+                    }
+                    Ok(())
+                },
+            );
+            if allocator.finish_compact_task(group_id) {
+                stats.freed_groups += 1;
+            }
+            result?;
+        }
+
+        Ok(stats)
+    }
+
     pub fn start_allocate_workers(&self, num: usize) {
         let workers = (0..num)
             .map(|i| {
@@ -227,9 +358,55 @@ impl Engine {
         let new_chunk = old_chunk.copy_chunk()?;
 
         // 3. replace chunk.
+        self.replace_chunk(chunk_id, &old_chunk, new_chunk)
+    }
+
+    /// Relocate a committed chunk into the `new_size` bucket. Its data is copied to a slot of the
+    /// new bucket first, then a single write batch points the meta at it and releases the old
+    /// slot, so a crash leaves the chunk intact in either its old or its new slot.
+    pub fn rebucket(&self, chunk_id: &[u8], new_size: Size) -> Result<()> {
+        let allocator = self.allocators.select_by_size(new_size)?;
+        let old_chunk = match self.get(chunk_id)? {
+            Some(chunk) => chunk,
+            None => {
+                return Err(Error::InvalidArg(format!(
+                    "rebucket chunk {:?} not found",
+                    chunk_id
+                )))
+            }
+        };
+        let meta = old_chunk.meta();
+        if meta.uncommitted {
+            return Err(Error::InvalidArg(format!(
+                "rebucket chunk {:?} is uncommitted",
+                chunk_id
+            )));
+        }
+        if meta.pos.chunk_size() == new_size {
+            return Ok(());
+        }
+
+        let new_chunk = old_chunk.copy_chunk_to(allocator)?;
+        match self.replace_chunk(chunk_id, &old_chunk, new_chunk)? {
+            Some(_) => Ok(()),
+            None => Err(Error::InvalidArg(format!(
+                "rebucket chunk {:?} was updated while moving",
+                chunk_id
+            ))),
+        }
+    }
+
+    /// Switch `chunk_id` from `old_chunk` to `new_chunk`, unless another thread updated or removed
+    /// it in the meantime.
+    fn replace_chunk(
+        &self,
+        chunk_id: &[u8],
+        old_chunk: &ChunkArc,
+        new_chunk: Chunk,
+    ) -> Result<Option<ChunkArc>> {
         let mut entry = self.meta_cache.entry_by_ref(chunk_id);
         match entry.get() {
-            Some(chunk) if Arc::ptr_eq(chunk, &old_chunk) => {
+            Some(chunk) if Arc::ptr_eq(chunk, old_chunk) => {
                 self.meta_store
                     .move_chunk(chunk_id, old_chunk.meta(), new_chunk.meta(), true)?;
                 let new_chunk = Arc::new(new_chunk);
@@ -259,6 +436,24 @@ impl Engine {
         )
     }
 
+    /// Store `data` as a new chunk with the given versions, computing its checksum locally.
+    pub fn put(&self, chunk_id: &[u8], data: &[u8], chain_ver: u32, chunk_ver: u32) -> Result<()> {
+        self.update(
+            chunk_id,
+            &mut UpdateReq {
+                without_checksum: true,
+                is_syncing: true,
+                create_new: true,
+                update_ver: chunk_ver,
+                chain_ver,
+                length: data.len() as _,
+                data: data.as_ptr() as u64,
+                ..Default::default()
+            },
+        )?;
+        Ok(())
+    }
+
     pub fn remove(&self, chunk_id: &[u8]) -> Result<ChunkArc> {
         self.update(
             chunk_id,
@@ -297,7 +492,7 @@ impl Engine {
         let data = if req.length != 0 {
             let data =
                 unsafe { std::slice::from_raw_parts(req.data as *const _, req.length as usize) };
-            let checksum = crc32c::crc32c(data);
+            let checksum = ChunkChecksum::ENGINE.compute(data);
             if req.without_checksum {
                 req.checksum = checksum;
             } else if checksum != req.checksum {
@@ -709,6 +904,33 @@ impl Engine {
         Ok(chunk_ids.len() as _)
     }
 
+    /// Remove the listed chunks in a single write batch, so either all of them are removed or none is.
+    /// Chunks that do not exist are skipped, returns how many were removed.
+    pub fn remove_chunks(&self, chunk_ids: &[Bytes]) -> Result<u64> {
+        let mut chunk_ids = chunk_ids.to_vec();
+        chunk_ids.sort(); // acquire locks in sequence to avoid deadlocks.
+        chunk_ids.dedup();
+
+        let mut write_batch = RocksDB::new_write_batch();
+        let mut entries = Vec::with_capacity(chunk_ids.len());
+        for chunk_id in &chunk_ids {
+            let mut entry = self.meta_cache.entry_by_ref(chunk_id);
+            if let Some(chunk) = self.get_with_entry(chunk_id, &mut entry)? {
+                self.meta_store
+                    .remove_mut(chunk_id, chunk.meta(), &mut write_batch)?;
+                entries.push(entry);
+            }
+        }
+
+        self.meta_store.write(write_batch, true)?;
+
+        let removed = entries.len() as u64;
+        for mut entry in entries {
+            entry.remove();
+        }
+        Ok(removed)
+    }
+
     pub fn upgrade_version(&self) -> Result<()> {
         let version = self.meta_store.get_version()?;
         let mut new_version = version;
@@ -748,6 +970,7 @@ impl std::fmt::Debug for Engine {
 }
 
 #[cfg(test)]
+#[allow(clippy::let_unit_value)]
 mod tests {
     use super::*;
     use std::sync::atomic::{AtomicBool, Ordering};
@@ -1150,6 +1373,332 @@ mod tests {
         thread.join().unwrap();
     }
 
+    #[test]
+    fn test_engine_defragment() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = EngineConfig {
+            path: dir.path().into(),
+            create: true,
+            ..Default::default()
+        };
+        let engine = Engine::open(&config).unwrap();
+
+        // 1. fill two groups, keep 11 chunks in the first one and half of the second one.
+        let mut data = create_aligned_buf(CHUNK_SIZE_SMALL);
+        for i in 0..512u32 {
+            data.fill(i as u8);
+            let checksum = crc32c::crc32c(&data);
+            engine.write(&i.to_le_bytes(), &data, 0, checksum).unwrap();
+        }
+        let sparse_group = engine
+            .get(&0u32.to_le_bytes())
+            .unwrap()
+            .unwrap()
+            .meta()
+            .pos
+            .group_id();
+        let kept = |i: u32| if i < 256 { i % 25 == 0 } else { i % 2 == 1 };
+        for i in 0..512u32 {
+            if !kept(i) {
+                engine.remove(&i.to_le_bytes()).unwrap();
+            }
+        }
+
+        // 2. plan.
+        let allocator = engine.allocators.select_by_size(CHUNK_SIZE_SMALL).unwrap();
+        let (group_ids, plan) = allocator.defrag_plan();
+        assert_eq!(group_ids, vec![sparse_group]);
+        assert_eq!(
+            plan,
+            DefragStats {
+                moved_chunks: 11,
+                freed_groups: 1
+            }
+        );
+
+        // 3. defragment.
+        assert_eq!(engine.defragment(CHUNK_SIZE_SMALL).unwrap(), plan);
+        assert_eq!(allocator.defrag_plan().1, DefragStats::default());
+
+        let mut buf = create_aligned_buf(CHUNK_SIZE_SMALL);
+        for i in (0..512u32).filter(|&i| kept(i)) {
+            let chunk = engine.get(&i.to_le_bytes()).unwrap().unwrap();
+            assert_ne!(chunk.meta().pos.group_id(), sparse_group);
+            chunk.pread(&mut buf, 0).unwrap();
+            assert!(buf.iter().all(|&v| v == i as u8));
+            assert_eq!(chunk.meta().checksum, crc32c::crc32c(&buf));
+        }
+
+        // 4. the emptied group is released.
+        assert_eq!(engine.allocate_groups(0, 0, 16), 1);
+        assert_eq!(engine.used_size().allocated_size, CHUNK_SIZE_SMALL * 256);
+        assert_eq!(engine.used_size().reserved_size, CHUNK_SIZE_SMALL * 117);
+    }
+
+    #[test]
+    fn test_engine_release_position() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = EngineConfig {
+            path: dir.path().into(),
+            create: true,
+            ..Default::default()
+        };
+
+        // 1. leave the position of chunk 0 taken without its chunk meta, like a crash would.
+        let stale_pos = {
+            let engine = Engine::open(&config).unwrap();
+            for i in 0..4u32 {
+                engine.put(&i.to_le_bytes(), &[i as u8; 512], 1, 1).unwrap();
+            }
+            let pos = engine.get(&0u32.to_le_bytes()).unwrap().unwrap().meta().pos;
+            let mut write_batch = RocksDB::new_write_batch();
+            write_batch.delete(MetaKey::chunk_meta_key(&0u32.to_le_bytes()));
+            engine.meta_store.write(write_batch, true).unwrap();
+            pos
+        };
+
+        let engine = Engine::open(&config).unwrap();
+        assert_eq!(
+            engine.stale_positions(CHUNK_SIZE_SMALL).unwrap(),
+            vec![stale_pos]
+        );
+        let reserved_size = engine.used_size().reserved_size;
+
+        // 2. positions held by a chunk are refused, whether it is in memory or not.
+        let held_pos = engine.meta_store.get_chunk_meta(&1u32.to_le_bytes());
+        let held_pos = held_pos.unwrap().unwrap().pos;
+        assert!(engine.release_position(held_pos).is_err());
+        let chunk = engine.get(&2u32.to_le_bytes()).unwrap().unwrap();
+        assert!(engine.release_position(chunk.meta().pos).is_err());
+
+        // 3. release the stale position.
+        engine.release_position(stale_pos).unwrap();
+        assert!(engine.release_position(stale_pos).is_err());
+        assert!(engine.stale_positions(CHUNK_SIZE_SMALL).unwrap().is_empty());
+        assert_eq!(
+            engine.used_size().reserved_size,
+            reserved_size + CHUNK_SIZE_SMALL
+        );
+        drop(chunk);
+        drop(engine);
+
+        // 4. the release is persisted.
+        let engine = Engine::open(&config).unwrap();
+        assert!(engine.stale_positions(CHUNK_SIZE_SMALL).unwrap().is_empty());
+        assert_eq!(
+            engine.used_size().reserved_size,
+            reserved_size + CHUNK_SIZE_SMALL
+        );
+        assert!(engine.get(&3u32.to_le_bytes()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_engine_remove_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = EngineConfig {
+            path: dir.path().into(),
+            create: true,
+            ..Default::default()
+        };
+
+        let engine = Engine::open(&config).unwrap();
+        for i in 0..4u32 {
+            engine.put(&i.to_le_bytes(), &[i as u8; 512], 1, 1).unwrap();
+        }
+
+        // chunk 1 is listed twice and chunk 9 does not exist.
+        let chunk_ids: Vec<Bytes> = [1u32, 3, 9, 1]
+            .iter()
+            .map(|i| Bytes::from(&i.to_le_bytes()[..]))
+            .collect();
+        assert_eq!(engine.remove_chunks(&chunk_ids).unwrap(), 2);
+        assert!(engine.get(&1u32.to_le_bytes()).unwrap().is_none());
+        assert!(engine.get(&3u32.to_le_bytes()).unwrap().is_none());
+        assert!(engine
+            .meta_store
+            .get_chunk_meta(&3u32.to_le_bytes())
+            .unwrap()
+            .is_none());
+        assert_eq!(engine.remove_chunks(&chunk_ids).unwrap(), 0);
+        drop(engine);
+
+        let engine = Engine::open(&config).unwrap();
+        assert!(engine.get(&1u32.to_le_bytes()).unwrap().is_none());
+        assert!(engine.get(&0u32.to_le_bytes()).unwrap().is_some());
+        assert!(engine.get(&2u32.to_le_bytes()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_open_store() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let config = EngineConfig {
+                path: dir.path().into(),
+                create: true,
+                prefix_len: 4,
+                ..Default::default()
+            };
+            let (meta_store, engine) = open_store_with(&config).unwrap();
+            assert!(Arc::ptr_eq(&meta_store, &engine.meta_store));
+            engine.put(&7u32.to_be_bytes(), &[7u8; 512], 1, 1).unwrap();
+        }
+
+        let (meta_store, engine) = open_store(dir.path(), DbOpenMode::ReadOnly, 4).unwrap();
+        assert_eq!((meta_store.prefix_len(), engine.prefix_len), (4, 4));
+        let chunk_meta = meta_store.get_chunk_meta(&7u32.to_be_bytes()).unwrap();
+        assert_eq!(chunk_meta.unwrap().len, 512);
+        assert!(engine.get(&7u32.to_be_bytes()).unwrap().is_some());
+        drop(engine);
+
+        // a reader cannot regroup the used sizes by another prefix length.
+        assert!(matches!(
+            open_store(dir.path(), DbOpenMode::ReadOnly, 8),
+            Err(Error::InvalidArg(_))
+        ));
+
+        // an engine refuses a meta store opened with another prefix length.
+        let config = EngineConfig {
+            path: dir.path().into(),
+            prefix_len: 8,
+            mode: DbOpenMode::ReadOnly,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Engine::with_meta_store(&config, meta_store),
+            Err(Error::InvalidArg(_))
+        ));
+    }
+
+    #[test]
+    fn test_engine_rebucket() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = EngineConfig {
+            path: dir.path().into(),
+            create: true,
+            ..Default::default()
+        };
+        let large = CHUNK_SIZE_SMALL.next_bucket().unwrap();
+        let data: Vec<u8> = (0..CHUNK_SIZE_SMALL.0 + 1).map(|i| i as u8).collect();
+
+        // 1. a chunk truncated in place keeps its larger slot, chain version 0 lets the truncate through.
+        let engine = Engine::open(&config).unwrap();
+        engine.put(&0u32.to_le_bytes(), &data, 0, 1).unwrap();
+        engine.truncate(&0u32.to_le_bytes(), 4096).unwrap();
+        engine.put(&1u32.to_le_bytes(), &data, 1, 1).unwrap();
+        let old_meta = engine
+            .get(&0u32.to_le_bytes())
+            .unwrap()
+            .unwrap()
+            .meta()
+            .clone();
+        assert_eq!(old_meta.pos.chunk_size(), large);
+
+        // 2. it moves down a bucket with its data and versions.
+        engine
+            .rebucket(&0u32.to_le_bytes(), CHUNK_SIZE_SMALL)
+            .unwrap();
+        let chunk = engine.get(&0u32.to_le_bytes()).unwrap().unwrap();
+        assert_eq!(chunk.meta().pos.chunk_size(), CHUNK_SIZE_SMALL);
+        assert_eq!(
+            (
+                chunk.meta().len,
+                chunk.meta().checksum,
+                chunk.meta().chunk_ver
+            ),
+            (old_meta.len, old_meta.checksum, old_meta.chunk_ver)
+        );
+        let mut buf = vec![0u8; 4096];
+        chunk.pread(&mut buf, 0).unwrap();
+        assert_eq!(buf, data[..4096]);
+        drop(chunk);
+
+        // 3. chunks that do not fit the target bucket and missing chunks are refused.
+        assert!(matches!(
+            engine.rebucket(&1u32.to_le_bytes(), CHUNK_SIZE_SMALL),
+            Err(Error::InvalidArg(_))
+        ));
+        let chunk = engine.get(&1u32.to_le_bytes()).unwrap().unwrap();
+        assert_eq!(chunk.meta().pos.chunk_size(), large);
+        drop(chunk);
+        assert!(engine
+            .rebucket(&2u32.to_le_bytes(), CHUNK_SIZE_SMALL)
+            .is_err());
+        drop(engine);
+
+        // 4. the move is persisted and the old slot is released.
+        let engine = Engine::open(&config).unwrap();
+        let meta = engine.meta_store.get_chunk_meta(&0u32.to_le_bytes());
+        assert_eq!(meta.unwrap().unwrap().pos.chunk_size(), CHUNK_SIZE_SMALL);
+        // the old slot would be left taken without a chunk meta otherwise.
+        assert!(engine.stale_positions(large).unwrap().is_empty());
+        assert!(engine.stale_positions(CHUNK_SIZE_SMALL).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_engine_open_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = EngineConfig {
+            path: dir.path().into(),
+            create: true,
+            ..Default::default()
+        };
+
+        let chunk_id = "chunk01".as_bytes();
+        let bytes = "hello world!".as_bytes();
+        {
+            let engine = Engine::open(&config).unwrap();
+            engine.put(chunk_id, bytes, 1, 1).unwrap();
+        }
+
+        config.create = false;
+        config.mode = DbOpenMode::ReadOnly;
+        let engine = Engine::open(&config).unwrap();
+        let chunk = engine.get(chunk_id).unwrap().unwrap();
+        let mut buf = [0u8; 12];
+        chunk.pread(&mut buf, 0).unwrap();
+        assert_eq!(buf, bytes);
+        assert!(engine.remove(chunk_id).is_err());
+    }
+
+    #[test]
+    fn test_engine_meta_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta_dir = tempfile::tempdir().unwrap();
+        let config = EngineConfig {
+            path: dir.path().into(),
+            create: true,
+            meta_path: Some(meta_dir.path().join("chunk_meta")),
+            ..Default::default()
+        };
+        assert_eq!(config.meta_path(), meta_dir.path().join("chunk_meta"));
+
+        let chunk_id = "chunk01".as_bytes();
+        {
+            let engine = Engine::open(&config).unwrap();
+            engine
+                .put(chunk_id, "hello world!".as_bytes(), 1, 1)
+                .unwrap();
+        }
+        assert!(meta_dir.path().join("chunk_meta").join("CURRENT").exists());
+        assert!(!dir.path().join("meta").exists());
+
+        let engine = Engine::open(&config).unwrap();
+        assert!(engine.get(chunk_id).unwrap().is_some());
+        drop(engine);
+
+        // The metadata is intact but the data directory is gone
+        let missing = dir.path().join("missing");
+        let config = EngineConfig {
+            path: missing.clone(),
+            create: false,
+            ..config
+        };
+        assert_eq!(
+            Engine::open(&config).err(),
+            Some(Error::DataDirMissing(missing))
+        );
+    }
+
     #[test]
     fn test_engine_truncate() {
         let dir = tempfile::tempdir().unwrap();
@@ -1687,4 +2236,81 @@ mod tests {
         let _ = get_thread.join().unwrap();
         let _ = commit_thread.join().unwrap();
     }
+
+    #[test]
+    fn test_engine_put() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = EngineConfig {
+            path: dir.path().into(),
+            create: true,
+            ..Default::default()
+        };
+        let engine = Engine::open(&config).unwrap();
+
+        let chunk_id = "chunk01".as_bytes();
+        let data = vec![7u8; 100 << 10];
+        engine.put(chunk_id, &data, 3, 5).unwrap();
+        assert!(matches!(
+            engine.put(chunk_id, &data, 3, 6),
+            Err(Error::ChunkAlreadyExists)
+        ));
+
+        let chunk = engine.get(chunk_id).unwrap().unwrap();
+        assert_eq!(chunk.meta().pos.chunk_size(), Size::kibibyte(128));
+        assert_eq!(chunk.meta().len, data.len() as u32);
+        assert_eq!(chunk.meta().chain_ver, 3);
+        assert_eq!(chunk.meta().chunk_ver, 5);
+        assert!(!chunk.meta().uncommitted);
+
+        let mut buf = vec![0u8; data.len()];
+        chunk.pread(&mut buf, 0).unwrap();
+        assert_eq!(buf, data);
+        assert_eq!(chunk.meta().checksum, crc32c::crc32c(&buf));
+        // SAFETY: the chunk is not written while it is mapped.
+        let region = unsafe { chunk.mmap() }.unwrap();
+        assert_eq!(&region[..], &data[..]);
+        assert!(unsafe { chunk.mmap_range(0, chunk.capacity() + 1) }.is_err());
+
+        engine.speed_up_quit();
+    }
+
+    #[test]
+    fn test_engine_rocksdb_tuning() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let config = EngineConfig {
+                path: dir.path().into(),
+                create: true,
+                prefix_len: 4,
+                ..Default::default()
+            };
+            let engine = Engine::open(&config).unwrap();
+            engine.put(&7u32.to_be_bytes(), &[7u8; 512], 1, 1).unwrap();
+        }
+
+        // the tuning reaches the meta store the engine opens, the layout is not repeated.
+        let tuning = RocksDBTuning {
+            block_cache_size: Some(Size::zero()),
+            bloom_filter: false,
+            readahead_size: Size::mebibyte(16),
+        };
+        let config = EngineConfig {
+            path: dir.path().into(),
+            prefix_len: 4,
+            mode: DbOpenMode::ReadOnly,
+            rocksdb_tuning: tuning.clone(),
+            ..Default::default()
+        };
+        assert_eq!(config.meta_store_config().rocksdb.tuning, tuning);
+        let (meta_store, engine) = open_store_with(&config).unwrap();
+        assert_eq!(
+            meta_store
+                .get_chunk_meta(&7u32.to_be_bytes())
+                .unwrap()
+                .unwrap()
+                .len,
+            512
+        );
+        assert!(engine.get(&7u32.to_be_bytes()).unwrap().is_some());
+    }
 }
@@ -12,6 +12,7 @@ fn create(path: &str, create: bool, prefix_len: usize, error: Pin<&mut CxxString
         path: PathBuf::from(path),
         create,
         prefix_len,
+        ..Default::default()
     };
     match Engine::open(&config) {
         Ok(engine) => Box::into_raw(Box::new(engine)),
@@ -22,6 +23,7 @@ fn create(path: &str, create: bool, prefix_len: usize, error: Pin<&mut CxxString
     }
 }
 
+#[allow(clippy::boxed_local)]
 fn release(_engine: Box<Engine>) {}
 
 #[allow(dead_code)]
@@ -163,6 +165,8 @@ impl Engine {
                     Error::ChunkCommittedUpdate(_) => 4008, // ChunkCommittedUpdate
                     Error::ChunkMissingUpdate(_) => 4007,   // ChunkMissingUpdate
                     Error::NoSpace => 7021,                 // NoSpace
+                    Error::Timeout(_) => 4020,              // MetaStoreOpenFailed
+                    Error::DataDirMissing(_) => 4040,       // ChunkStoreInitFailed
                 };
                 std::ptr::null_mut()
             }
@@ -104,6 +104,16 @@ impl Cluster {
         Ok(())
     }
 
+    /// Map `len` bytes at `offset` of the chunk at `pos` read-only instead of reading them.
+    ///
+    /// # Safety
+    ///
+    /// See `MmapRegion::map`, the range must not be written while the region is alive.
+    pub(crate) unsafe fn mmap(&self, pos: Position, offset: u32, len: u32) -> Result<MmapRegion> {
+        let offset = pos.offset() + offset;
+        MmapRegion::map(&self.normal_fd, offset.into(), len as usize)
+    }
+
     fn handle_error(e: std::io::Error) -> Result<()> {
         if e.kind() == std::io::ErrorKind::Interrupted {
             Ok(())
@@ -148,6 +158,17 @@ mod tests {
             assert!(cluster.pread(pos, &mut buf, 0).is_ok());
             assert_eq!(&buf, &bytes[0..buf.len()]);
 
+            let pos_1 = Position::new(group_id, 1);
+            assert!(cluster.pwrite(pos_1, bytes, 0).is_ok());
+            // SAFETY: the slot is not written while it is mapped.
+            unsafe {
+                assert_eq!(
+                    &cluster.mmap(pos_1, 0, bytes.len() as u32).unwrap()[..],
+                    bytes
+                );
+                assert_eq!(&cluster.mmap(pos_1, 6, 5).unwrap()[..], &bytes[6..11]);
+            }
+
             cluster.fallocate(group_id, true).unwrap();
             let meta = cluster.normal_fd.metadata().unwrap();
             assert_eq!(meta.len(), group_id.size());
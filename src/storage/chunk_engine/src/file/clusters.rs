@@ -47,6 +47,7 @@ impl Clusters {
     }
 
     pub fn pread(&self, pos: Position, buf: &mut [u8], offset: u32) -> Result<()> {
+        IoStats::add_chunk_read(buf.len() as u64);
         self.files[pos.cluster() as usize].pread(pos, buf, offset)
     }
 
@@ -54,6 +55,14 @@ impl Clusters {
         self.files[pos.cluster() as usize].pwrite(pos, buf, offset)
     }
 
+    /// # Safety
+    ///
+    /// See `MmapRegion::map`, the range must not be written while the region is alive.
+    pub(crate) unsafe fn mmap(&self, pos: Position, offset: u32, len: u32) -> Result<MmapRegion> {
+        IoStats::add_chunk_read(len as u64);
+        self.files[pos.cluster() as usize].mmap(pos, offset, len)
+    }
+
     pub fn fd_and_offset(&self, pos: Position) -> FdAndOffset {
         FdAndOffset {
             fd: self.files[pos.cluster() as usize].direct_fd.as_raw_fd(),
@@ -0,0 +1,145 @@
+use std::{fs::File, ops::Deref, os::fd::AsRawFd};
+
+use super::super::*;
+
+/// A read-only memory mapping of a range of a file, its pages are read on first access.
+///
+/// The mapping is shared with the file, so the bytes it derefs to are only immutable as long as
+/// nothing writes to or truncates that range of the file, see `MmapRegion::map`.
+pub struct MmapRegion {
+    addr: *mut libc::c_void,
+    map_len: usize,
+    /// Offset of the range in the mapping, which starts at a page boundary.
+    start: usize,
+    len: usize,
+}
+
+// SAFETY: the region owns its mapping, which is read-only and only unmapped on drop. The pointer
+// is never written through, so moving the region to or reading it from other threads is like
+// sharing a `&[u8]`, given the contract of `map` that the mapped file range does not change.
+unsafe impl Send for MmapRegion {}
+unsafe impl Sync for MmapRegion {}
+
+impl MmapRegion {
+    /// Map `len` bytes of `file` starting at `offset`. The range must lie within the file.
+    ///
+    /// # Safety
+    ///
+    /// While the region is alive, no one may write to the mapped range of the file or truncate the
+    /// file below its end, through this process or any other. A write would change the bytes
+    /// behind the `&[u8]` the region derefs to, and touching pages past the end of the file raises
+    /// SIGBUS.
+    pub unsafe fn map(file: &File, offset: u64, len: usize) -> Result<Self> {
+        let file_len = file
+            .metadata()
+            .map_err(|e| Error::IoError(format!("stat {:?} failed: {:?}", file, e)))?
+            .len();
+        if offset
+            .checked_add(len as u64)
+            .is_none_or(|end| end > file_len)
+        {
+            return Err(Error::InvalidArg(format!(
+                "mmap {:?} offset {} len {} is beyond the end of the file at {}",
+                file, offset, len, file_len
+            )));
+        }
+        if len == 0 {
+            // mmap rejects empty mappings.
+            return Ok(Self {
+                addr: std::ptr::null_mut(),
+                map_len: 0,
+                start: 0,
+                len: 0,
+            });
+        }
+
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+        let start = (offset % page_size) as usize;
+        let map_len = start + len;
+        let addr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                map_len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                (offset - start as u64) as libc::off_t,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(Error::IoError(format!(
+                "mmap {:?} offset {} len {} failed: {:?}",
+                file,
+                offset,
+                len,
+                std::io::Error::last_os_error()
+            )));
+        }
+
+        Ok(Self {
+            addr,
+            map_len,
+            start,
+            len,
+        })
+    }
+}
+
+impl Deref for MmapRegion {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts((self.addr as *const u8).add(self.start), self.len) }
+    }
+}
+
+impl Drop for MmapRegion {
+    fn drop(&mut self) {
+        if self.map_len > 0 {
+            unsafe { libc::munmap(self.addr, self.map_len) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::FileExt;
+
+    #[test]
+    fn test_mmap_region() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(dir.path().join("data"))
+            .unwrap();
+        let data: Vec<u8> = (0..20000u32).map(|i| i as u8).collect();
+        file.write_all_at(&data, 0).unwrap();
+
+        // SAFETY: nothing writes to the file while it is mapped.
+        unsafe {
+            // Ranges starting at and off a page boundary.
+            for (offset, len) in [(0, 20000), (4096, 100), (1234, 5678), (19999, 1)] {
+                let region = MmapRegion::map(&file, offset as u64, len).unwrap();
+                assert_eq!(&region[..], &data[offset..offset + len]);
+            }
+            assert!(MmapRegion::map(&file, 0, 0).unwrap().is_empty());
+
+            // Ranges past the end of the file are rejected instead of faulting when read.
+            assert!(matches!(
+                MmapRegion::map(&file, 19999, 2),
+                Err(Error::InvalidArg(_))
+            ));
+            assert!(MmapRegion::map(&file, u64::MAX, 2).is_err());
+
+            let dev_null = File::open("/dev/null").unwrap();
+            assert!(MmapRegion::map(&dev_null, 0, 10).is_err());
+        }
+    }
+}
@@ -1,7 +1,9 @@
 mod cluster;
 mod clusters;
 mod fs_type;
+mod mmap;
 
 pub use cluster::*;
 pub use clusters::*;
 pub use fs_type::*;
+pub use mmap::*;
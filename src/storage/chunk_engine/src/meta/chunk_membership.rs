@@ -0,0 +1,236 @@
+use std::{
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::super::*;
+
+/// Stores with more chunks than this get a Bloom filter instead of a set of ID prefixes.
+const EXACT_LIMIT: u64 = 1 << 20;
+/// Bloom filter bits per chunk and probes per lookup, about 1% false positives.
+const BLOOM_BITS_PER_KEY: u64 = 10;
+const BLOOM_PROBES: u64 = 7;
+
+/// Lookup counters of a `ChunkMembership`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkMembershipStats {
+    pub lookups: u64,
+    /// Lookups of chunks known to be absent, which never reached RocksDB.
+    pub skipped: u64,
+}
+
+/// Which chunk IDs may exist in a `MetaStore`, built from a single scan of the chunk meta keys.
+///
+/// It has no false negatives, so a chunk it rules out is guaranteed to be absent as of the scan. Like
+/// `MetaCache` it is never updated and only meant for read-only users of a store.
+pub struct ChunkMembership {
+    filter: MembershipFilter,
+    lookups: AtomicU64,
+    skipped: AtomicU64,
+}
+
+enum MembershipFilter {
+    /// The first 8 bytes of every chunk ID, zero padded.
+    Prefixes(HashSet<u64>),
+    Bloom {
+        bits: Vec<u64>,
+        num_bits: u64,
+    },
+}
+
+impl ChunkMembership {
+    /// Scan the chunk meta keys of `meta_store` without reading their values.
+    pub fn build(meta_store: &MetaStore) -> Result<Self> {
+        // The estimate only picks the structure and sizes the filter, a wrong one costs precision but never correctness.
+        let estimate = meta_store
+            .property("rocksdb.estimate-num-keys")
+            .and_then(|keys| keys.parse::<u64>().ok())
+            .unwrap_or(0);
+        let mut filter = if estimate > EXACT_LIMIT {
+            let num_bits = (estimate * BLOOM_BITS_PER_KEY).next_multiple_of(64);
+            MembershipFilter::Bloom {
+                bits: vec![0; (num_bits / 64) as usize],
+                num_bits,
+            }
+        } else {
+            MembershipFilter::Prefixes(HashSet::new())
+        };
+
+        let mark = meta_store.chunk_meta_key_prefix();
+        let end_key = MetaKey::chunk_meta_key_with(mark, &[]);
+        let mut it = meta_store.iterator();
+        it.seek(&end_key)?;
+        if it.key() == Some(end_key.as_ref()) {
+            it.next();
+        }
+        while let Some(key) = it.key() {
+            if key[0] != mark {
+                break;
+            }
+            filter.insert(&MetaKey::parse_chunk_meta_key(key));
+            it.next();
+        }
+        it.status()?;
+
+        Ok(Self {
+            filter,
+            lookups: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+        })
+    }
+
+    /// Whether the chunk may exist, `false` only for chunks that certainly do not.
+    pub fn may_contain(&self, chunk_id: &[u8]) -> bool {
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+        let found = self.filter.contains(chunk_id);
+        if !found {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        found
+    }
+
+    /// Whether the IDs are kept in a Bloom filter rather than a set of prefixes.
+    pub fn is_bloom(&self) -> bool {
+        matches!(self.filter, MembershipFilter::Bloom { .. })
+    }
+
+    pub fn stats(&self) -> ChunkMembershipStats {
+        ChunkMembershipStats {
+            lookups: self.lookups.load(Ordering::Relaxed),
+            skipped: self.skipped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl MembershipFilter {
+    fn insert(&mut self, chunk_id: &[u8]) {
+        match self {
+            Self::Prefixes(prefixes) => {
+                prefixes.insert(id_prefix(chunk_id));
+            }
+            Self::Bloom { bits, num_bits } => {
+                for bit in bloom_bits(chunk_id, *num_bits) {
+                    bits[(bit / 64) as usize] |= 1 << (bit % 64);
+                }
+            }
+        }
+    }
+
+    fn contains(&self, chunk_id: &[u8]) -> bool {
+        match self {
+            Self::Prefixes(prefixes) => prefixes.contains(&id_prefix(chunk_id)),
+            Self::Bloom { bits, num_bits } => bloom_bits(chunk_id, *num_bits)
+                .all(|bit| bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0),
+        }
+    }
+}
+
+fn id_prefix(chunk_id: &[u8]) -> u64 {
+    let mut prefix = [0u8; 8];
+    let len = chunk_id.len().min(8);
+    prefix[..len].copy_from_slice(&chunk_id[..len]);
+    u64::from_be_bytes(prefix)
+}
+
+/// The bits a chunk ID sets, by double hashing one 64-bit hash.
+fn bloom_bits(chunk_id: &[u8], num_bits: u64) -> impl Iterator<Item = u64> {
+    let mut hasher = DefaultHasher::new();
+    chunk_id.hash(&mut hasher);
+    let hash = hasher.finish();
+    let (h1, h2) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+    (0..BLOOM_PROBES).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+}
+
+impl MetaStore {
+    /// `get_chunk_metas` that only looks up the chunks `membership` does not rule out.
+    pub fn get_chunk_metas_with(
+        &self,
+        chunk_ids: &[Vec<u8>],
+        membership: &ChunkMembership,
+    ) -> Result<Vec<Option<ChunkMeta>>> {
+        let candidates: Vec<usize> = (0..chunk_ids.len())
+            .filter(|&i| membership.may_contain(&chunk_ids[i]))
+            .collect();
+        let lookups: Vec<Vec<u8>> = candidates.iter().map(|&i| chunk_ids[i].clone()).collect();
+        let mut out = vec![None; chunk_ids.len()];
+        for (i, chunk_meta) in candidates.into_iter().zip(self.get_chunk_metas(&lookups)?) {
+            out[i] = chunk_meta;
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_membership() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().into(),
+                create: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        let group_id = GroupId::default();
+        for i in 0..4u64 {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(group_id, i as u8),
+                chunk_ver: i as u32,
+                ..Default::default()
+            };
+            meta_store
+                .add_chunk(&(i << 32).to_be_bytes(), &chunk_meta, false)
+                .unwrap();
+        }
+
+        let membership = ChunkMembership::build(&meta_store).unwrap();
+        assert!(!membership.is_bloom());
+        let chunk_ids: Vec<Vec<u8>> = [0u64, 1 << 32, 5 << 32, 3 << 32, 9 << 32]
+            .iter()
+            .map(|i| i.to_be_bytes().to_vec())
+            .collect();
+        let chunk_metas = meta_store
+            .get_chunk_metas_with(&chunk_ids, &membership)
+            .unwrap();
+        let versions: Vec<_> = chunk_metas
+            .iter()
+            .map(|chunk_meta| chunk_meta.as_ref().map(|chunk_meta| chunk_meta.chunk_ver))
+            .collect();
+        assert_eq!(versions, [Some(0), Some(1), None, Some(3), None]);
+        // Both absent IDs were answered without a lookup
+        assert_eq!(
+            membership.stats(),
+            ChunkMembershipStats {
+                lookups: 5,
+                skipped: 2
+            }
+        );
+
+        // An ID sharing the 8-byte prefix of a chunk cannot be ruled out, but is still absent
+        let longer = [(1u64 << 32).to_be_bytes().as_slice(), &[7]].concat();
+        assert!(membership.may_contain(&longer));
+        assert_eq!(meta_store.get_chunk_meta(&longer).unwrap(), None);
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let mut filter = MembershipFilter::Bloom {
+            bits: vec![0; 1000 * BLOOM_BITS_PER_KEY as usize / 64 + 1],
+            num_bits: (1000 * BLOOM_BITS_PER_KEY).next_multiple_of(64),
+        };
+        for i in 0..1000u32 {
+            filter.insert(&i.to_be_bytes());
+        }
+        assert!((0..1000u32).all(|i| filter.contains(&i.to_be_bytes())));
+        let false_positives = (1000..11000u32)
+            .filter(|i| filter.contains(&i.to_be_bytes()))
+            .count();
+        assert!(false_positives < 300, "{}", false_positives);
+    }
+}
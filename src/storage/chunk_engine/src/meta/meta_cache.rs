@@ -0,0 +1,190 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use super::super::*;
+
+/// Hit and miss counters of a `MetaCache`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MetaCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+/// A bounded LRU cache of chunk metas in front of a `MetaStore`.
+///
+/// Entries are never invalidated, so it is only meant for read-only users of a store that is not written
+/// underneath them. Chunks that do not exist are not cached.
+pub struct MetaCache {
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+#[derive(Default)]
+struct LruState {
+    /// Chunk ID to meta and the tick it was last used at.
+    entries: HashMap<Bytes, (ChunkMeta, u64)>,
+    /// Last used tick to chunk ID, the first entry is the least recently used.
+    order: BTreeMap<u64, Bytes>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl MetaCache {
+    /// A cache holding at most `capacity` metas, a capacity of 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(LruState::default()),
+        }
+    }
+
+    pub fn get_chunk_meta(
+        &self,
+        meta_store: &MetaStore,
+        chunk_id: &[u8],
+    ) -> Result<Option<ChunkMeta>> {
+        let chunk_id = Bytes::from(chunk_id);
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(chunk_meta) = state.touch(&chunk_id) {
+                state.hits += 1;
+                return Ok(Some(chunk_meta));
+            }
+            state.misses += 1;
+        }
+
+        let chunk_meta = meta_store.get_chunk_meta(&chunk_id)?;
+        if let Some(chunk_meta) = &chunk_meta {
+            if self.capacity > 0 {
+                let mut state = self.state.lock().unwrap();
+                state.insert(chunk_id, chunk_meta.clone(), self.capacity);
+            }
+        }
+        Ok(chunk_meta)
+    }
+
+    pub fn stats(&self) -> MetaCacheStats {
+        let state = self.state.lock().unwrap();
+        MetaCacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            entries: state.entries.len(),
+        }
+    }
+}
+
+impl LruState {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// The cached meta of `chunk_id`, marked as the most recently used.
+    fn touch(&mut self, chunk_id: &Bytes) -> Option<ChunkMeta> {
+        let tick = self.next_tick();
+        let (chunk_meta, last_used) = self.entries.get_mut(chunk_id)?;
+        let chunk_id = self.order.remove(last_used).unwrap();
+        *last_used = tick;
+        self.order.insert(tick, chunk_id);
+        Some(chunk_meta.clone())
+    }
+
+    fn insert(&mut self, chunk_id: Bytes, chunk_meta: ChunkMeta, capacity: usize) {
+        let tick = self.next_tick();
+        if let Some((_, last_used)) = self.entries.insert(chunk_id.clone(), (chunk_meta, tick)) {
+            self.order.remove(&last_used);
+        }
+        self.order.insert(tick, chunk_id);
+
+        while self.entries.len() > capacity {
+            let (_, evicted) = self.order.pop_first().unwrap();
+            self.entries.remove(&evicted);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_cache() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().into(),
+                create: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let meta_store = MetaStore::open(&config).unwrap();
+        let group_id = GroupId::default();
+        for i in 0..4u32 {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(group_id, i as u8),
+                chunk_ver: i,
+                ..Default::default()
+            };
+            meta_store
+                .add_chunk(&i.to_be_bytes(), &chunk_meta, false)
+                .unwrap();
+        }
+
+        let cache = MetaCache::new(2);
+        let get = |i: u32| {
+            cache
+                .get_chunk_meta(&meta_store, &i.to_be_bytes())
+                .unwrap()
+                .map(|chunk_meta| chunk_meta.chunk_ver)
+        };
+        assert_eq!(get(0), Some(0));
+        assert_eq!(get(1), Some(1));
+        assert_eq!(get(0), Some(0));
+        // 1 is now the least recently used and makes room for 2
+        assert_eq!(get(2), Some(2));
+        assert_eq!(
+            cache.stats(),
+            MetaCacheStats {
+                hits: 1,
+                misses: 3,
+                entries: 2
+            }
+        );
+        assert_eq!(get(0), Some(0));
+        assert_eq!(get(1), Some(1));
+        assert_eq!(
+            cache.stats(),
+            MetaCacheStats {
+                hits: 2,
+                misses: 4,
+                entries: 2
+            }
+        );
+
+        // Missing chunks are looked up every time and never cached
+        assert_eq!(get(9), None);
+        assert_eq!(get(9), None);
+        assert_eq!(cache.stats().misses, 6);
+
+        let disabled = MetaCache::new(0);
+        for _ in 0..2 {
+            let chunk_meta = disabled.get_chunk_meta(&meta_store, &3u32.to_be_bytes());
+            assert_eq!(chunk_meta.unwrap().unwrap().chunk_ver, 3);
+        }
+        assert_eq!(
+            disabled.stats(),
+            MetaCacheStats {
+                hits: 0,
+                misses: 2,
+                entries: 0
+            }
+        );
+    }
+}
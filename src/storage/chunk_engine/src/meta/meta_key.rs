@@ -26,7 +26,12 @@ impl MetaKey {
     }
 
     pub fn chunk_meta_key(chunk_id: &[u8]) -> Self {
-        let mut out = Self::chunk_meta_key_prefix();
+        Self::chunk_meta_key_with(Self::CHUNK_META_KEY_PREFIX, chunk_id)
+    }
+
+    /// The chunk meta key of a store whose chunk meta keys start with `mark`, see `MetaStoreConfig::chunk_meta_key_prefix`.
+    pub fn chunk_meta_key_with(mark: u8, chunk_id: &[u8]) -> Self {
+        let mut out = Self::prefix(mark);
         for num in chunk_id {
             out.0.push(!num)
         }
@@ -214,4 +219,20 @@ mod tests {
 
         MetaKey::parse_writing_chunk_key(MetaKey::writing_chunk_key_prefix().as_ref()).unwrap_err();
     }
+
+    #[test]
+    fn test_chunk_meta_key_round_trip() {
+        use super::super::super::*;
+
+        let chunk_ids: [&[u8]; 4] = [&[], &[0], &[0xff, 0x00, 0x7f], &[0xab; 16]];
+        for chunk_id in chunk_ids {
+            let key = MetaKey::chunk_meta_key(chunk_id);
+            assert_eq!(key.as_ref()[0], MetaKey::CHUNK_META_KEY_PREFIX);
+            assert_eq!(key.as_ref().len(), 1 + chunk_id.len());
+            assert_eq!(
+                MetaKey::parse_chunk_meta_key(key.as_ref()).as_ref(),
+                chunk_id
+            );
+        }
+    }
 }
@@ -1,13 +1,88 @@
-use std::{cell::RefCell, collections::HashMap, ops::DerefMut};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ops::DerefMut,
+};
 
 use super::super::*;
 use byteorder::{ByteOrder, LittleEndian};
 use derse::{Deserialize, DownwardBytes, Serialize};
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 pub struct MetaStoreConfig {
     pub rocksdb: RocksDBConfig,
     pub prefix_len: usize,
+    /// First byte of the chunk meta keys, `MetaKey::CHUNK_META_KEY_PREFIX` unless the store uses another key layout.
+    pub chunk_meta_key_prefix: u8,
+}
+
+impl Default for MetaStoreConfig {
+    fn default() -> Self {
+        Self {
+            rocksdb: RocksDBConfig::default(),
+            prefix_len: 0,
+            chunk_meta_key_prefix: MetaKey::CHUNK_META_KEY_PREFIX,
+        }
+    }
+}
+
+/// Totals of the chunks of one chunk size.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BucketStats {
+    pub count: u64,
+    pub total_len: u64,
+    pub total_allocated: u64,
+}
+
+/// Iterator over the chunk metas of a store in key order, see `MetaStore::chunks`.
+///
+/// It seeks on the first call to `next`, and stops after the first error.
+pub struct ChunkMetaIter<'a> {
+    it: RocksDBIterator<'a>,
+    mark: u8,
+    started: bool,
+    done: bool,
+}
+
+impl ChunkMetaIter<'_> {
+    fn advance(&mut self) -> Result<Option<(Bytes, ChunkMeta)>> {
+        if self.started {
+            self.it.next();
+        } else {
+            self.started = true;
+            let end_key = MetaKey::chunk_meta_key_with(self.mark, &[]);
+            self.it.seek(&end_key)?;
+            if self.it.key() == Some(end_key.as_ref()) {
+                self.it.next(); // [begin, end)
+            }
+        }
+
+        match self.it.key() {
+            Some(key) if key[0] == self.mark => {
+                let chunk_id = MetaKey::parse_chunk_meta_key(key);
+                let chunk_meta = ChunkMeta::deserialize(self.it.value().unwrap_or(&[]))
+                    .map_err(Error::SerializationError)?;
+                Ok(Some((chunk_id, chunk_meta)))
+            }
+            _ => {
+                self.it.status()?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Iterator for ChunkMetaIter<'_> {
+    type Item = Result<(Bytes, ChunkMeta)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = self.advance().transpose();
+        self.done = !matches!(item, Some(Ok(_)));
+        item
+    }
 }
 
 pub struct MetaStore {
@@ -28,13 +103,39 @@ impl MetaStore {
             config: config.clone(),
         };
 
-        this.update_used_size_if_need()?;
+        // only a writer can regroup the used sizes by another prefix length.
+        if config.rocksdb.mode == DbOpenMode::ReadWrite {
+            this.update_used_size_if_need()?;
+        } else {
+            let used_size_prefix_len = this.used_size_prefix_len()?;
+            if used_size_prefix_len != config.prefix_len {
+                return Err(Error::InvalidArg(format!(
+                    "the store groups used sizes by a prefix length of {}, not {}",
+                    used_size_prefix_len, config.prefix_len
+                )));
+            }
+        }
 
         Ok(this)
     }
 
+    /// Length of the chunk ID prefix the timestamp and used size keys are grouped by.
+    pub fn prefix_len(&self) -> usize {
+        self.config.prefix_len
+    }
+
+    /// First byte of the chunk meta keys, see `MetaStoreConfig::chunk_meta_key_prefix`.
+    pub fn chunk_meta_key_prefix(&self) -> u8 {
+        self.config.chunk_meta_key_prefix
+    }
+
+    /// The chunk meta key of `chunk_id` in this store's key layout.
+    fn chunk_meta_key(&self, chunk_id: &[u8]) -> MetaKey {
+        MetaKey::chunk_meta_key_with(self.config.chunk_meta_key_prefix, chunk_id)
+    }
+
     pub fn get_chunk_meta(&self, chunk_id: &[u8]) -> Result<Option<ChunkMeta>> {
-        let chunk_meta_key = MetaKey::chunk_meta_key(chunk_id);
+        let chunk_meta_key = self.chunk_meta_key(chunk_id);
         let value = self.rocksdb.get(chunk_meta_key)?;
 
         if let Some(value) = value {
@@ -46,6 +147,96 @@ impl MetaStore {
         }
     }
 
+    /// The serialized meta of a chunk as stored in RocksDB, without deserializing it.
+    pub fn get_chunk_meta_raw(&self, chunk_id: &[u8]) -> Result<Option<Bytes>> {
+        let chunk_meta_key = self.chunk_meta_key(chunk_id);
+        let value = self.rocksdb.get(chunk_meta_key)?;
+        Ok(value.map(|value| Bytes::from(value.as_ref())))
+    }
+
+    /// Fetch the metas of many chunks at once, `None` for chunks that do not exist.
+    pub fn get_chunk_metas(&self, chunk_ids: &[Vec<u8>]) -> Result<Vec<Option<ChunkMeta>>> {
+        let keys = chunk_ids
+            .iter()
+            .map(|chunk_id| self.chunk_meta_key(chunk_id));
+        self.rocksdb
+            .multi_get(keys)
+            .into_iter()
+            .map(|value| match value? {
+                Some(value) => Ok(Some(
+                    ChunkMeta::deserialize(value.as_ref()).map_err(Error::SerializationError)?,
+                )),
+                None => Ok(None),
+            })
+            .collect()
+    }
+
+    /// IDs of at most `limit` chunks whose ID starts with `prefix`, in key order.
+    pub fn chunks_with_prefix(&self, prefix: &[u8], limit: usize) -> Result<Vec<Bytes>> {
+        // chunk ids are stored inverted, the keys of one id prefix are still contiguous.
+        let key_prefix = self.chunk_meta_key(prefix);
+        let mut it = self.iterator();
+        it.seek(&key_prefix)?;
+
+        let mut out = vec![];
+        while out.len() < limit && it.valid() {
+            let key = it.key().unwrap();
+            if !key.starts_with(key_prefix.as_ref()) {
+                break;
+            }
+            out.push(MetaKey::parse_chunk_meta_key(key));
+            it.next();
+        }
+        it.status()?;
+        Ok(out)
+    }
+
+    /// Count the chunks of one chunk size in a single pass over the chunk metas.
+    pub fn bucket_stats(&self, chunk_size: Size) -> Result<BucketStats> {
+        let prefix = self.chunk_meta_key(&[]);
+        let mut stats = BucketStats::default();
+        self.iterator().iterate(&prefix, |key, value| {
+            if key == prefix.as_ref() {
+                return Ok(());
+            }
+            let chunk_meta = ChunkMeta::deserialize(value).map_err(Error::SerializationError)?;
+            if chunk_meta.pos.chunk_size() == chunk_size {
+                stats.count += 1;
+                stats.total_len += chunk_meta.len as u64;
+                stats.total_allocated += chunk_size.0;
+            }
+            Ok(())
+        })?;
+        Ok(stats)
+    }
+
+    /// All chunk IDs and metas, in key order.
+    pub fn chunks(&self) -> ChunkMetaIter<'_> {
+        ChunkMetaIter {
+            it: self.iterator(),
+            mark: self.config.chunk_meta_key_prefix,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Positions of one chunk size held by a chunk meta, in a single pass over the chunk metas.
+    pub fn chunk_positions(&self, chunk_size: Size) -> Result<HashSet<Position>> {
+        let prefix = self.chunk_meta_key(&[]);
+        let mut positions = HashSet::new();
+        self.iterator().iterate(&prefix, |key, value| {
+            if key == prefix.as_ref() {
+                return Ok(());
+            }
+            let chunk_meta = ChunkMeta::deserialize(value).map_err(Error::SerializationError)?;
+            if chunk_meta.pos.chunk_size() == chunk_size {
+                positions.insert(chunk_meta.pos);
+            }
+            Ok(())
+        })?;
+        Ok(positions)
+    }
+
     pub fn query_chunks(
         &self,
         begin: impl AsRef<[u8]>,
@@ -65,7 +256,7 @@ impl MetaStore {
     ) -> Result<Vec<(Bytes, ChunkMeta)>> {
         let mut out = Vec::<(Bytes, ChunkMeta)>::with_capacity(4096);
 
-        let end_key = MetaKey::chunk_meta_key(end.as_ref());
+        let end_key = self.chunk_meta_key(end.as_ref());
         it.seek(&end_key)?;
 
         if it.key() == Some(end_key.as_ref()) {
@@ -77,7 +268,7 @@ impl MetaStore {
                 break;
             }
 
-            if it.key().unwrap()[0] != MetaKey::CHUNK_META_KEY_PREFIX {
+            if it.key().unwrap()[0] != self.config.chunk_meta_key_prefix {
                 break;
             }
 
@@ -135,6 +326,42 @@ impl MetaStore {
         Ok(out)
     }
 
+    /// Chunks with a timestamp of at least `since` across every ID prefix, with their timestamps, in
+    /// prefix and then timestamp order. Each prefix's older keys are skipped with a seek instead of read.
+    pub fn query_chunks_since(&self, since: u64) -> Result<Vec<(u64, Bytes)>> {
+        let prefix_len = self.config.prefix_len;
+        let mut it = self.iterator();
+        let mut out = vec![];
+
+        it.seek(MetaKey::timestamp_key_prefix())?;
+        while let Some(key) = it.key() {
+            if key[0] != MetaKey::TIMESTAMP_KEY_PREFIX {
+                break;
+            }
+            let Some(prefix) = key.get(1..1 + prefix_len).map(<[u8]>::to_vec) else {
+                return Err(Error::MetaError(format!(
+                    "parse timestamp key fail: {:?}",
+                    key
+                )));
+            };
+
+            // Read the rest of this prefix, which leaves the iterator at the next one.
+            it.seek(MetaKey::timestamp_key_filter(&prefix, since))?;
+            while let Some(key) = it.key() {
+                if key[0] != MetaKey::TIMESTAMP_KEY_PREFIX
+                    || key.get(1..1 + prefix_len) != Some(&prefix[..])
+                {
+                    break;
+                }
+                out.push(MetaKey::parse_timestamp_key(key, prefix_len)?);
+                it.next();
+            }
+        }
+        it.status()?;
+
+        Ok(out)
+    }
+
     #[inline(always)]
     pub fn write(&self, write_batch: rocksdb::WriteBatch, sync: bool) -> Result<()> {
         self.rocksdb.write(write_batch, sync)
@@ -153,7 +380,7 @@ impl MetaStore {
         write_batch: &mut rocksdb::WriteBatch,
     ) -> Result<()> {
         // 1. add chunk meta.
-        let chunk_meta_key = MetaKey::chunk_meta_key(chunk_id);
+        let chunk_meta_key = self.chunk_meta_key(chunk_id);
         Self::with_tls_bytes(|bytes| {
             chunk_meta
                 .serialize_to(bytes)
@@ -210,7 +437,7 @@ impl MetaStore {
         write_batch: &mut rocksdb::WriteBatch,
     ) -> Result<()> {
         // 1. change chunk meta.
-        let chunk_meta_key = MetaKey::chunk_meta_key(chunk_id);
+        let chunk_meta_key = self.chunk_meta_key(chunk_id);
         Self::with_tls_bytes(|bytes| {
             new_meta
                 .serialize_to(bytes)
@@ -283,7 +510,7 @@ impl MetaStore {
         write_batch: &mut rocksdb::WriteBatch,
     ) -> Result<()> {
         // 1. delete chunk meta.
-        let chunk_meta_key = MetaKey::chunk_meta_key(chunk_id);
+        let chunk_meta_key = self.chunk_meta_key(chunk_id);
         write_batch.delete(chunk_meta_key);
 
         // 2. delete pos->chunk map.
@@ -318,6 +545,34 @@ impl MetaStore {
         Ok(())
     }
 
+    /// Clear the bit of a position that no chunk meta holds, such as one left taken by a crash.
+    pub fn release_position(&self, pos: Position) -> Result<()> {
+        let pos_to_chunk_key = MetaKey::pos_to_chunk_key(pos);
+        if let Some(chunk_id) = self.rocksdb.get(&pos_to_chunk_key)? {
+            if let Some(chunk_meta) = self.get_chunk_meta(&chunk_id)? {
+                if chunk_meta.pos == pos {
+                    return Err(Error::InvalidArg(format!(
+                        "position {:?} is held by chunk {:?}",
+                        pos,
+                        Bytes::from(chunk_id.as_ref())
+                    )));
+                }
+            }
+        }
+
+        let mut write_batch = RocksDB::new_write_batch();
+        write_batch.delete(pos_to_chunk_key);
+        let group_bits_key = MetaKey::group_bits_key(pos.group_id());
+        Self::with_tls_bytes(|bytes| {
+            MergeState::release(pos.index())
+                .serialize_to(bytes)
+                .map_err(Error::SerializationError)?;
+            write_batch.merge(group_bits_key, &bytes[..]);
+            Ok(())
+        })?;
+        self.write(write_batch, true)
+    }
+
     pub fn allocate_group(&self, group_id: GroupId) -> Result<()> {
         let group_bits_key = MetaKey::group_bits_key(group_id);
         self.rocksdb
@@ -329,10 +584,20 @@ impl MetaStore {
         self.rocksdb.delete(group_bits_key, true)
     }
 
-    pub fn iterator(&self) -> RocksDBIterator {
+    pub fn iterator(&self) -> RocksDBIterator<'_> {
         self.rocksdb.new_iterator()
     }
 
+    /// Apply the primary's latest updates, only meaningful when opened in secondary mode.
+    pub fn catch_up_with_primary(&self) -> Result<()> {
+        self.rocksdb.catch_up_with_primary()
+    }
+
+    /// Read a property of the underlying RocksDB, `None` if it is unknown.
+    pub fn property(&self, name: &str) -> Option<String> {
+        self.rocksdb.property(name)
+    }
+
     fn update_used_size(
         &self,
         chunk_id: &[u8],
@@ -523,8 +788,9 @@ impl MetaStore {
         })
     }
 
-    fn update_used_size_if_need(&mut self) -> Result<()> {
-        let old_len = match self.rocksdb.get(MetaKey::used_size_prefix_len_key())? {
+    /// The prefix length the used sizes in the store are grouped by.
+    fn used_size_prefix_len(&self) -> Result<usize> {
+        match self.rocksdb.get(MetaKey::used_size_prefix_len_key())? {
             Some(value) => {
                 if value.len() != std::mem::size_of::<u32>() {
                     return Err(Error::InvalidArg(format!(
@@ -532,11 +798,14 @@ impl MetaStore {
                         value.as_ref()
                     )));
                 }
-                LittleEndian::read_u32(value.as_ref()) as usize
+                Ok(LittleEndian::read_u32(value.as_ref()) as usize)
             }
-            None => 0,
-        };
+            None => Ok(0),
+        }
+    }
 
+    fn update_used_size_if_need(&mut self) -> Result<()> {
+        let old_len = self.used_size_prefix_len()?;
         let prefix_len = self.config.prefix_len;
         if old_len == prefix_len {
             return Ok(());
@@ -547,7 +816,7 @@ impl MetaStore {
             map.insert(Bytes::new(), 0);
         }
         let mut it = self.iterator();
-        it.iterate(MetaKey::chunk_meta_key_prefix(), |key, value| {
+        it.iterate(self.chunk_meta_key(&[]), |key, value| {
             let mut chunk_id = MetaKey::parse_chunk_meta_key(key);
             chunk_id.resize(prefix_len, 0);
             let chunk_meta = ChunkMeta::deserialize(value).map_err(Error::SerializationError)?;
@@ -593,6 +862,7 @@ impl MetaStore {
         write_batch: &mut rocksdb::WriteBatch,
     ) -> Result<()> {
         if prefix == MetaKey::CHUNK_META_KEY_PREFIX
+            || prefix == self.config.chunk_meta_key_prefix
             || prefix == MetaKey::GROUP_BITS_KEY_PREFIX
             || prefix == MetaKey::POS_TO_CHUNK_KEY_PREFIX
             || prefix == MetaKey::USED_SIZE_KEY_PREFIX
@@ -654,7 +924,7 @@ mod tests {
 
         meta_store
             .rocksdb
-            .put(MetaKey::version_key(), &[], false)
+            .put(MetaKey::version_key(), [], false)
             .unwrap();
         meta_store.get_version().unwrap_err();
     }
@@ -730,6 +1000,313 @@ mod tests {
         assert_eq!(count, 1);
     }
 
+    #[test]
+    fn test_meta_store_get_chunk_metas() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().into(),
+                create: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let meta_store = MetaStore::open(&config).unwrap();
+        assert!(meta_store.get_chunk_metas(&[]).unwrap().is_empty());
+
+        let group_id = GroupId::default();
+        for i in [1u32, 3, 4] {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(group_id, i as u8),
+                chunk_ver: i,
+                ..Default::default()
+            };
+            meta_store
+                .add_chunk(&i.to_be_bytes(), &chunk_meta, false)
+                .unwrap();
+        }
+
+        let chunk_ids = [4u32, 2, 1, 5, 3]
+            .iter()
+            .map(|i| i.to_be_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let chunk_metas = meta_store.get_chunk_metas(&chunk_ids).unwrap();
+        assert_eq!(
+            chunk_metas
+                .iter()
+                .map(|m| m.as_ref().map(|m| m.chunk_ver))
+                .collect::<Vec<_>>(),
+            vec![Some(4), None, Some(1), None, Some(3)]
+        );
+
+        // a value that is not a chunk meta fails the whole batch.
+        meta_store
+            .rocksdb
+            .put(MetaKey::chunk_meta_key(&2u32.to_be_bytes()), [1], false)
+            .unwrap();
+        meta_store.get_chunk_metas(&chunk_ids).unwrap_err();
+    }
+
+    #[test]
+    fn test_meta_store_get_chunk_meta_raw() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().into(),
+                create: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let meta_store = MetaStore::open(&config).unwrap();
+        let chunk_id = 7u32.to_be_bytes();
+        assert!(meta_store.get_chunk_meta_raw(&chunk_id).unwrap().is_none());
+
+        let mut chunk_meta = ChunkMeta {
+            pos: Position::new(GroupId::default(), 3),
+            chain_ver: 2,
+            chunk_ver: 5,
+            len: 4096,
+            checksum: 0xdeadbeef,
+            timestamp: 1,
+            ..Default::default()
+        };
+        chunk_meta.set_default_etag_if_need();
+        meta_store.add_chunk(&chunk_id, &chunk_meta, false).unwrap();
+
+        let raw = meta_store.get_chunk_meta_raw(&chunk_id).unwrap().unwrap();
+        let decoded = ChunkMeta::deserialize(raw.as_ref()).unwrap();
+        assert_eq!(decoded, chunk_meta);
+        assert_eq!(Some(decoded), meta_store.get_chunk_meta(&chunk_id).unwrap());
+    }
+
+    #[test]
+    fn test_meta_store_chunks_with_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().into(),
+                create: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let meta_store = MetaStore::open(&config).unwrap();
+        let group_id = GroupId::default();
+        for (i, chunk_id) in [[1u8, 2, 3], [1, 2, 4], [1, 3, 0], [2, 0, 0]]
+            .iter()
+            .enumerate()
+        {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(group_id, i as u8),
+                ..Default::default()
+            };
+            meta_store.add_chunk(chunk_id, &chunk_meta, false).unwrap();
+        }
+
+        let ids = |prefix: &[u8], limit| {
+            let mut ids = meta_store
+                .chunks_with_prefix(prefix, limit)
+                .unwrap()
+                .into_iter()
+                .map(|id| id.to_vec())
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(&[1, 2], 10), vec![vec![1, 2, 3], vec![1, 2, 4]]);
+        assert_eq!(ids(&[1], 10).len(), 3);
+        assert_eq!(ids(&[1], 2).len(), 2);
+        assert_eq!(ids(&[1, 3, 0], 10), vec![vec![1, 3, 0]]);
+        assert!(ids(&[3], 10).is_empty());
+        assert_eq!(ids(&[], 10).len(), 4);
+    }
+
+    #[test]
+    fn test_meta_store_bucket_stats() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().into(),
+                create: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let meta_store = MetaStore::open(&config).unwrap();
+        assert_eq!(
+            meta_store.bucket_stats(CHUNK_SIZE_NORMAL).unwrap(),
+            BucketStats::default()
+        );
+
+        for i in 0..10u32 {
+            let chunk_size = if i % 2 == 0 {
+                CHUNK_SIZE_NORMAL
+            } else {
+                CHUNK_SIZE_SMALL
+            };
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(GroupId::new(chunk_size, 0, 0), i as u8),
+                len: i * 100,
+                ..Default::default()
+            };
+            meta_store
+                .add_chunk(&i.to_be_bytes(), &chunk_meta, false)
+                .unwrap();
+        }
+
+        assert_eq!(
+            meta_store.bucket_stats(CHUNK_SIZE_NORMAL).unwrap(),
+            BucketStats {
+                count: 5,
+                total_len: 2000,
+                total_allocated: 5 * CHUNK_SIZE_NORMAL.0,
+            }
+        );
+        assert_eq!(
+            meta_store.bucket_stats(CHUNK_SIZE_SMALL).unwrap(),
+            BucketStats {
+                count: 5,
+                total_len: 2500,
+                total_allocated: 5 * CHUNK_SIZE_SMALL.0,
+            }
+        );
+        assert_eq!(
+            meta_store.bucket_stats(CHUNK_SIZE_LARGE).unwrap(),
+            BucketStats::default()
+        );
+    }
+
+    #[test]
+    fn test_meta_store_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().into(),
+                create: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let meta_store = MetaStore::open(&config).unwrap();
+        assert!(meta_store.chunks().next().is_none());
+
+        for i in 0..10u32 {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(GroupId::new(CHUNK_SIZE_NORMAL, 0, 0), i as u8),
+                len: i * 100,
+                ..Default::default()
+            };
+            meta_store
+                .add_chunk(&i.to_be_bytes(), &chunk_meta, false)
+                .unwrap();
+        }
+        // The keys add_chunk writes after the chunk metas are not yielded.
+        let chunks = meta_store.chunks().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(chunks.len(), 10);
+        for (chunk_id, chunk_meta) in &chunks {
+            let i = u32::from_be_bytes(chunk_id.as_ref().try_into().unwrap());
+            assert_eq!(chunk_meta.len, i * 100);
+        }
+
+        // Keys store inverted IDs, so the largest ID comes first.
+        let large: Vec<u32> = meta_store
+            .chunks()
+            .map(Result::unwrap)
+            .filter(|(_, chunk_meta)| chunk_meta.len >= 500)
+            .map(|(_, chunk_meta)| chunk_meta.len / 100)
+            .take(3)
+            .collect();
+        assert_eq!(large, [9, 8, 7]);
+        assert_eq!(meta_store.chunks().skip(8).count(), 2);
+        let total_len: u32 = meta_store.chunks().map(|chunk| chunk.unwrap().1.len).sum();
+        assert_eq!(total_len, 4500);
+    }
+
+    #[test]
+    fn test_meta_store_chunk_meta_key_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().into(),
+                create: true,
+                ..Default::default()
+            },
+            chunk_meta_key_prefix: 10,
+            ..Default::default()
+        };
+
+        let pos = Position::new(GroupId::new(CHUNK_SIZE_NORMAL, 0, 0), 0);
+        {
+            let meta_store = MetaStore::open(&config).unwrap();
+            for i in 0..4u32 {
+                let chunk_meta = ChunkMeta {
+                    pos: Position::new(pos.group_id(), i as u8),
+                    len: 100,
+                    ..Default::default()
+                };
+                meta_store
+                    .add_chunk(&i.to_be_bytes(), &chunk_meta, false)
+                    .unwrap();
+            }
+
+            // Lookups, scans and the membership index all read the keys under the configured prefix.
+            let chunk_id = 2u32.to_be_bytes();
+            assert_eq!(
+                meta_store.get_chunk_meta(&chunk_id).unwrap().unwrap().len,
+                100
+            );
+            assert!(meta_store.get_chunk_meta_raw(&chunk_id).unwrap().is_some());
+            let chunk_ids = vec![chunk_id.to_vec(), 9u32.to_be_bytes().to_vec()];
+            let metas = meta_store.get_chunk_metas(&chunk_ids).unwrap();
+            assert_eq!(
+                metas.iter().map(Option::is_some).collect::<Vec<_>>(),
+                [true, false]
+            );
+            assert_eq!(meta_store.chunks().count(), 4);
+            assert_eq!(meta_store.chunks_with_prefix(&[0, 0], 10).unwrap().len(), 4);
+            assert_eq!(meta_store.bucket_stats(CHUNK_SIZE_NORMAL).unwrap().count, 4);
+            assert_eq!(
+                meta_store.chunk_positions(CHUNK_SIZE_NORMAL).unwrap().len(),
+                4
+            );
+            assert_eq!(meta_store.query_chunks([], [], u64::MAX).unwrap().len(), 4);
+            let membership = ChunkMembership::build(&meta_store).unwrap();
+            assert!(membership.may_contain(&chunk_id));
+
+            let mut it = meta_store.iterator();
+            it.seek(MetaKey::chunk_meta_key_with(10, &chunk_id))
+                .unwrap();
+            assert_eq!(
+                it.key(),
+                Some(MetaKey::chunk_meta_key_with(10, &chunk_id).as_ref())
+            );
+
+            let mut write_batch = RocksDB::new_write_batch();
+            assert!(meta_store.remove_range_mut(10, &mut write_batch).is_err());
+        }
+
+        // The same store read with the default layout has no chunk metas.
+        config.chunk_meta_key_prefix = MetaKey::CHUNK_META_KEY_PREFIX;
+        let meta_store = MetaStore::open(&config).unwrap();
+        assert_eq!(meta_store.chunks().count(), 0);
+        assert!(meta_store
+            .get_chunk_meta(&2u32.to_be_bytes())
+            .unwrap()
+            .is_none());
+    }
+
     #[test]
     fn test_meta_store_open_failed() {
         let config = MetaStoreConfig {
@@ -755,6 +1332,7 @@ mod tests {
                 ..Default::default()
             },
             prefix_len: 4,
+            ..Default::default()
         };
 
         let meta_store = MetaStore::open(&config).unwrap();
@@ -807,6 +1385,7 @@ mod tests {
                 ..Default::default()
             },
             prefix_len: 0,
+            ..Default::default()
         };
 
         const N: u64 = 1024;
@@ -845,6 +1424,11 @@ mod tests {
             .query_chunks_by_timestamp(&[], start, end + 1, u64::MAX)
             .unwrap();
         assert_eq!(vec.len(), N as usize);
+        assert_eq!(
+            meta_store.query_chunks_since(start).unwrap().len(),
+            N as usize
+        );
+        assert!(meta_store.query_chunks_since(end + 1).unwrap().is_empty());
 
         drop(meta_store);
 
@@ -875,4 +1459,44 @@ mod tests {
         let size = meta_store.query_used_size(&[]).unwrap();
         assert_eq!(size, 0);
     }
+
+    #[test]
+    fn test_query_chunks_since() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().into(),
+                create: true,
+                ..Default::default()
+            },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        assert!(meta_store.query_chunks_since(0).unwrap().is_empty());
+
+        // Three prefixes, each with an old and a new chunk.
+        for prefix in 0..3u32 {
+            for (index, timestamp) in [100u64, 200].into_iter().enumerate() {
+                let chunk_id = [prefix.to_be_bytes(), (index as u32).to_be_bytes()].concat();
+                let chunk_meta = ChunkMeta {
+                    pos: Position::new(GroupId::default(), (2 * prefix as usize + index) as u8),
+                    timestamp,
+                    ..Default::default()
+                };
+                meta_store.add_chunk(&chunk_id, &chunk_meta, false).unwrap();
+            }
+        }
+
+        let chunks = meta_store.query_chunks_since(150).unwrap();
+        let expected = (0..3u32)
+            .map(|prefix| {
+                let chunk_id = [prefix.to_be_bytes(), 1u32.to_be_bytes()].concat();
+                (200, Bytes::from(&chunk_id[..]))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(chunks, expected);
+        assert_eq!(meta_store.query_chunks_since(100).unwrap().len(), 6);
+        assert!(meta_store.query_chunks_since(201).unwrap().is_empty());
+    }
 }
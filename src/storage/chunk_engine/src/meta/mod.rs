@@ -1,8 +1,12 @@
+mod chunk_membership;
+mod meta_cache;
 mod meta_key;
 mod meta_merge;
 mod meta_store;
 mod rocksdb;
 
+pub use chunk_membership::*;
+pub use meta_cache::*;
 pub use meta_key::*;
 pub use meta_merge::*;
 pub use meta_store::*;
@@ -1,16 +1,51 @@
-use crate::{Error, Result, Size};
+use crate::{Error, IoStats, Result, Size};
+use serde::Deserialize;
 use std::path::PathBuf;
 
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+pub enum DbOpenMode {
+    #[default]
+    ReadWrite,
+    ReadOnly,
+    /// Follow a live primary without blocking it. `wal_dir` holds the secondary's own files and
+    /// must not be the primary's path.
+    Secondary {
+        wal_dir: PathBuf,
+    },
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct RocksDBConfig {
     pub path: PathBuf,
     pub create: bool,
-    pub read_only: bool,
+    pub mode: DbOpenMode,
+    pub tuning: RocksDBTuning,
+}
+
+/// Options that change how fast RocksDB reads and how much memory it takes, but not what it reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RocksDBTuning {
+    /// Capacity of the LRU block cache, `None` keeps RocksDB's default and zero disables it.
+    pub block_cache_size: Option<Size>,
+    pub bloom_filter: bool,
+    /// Read-ahead size of iterators.
+    pub readahead_size: Size,
+}
+
+impl Default for RocksDBTuning {
+    fn default() -> Self {
+        Self {
+            block_cache_size: None,
+            bloom_filter: true,
+            readahead_size: Size::mebibyte(4),
+        }
+    }
 }
 
 pub struct RocksDB {
     db: rocksdb::DB,
     write_options: [rocksdb::WriteOptions; 2], // 0 for non-sync, 1 for sync.
+    readahead_size: Size,
 }
 
 pub trait MergeOp {
@@ -34,13 +69,29 @@ impl RocksDB {
         );
 
         let mut table_options = rocksdb::BlockBasedOptions::default();
-        table_options.set_bloom_filter(10.0, true);
+        let tuning = &config.tuning;
+        if tuning.bloom_filter {
+            table_options.set_bloom_filter(10.0, true);
+        }
+        match tuning.block_cache_size {
+            Some(Size(0)) => table_options.disable_cache(),
+            Some(size) => {
+                table_options.set_block_cache(&rocksdb::Cache::new_lru_cache(size.0 as usize))
+            }
+            None => {}
+        }
         db_options.set_block_based_table_factory(&table_options);
 
-        let db = if config.read_only {
-            rocksdb::DB::open_for_read_only(&db_options, &config.path, false)
-        } else {
-            rocksdb::DB::open(&db_options, &config.path)
+        let db = match &config.mode {
+            DbOpenMode::ReadWrite => rocksdb::DB::open(&db_options, &config.path),
+            DbOpenMode::ReadOnly => {
+                rocksdb::DB::open_for_read_only(&db_options, &config.path, false)
+            }
+            DbOpenMode::Secondary { wal_dir } => {
+                // secondary instances require keeping all table files open.
+                db_options.set_max_open_files(-1);
+                rocksdb::DB::open_as_secondary(&db_options, &config.path, wal_dir)
+            }
         }
         .map_err(|err| Error::RocksDBError(format!("open rocksdb fail: {:?}", err)))?;
 
@@ -50,16 +101,31 @@ impl RocksDB {
         Ok(Self {
             db,
             write_options: [rocksdb::WriteOptions::new(), sync_write_options],
+            readahead_size: tuning.readahead_size,
         })
     }
 
     pub fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<rocksdb::DBPinnableSlice>> {
+        IoStats::add_key_lookups(1);
         match self.db.get_pinned(key) {
             Ok(v) => Ok(v),
             Err(e) => Err(Error::RocksDBError(format!("RocksDB fail: {e:?}"))),
         }
     }
 
+    /// Look up many keys in one call, the results are in the order of `keys`.
+    pub fn multi_get<K: AsRef<[u8]>>(
+        &self,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Vec<Result<Option<Vec<u8>>>> {
+        let results = self.db.multi_get(keys);
+        IoStats::add_key_lookups(results.len() as u64);
+        results
+            .into_iter()
+            .map(|r| r.map_err(|e| Error::RocksDBError(format!("RocksDB fail: {e:?}"))))
+            .collect()
+    }
+
     pub fn put(&self, key: impl AsRef<[u8]>, value: impl AsRef<[u8]>, sync: bool) -> Result<()> {
         match self
             .db
@@ -88,10 +154,22 @@ impl RocksDB {
         }
     }
 
-    pub fn new_iterator(&self) -> RocksDBIterator {
+    pub fn catch_up_with_primary(&self) -> Result<()> {
+        match self.db.try_catch_up_with_primary() {
+            Ok(v) => Ok(v),
+            Err(e) => Err(Error::RocksDBError(format!("RocksDB fail: {e:?}"))),
+        }
+    }
+
+    /// Read a RocksDB property such as `rocksdb.estimate-num-keys`, `None` if it is unknown.
+    pub fn property(&self, name: &str) -> Option<String> {
+        self.db.property_value(name).ok().flatten()
+    }
+
+    pub fn new_iterator(&self) -> RocksDBIterator<'_> {
         let mut read_options = rocksdb::ReadOptions::default();
-        read_options.set_readahead_size(Size::mebibyte(4).into());
-        RocksDBIterator(self.db.raw_iterator_opt(read_options))
+        read_options.set_readahead_size(self.readahead_size.into());
+        RocksDBIterator(self.db.raw_iterator_opt(read_options), 0)
     }
 }
 
@@ -101,7 +179,8 @@ impl Drop for RocksDB {
     }
 }
 
-pub struct RocksDBIterator<'a>(rocksdb::DBRawIterator<'a>);
+/// The second field counts the keys visited, added to `IoStats` when the iterator is dropped.
+pub struct RocksDBIterator<'a>(rocksdb::DBRawIterator<'a>, u64);
 
 impl RocksDBIterator<'_> {
     pub fn iterate<P, Fn>(&mut self, prefix: P, mut func: Fn) -> Result<u32>
@@ -117,6 +196,7 @@ impl RocksDBIterator<'_> {
             it.next();
             count += 1;
         }
+        self.1 += count as u64;
         self.status()?;
         Ok(count)
     }
@@ -126,6 +206,7 @@ impl RocksDBIterator<'_> {
         P: AsRef<[u8]>,
     {
         self.0.seek(prefix.as_ref());
+        self.1 += self.0.valid() as u64;
         self.status()
     }
 
@@ -141,6 +222,7 @@ impl RocksDBIterator<'_> {
 
     pub fn next(&mut self) {
         self.0.next();
+        self.1 += self.0.valid() as u64;
     }
 
     pub fn key(&self) -> Option<&[u8]> {
@@ -152,6 +234,12 @@ impl RocksDBIterator<'_> {
     }
 }
 
+impl Drop for RocksDBIterator<'_> {
+    fn drop(&mut self) {
+        IoStats::add_keys_scanned(self.1);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -307,8 +395,99 @@ mod tests {
         let config = RocksDBConfig {
             path: dir.path().into(),
             create: false,
-            read_only: true,
+            mode: DbOpenMode::ReadOnly,
+            ..Default::default()
         };
         RocksDB::open::<MetaMergeOp>(&config).unwrap();
     }
+
+    #[test]
+    fn test_rocksdb_scan_options() {
+        use super::super::*;
+        use crate::Size;
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = RocksDBConfig {
+            path: dir.path().into(),
+            create: true,
+            ..Default::default()
+        };
+        let rocksdb = RocksDB::open::<MetaMergeOp>(&config).unwrap();
+        for i in 0..16u32 {
+            rocksdb.put(i.to_be_bytes(), "value", true).unwrap();
+        }
+        drop(rocksdb);
+
+        for block_cache_size in [Some(Size::zero()), Some(Size::mebibyte(8)), None] {
+            let config = RocksDBConfig {
+                path: dir.path().into(),
+                mode: DbOpenMode::ReadOnly,
+                tuning: RocksDBTuning {
+                    block_cache_size,
+                    bloom_filter: false,
+                    readahead_size: Size::mebibyte(16),
+                },
+                ..Default::default()
+            };
+            let rocksdb = RocksDB::open::<MetaMergeOp>(&config).unwrap();
+            let mut it = rocksdb.new_iterator();
+            let count = it.iterate([], |_, _| Ok(())).unwrap();
+            assert_eq!(count, 16);
+        }
+    }
+
+    #[test]
+    fn test_rocksdb_property() {
+        use super::super::*;
+        let dir = tempfile::tempdir().unwrap();
+
+        let config = RocksDBConfig {
+            path: dir.path().into(),
+            create: true,
+            ..Default::default()
+        };
+        let rocksdb = RocksDB::open::<MetaMergeOp>(&config).unwrap();
+        rocksdb.put("key", "value", true).unwrap();
+
+        let keys = rocksdb.property("rocksdb.estimate-num-keys").unwrap();
+        assert!(keys.parse::<u64>().is_ok());
+        assert_eq!(rocksdb.property("no-such-property"), None);
+    }
+
+    #[test]
+    fn test_rocksdb_secondary() {
+        use super::super::*;
+        let dir = tempfile::tempdir().unwrap();
+        let wal_dir = tempfile::tempdir().unwrap();
+
+        let config = RocksDBConfig {
+            path: dir.path().into(),
+            create: true,
+            ..Default::default()
+        };
+        let primary = RocksDB::open::<MetaMergeOp>(&config).unwrap();
+        primary.put("merry", "world", true).unwrap();
+
+        let config = RocksDBConfig {
+            path: dir.path().into(),
+            create: false,
+            mode: DbOpenMode::Secondary {
+                wal_dir: wal_dir.path().into(),
+            },
+            ..Default::default()
+        };
+        let secondary = RocksDB::open::<MetaMergeOp>(&config).unwrap();
+        assert_eq!(
+            secondary.get("merry").unwrap().as_deref(),
+            Some("world".as_bytes())
+        );
+        assert!(secondary.put("peace", "love", false).is_err());
+
+        primary.put("peace", "love", true).unwrap();
+        secondary.catch_up_with_primary().unwrap();
+        assert_eq!(
+            secondary.get("peace").unwrap().as_deref(),
+            Some("love".as_bytes())
+        );
+    }
 }
@@ -0,0 +1,127 @@
+use super::super::*;
+
+/// The checksum the engine stores in `ChunkMeta::checksum`: CRC-32C (Castagnoli) of the chunk's
+/// `len` bytes. Every recomputation of a stored checksum goes through here so none of them can
+/// drift from what the engine writes.
+pub fn compute_checksum(data: &[u8]) -> u32 {
+    crc32c::crc32c(data)
+}
+
+/// Algorithms that may have produced `ChunkMeta::checksum`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkChecksum {
+    #[default]
+    Crc32c,
+    Crc32,
+    /// XXH64 with seed 0, truncated to the low 32 bits.
+    XxHash64,
+}
+
+impl ChunkChecksum {
+    pub const ALL: [ChunkChecksum; 3] = [Self::Crc32c, Self::Crc32, Self::XxHash64];
+
+    /// The algorithm the engine writes.
+    pub const ENGINE: ChunkChecksum = Self::Crc32c;
+
+    pub fn compute(self, data: &[u8]) -> u32 {
+        match self {
+            Self::Crc32c => compute_checksum(data),
+            Self::Crc32 => crc32fast::hash(data),
+            Self::XxHash64 => xxhash_rust::xxh64::xxh64(data, 0) as u32,
+        }
+    }
+
+    /// Find a supported algorithm whose checksum of `data` is `expected`.
+    pub fn detect(data: &[u8], expected: u32) -> Option<ChunkChecksum> {
+        Self::ALL
+            .into_iter()
+            .find(|algo| algo.compute(data) == expected)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Crc32c => "crc32c",
+            Self::Crc32 => "crc32",
+            Self::XxHash64 => "xxhash64",
+        }
+    }
+}
+
+impl std::str::FromStr for ChunkChecksum {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|algo| algo.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                Error::InvalidArg(format!(
+                    "unknown checksum algorithm {s:?}, expected one of crc32c, crc32, xxhash64"
+                ))
+            })
+    }
+}
+
+impl std::fmt::Display for ChunkChecksum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_checksum() {
+        let data = "123456789".as_bytes();
+        assert_eq!(ChunkChecksum::Crc32c.compute(data), 0xE3069283);
+        assert_eq!(ChunkChecksum::Crc32.compute(data), 0xCBF43926);
+        // The low 32 bits of XXH64 0x8CB841DB40E6AE83
+        assert_eq!(ChunkChecksum::XxHash64.compute(data), 0x40E6AE83);
+        assert_eq!(ChunkChecksum::XxHash64.compute(&[]), 0x51D8E999);
+        assert_eq!(ChunkChecksum::Crc32c.compute(&[]), 0);
+        assert_eq!(ChunkChecksum::Crc32.compute(&[]), 0);
+
+        assert_eq!(ChunkChecksum::default(), ChunkChecksum::ENGINE);
+        assert_eq!(
+            ChunkChecksum::detect(data, 0xCBF43926),
+            Some(ChunkChecksum::Crc32)
+        );
+        assert_eq!(ChunkChecksum::detect(data, 0x12345678), None);
+
+        for algo in ChunkChecksum::ALL {
+            assert_eq!(algo.to_string().parse::<ChunkChecksum>(), Ok(algo));
+        }
+        assert_eq!("CRC32C".parse::<ChunkChecksum>(), Ok(ChunkChecksum::Crc32c));
+        assert!("md5".parse::<ChunkChecksum>().is_err());
+    }
+
+    #[test]
+    fn test_compute_checksum() {
+        assert_eq!(compute_checksum("123456789".as_bytes()), 0xE3069283);
+        assert_eq!(compute_checksum(&[]), 0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = EngineConfig {
+            path: dir.path().into(),
+            create: true,
+            ..Default::default()
+        };
+        let engine = Engine::open(&config).unwrap();
+        let data: Vec<u8> = (0..10000u32).map(|i| (i * 7) as u8).collect();
+        // Written in two parts, so the stored checksum is combined rather than computed at once
+        engine
+            .write(b"chunk", &data[..4096], 0, compute_checksum(&data[..4096]))
+            .unwrap();
+        let chunk = engine
+            .write(
+                b"chunk",
+                &data[4096..],
+                4096,
+                compute_checksum(&data[4096..]),
+            )
+            .unwrap();
+        assert_eq!(chunk.meta().checksum, compute_checksum(&data));
+    }
+}
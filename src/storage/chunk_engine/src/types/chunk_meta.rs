@@ -2,13 +2,20 @@ use super::super::*;
 
 pub type ETag = tinyvec::TinyVec<[u8; 14]>;
 
+/// Metadata of a chunk, stored with derse.
+///
+/// With the `serde` feature it also has a serde representation for external tools, which is
+/// stable: the field names are the ones below, `pos` is the raw 64-bit position, `checksum`
+/// is a number rather than a hex string, and `etag` is an array of bytes.
 #[derive(derse::Serialize, derse::Deserialize, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct ChunkMeta {
     pub pos: Position,
     pub chain_ver: u32,
     pub chunk_ver: u32,
     pub len: u32,
+    /// 32-bit checksum of the data, written with `ChunkChecksum::ENGINE`.
     pub checksum: u32,
     pub timestamp: u64,
     pub last_request_id: u64,
@@ -81,4 +88,41 @@ mod tests {
         let der = ChunkMeta::deserialize(&bytes[..]).unwrap();
         assert_eq!(ser, der);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_chunk_meta_serde() {
+        let meta = ChunkMeta {
+            pos: Position::new(GroupId::new(Size::MB, 1, 2), 3),
+            chain_ver: 4,
+            chunk_ver: 5,
+            len: 6,
+            checksum: 0xdeadbeef,
+            timestamp: 7,
+            etag: ETag::from(b"hi".as_slice()),
+            uncommitted: true,
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&meta).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "pos": meta.pos.0,
+                "chain_ver": 4,
+                "chunk_ver": 5,
+                "len": 6,
+                "checksum": 0xdeadbeefu32,
+                "timestamp": 7,
+                "last_request_id": 0,
+                "last_client_low": 0,
+                "last_client_high": 0,
+                "etag": [b'h', b'i'],
+                "uncommitted": true,
+            })
+        );
+        let back: ChunkMeta = serde_json::from_value(json).unwrap();
+        assert_eq!(back, meta);
+        assert_eq!(serde_json::to_string(&Size::MB).unwrap(), "1048576");
+    }
 }
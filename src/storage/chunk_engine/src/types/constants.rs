@@ -6,3 +6,12 @@ pub const CHUNK_SIZE_LARGE: Size = Size::mebibyte(4);
 pub const CHUNK_SIZE_ULTRA: Size = Size::mebibyte(64);
 pub const CHUNK_SIZE_SHIFT: usize = 16; // 64KiB is 2^16
 pub const CHUNK_SIZE_NUMBER: usize = 11; // from 64KiB to 64MiB
+
+// `Size::all_buckets` doubles from the smallest size and `Allocators` indexes buckets by their
+// shift, so the ladder must be CHUNK_SIZE_NUMBER powers of two from 2^CHUNK_SIZE_SHIFT.
+const _: () = assert!(CHUNK_SIZE_SMALL.0 == 1 << CHUNK_SIZE_SHIFT);
+const _: () = assert!(CHUNK_SIZE_ULTRA.0 == CHUNK_SIZE_SMALL.0 << (CHUNK_SIZE_NUMBER - 1));
+const _: () =
+    assert!(CHUNK_SIZE_NORMAL.0.is_power_of_two() && CHUNK_SIZE_LARGE.0.is_power_of_two());
+const _: () =
+    assert!(CHUNK_SIZE_SMALL.0 < CHUNK_SIZE_NORMAL.0 && CHUNK_SIZE_LARGE.0 < CHUNK_SIZE_ULTRA.0);
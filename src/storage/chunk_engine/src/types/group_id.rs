@@ -30,6 +30,11 @@ impl GroupId {
         (self.0 as u32) >> Self::SHIFT
     }
 
+    /// Index of the group within its chunk size, groups are allocated in this order.
+    pub const fn index(&self) -> u32 {
+        self.0 as u32
+    }
+
     pub fn offset(&self) -> Size {
         const MARKS: u64 = !(GroupId::COUNT - 1) as u64;
         self.chunk_size() * (self.0 & MARKS)
@@ -1,3 +1,4 @@
+mod checksum;
 mod chunk_meta;
 mod constants;
 mod group_id;
@@ -5,6 +6,7 @@ mod group_state;
 mod merge_state;
 mod position;
 
+pub use checksum::*;
 pub use chunk_meta::*;
 pub use constants::*;
 pub use group_id::*;
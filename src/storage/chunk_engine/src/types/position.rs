@@ -3,6 +3,11 @@ use super::super::*;
 use derse::{Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(C)]
 pub struct Position(pub u64);
 
@@ -33,6 +38,11 @@ impl Position {
         (self.0 as u32) >> Self::SHIFT
     }
 
+    /// Index of the group holding this position, see `GroupId::index`.
+    pub fn group_index(&self) -> u32 {
+        self.group_id().index()
+    }
+
     pub fn index(&self) -> u8 {
         self.0 as u8
     }
@@ -108,6 +118,8 @@ mod tests {
         assert_eq!(position.group(), 233);
         assert_eq!(position.index(), 223);
         assert_eq!(position.group_id(), group_id);
+        assert_eq!(group_id.index(), 233 << 8 | 23);
+        assert_eq!(position.group_index(), group_id.index());
         assert_eq!(position.to_be_bytes().len(), 8);
         assert_eq!(
             format!("{:?}", position),
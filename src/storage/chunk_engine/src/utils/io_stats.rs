@@ -0,0 +1,129 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Process-wide counters of the reads done through this crate, for tools that report what a
+/// command cost. Counting is off until `enable` is called, so the engine pays a single relaxed
+/// load per read otherwise.
+struct IoCounters {
+    enabled: AtomicBool,
+    keys_scanned: AtomicU64,
+    key_lookups: AtomicU64,
+    chunk_reads: AtomicU64,
+    bytes_read: AtomicU64,
+}
+
+static COUNTERS: IoCounters = IoCounters {
+    enabled: AtomicBool::new(false),
+    keys_scanned: AtomicU64::new(0),
+    key_lookups: AtomicU64::new(0),
+    chunk_reads: AtomicU64::new(0),
+    bytes_read: AtomicU64::new(0),
+};
+
+/// A snapshot of the I/O counters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IoStats {
+    /// Keys visited by RocksDB iterators.
+    pub keys_scanned: u64,
+    /// Keys looked up by a point get, one per key of a batched lookup.
+    pub key_lookups: u64,
+    /// Reads and mappings of chunk data.
+    pub chunk_reads: u64,
+    pub bytes_read: u64,
+}
+
+impl IoStats {
+    /// Start counting, the counters only cover reads done afterwards.
+    pub fn enable() {
+        COUNTERS.enabled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn snapshot() -> Self {
+        Self {
+            keys_scanned: COUNTERS.keys_scanned.load(Ordering::Relaxed),
+            key_lookups: COUNTERS.key_lookups.load(Ordering::Relaxed),
+            chunk_reads: COUNTERS.chunk_reads.load(Ordering::Relaxed),
+            bytes_read: COUNTERS.bytes_read.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The reads done since `earlier` was taken.
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            keys_scanned: self.keys_scanned - earlier.keys_scanned,
+            key_lookups: self.key_lookups - earlier.key_lookups,
+            chunk_reads: self.chunk_reads - earlier.chunk_reads,
+            bytes_read: self.bytes_read - earlier.bytes_read,
+        }
+    }
+
+    pub(crate) fn add_keys_scanned(count: u64) {
+        if count > 0 && COUNTERS.enabled.load(Ordering::Relaxed) {
+            COUNTERS.keys_scanned.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn add_key_lookups(count: u64) {
+        if COUNTERS.enabled.load(Ordering::Relaxed) {
+            COUNTERS.key_lookups.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn add_chunk_read(bytes: u64) {
+        if COUNTERS.enabled.load(Ordering::Relaxed) {
+            COUNTERS.chunk_reads.fetch_add(1, Ordering::Relaxed);
+            COUNTERS.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::*;
+
+    #[test]
+    fn test_io_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = MetaStoreConfig {
+            rocksdb: RocksDBConfig {
+                path: dir.path().join("meta"),
+                create: true,
+                ..Default::default()
+            },
+            prefix_len: 4,
+            ..Default::default()
+        };
+        let meta_store = MetaStore::open(&config).unwrap();
+        let clusters = Clusters::open(&ClustersConfig {
+            path: dir.path().join("data"),
+            chunk_size: CHUNK_SIZE_NORMAL,
+            create: true,
+        })
+        .unwrap();
+        let group_id = GroupId::new(CHUNK_SIZE_NORMAL, 0, 0);
+        clusters.allocate(group_id).unwrap();
+        for i in 0..8u32 {
+            let chunk_meta = ChunkMeta {
+                pos: Position::new(group_id, i as u8),
+                ..Default::default()
+            };
+            meta_store
+                .add_chunk(&i.to_be_bytes(), &chunk_meta, false)
+                .unwrap();
+        }
+
+        IoStats::enable();
+        // Other tests share the counters, so only lower bounds hold
+        let before = IoStats::snapshot();
+        assert_eq!(meta_store.chunks().count(), 8);
+        meta_store.get_chunk_meta(&0u32.to_be_bytes()).unwrap();
+        let mut buf = [0u8; 4096];
+        clusters
+            .pread(Position::new(group_id, 0), &mut buf, 0)
+            .unwrap();
+        let stats = IoStats::snapshot().since(&before);
+        assert!(stats.keys_scanned >= 8, "{:?}", stats);
+        assert!(stats.key_lookups >= 1, "{:?}", stats);
+        assert!(stats.chunk_reads >= 1, "{:?}", stats);
+        assert!(stats.bytes_read >= 4096, "{:?}", stats);
+    }
+}
@@ -1,5 +1,6 @@
 mod aligned;
 mod bytes;
+mod io_stats;
 mod result;
 mod shards_map;
 mod shards_set;
@@ -8,6 +9,7 @@ mod worker;
 
 pub use aligned::*;
 pub use bytes::*;
+pub use io_stats::*;
 pub use result::*;
 pub use shards_map::*;
 pub use shards_set::*;
@@ -12,13 +12,22 @@ pub enum Error {
     ChunkCommittedUpdate(String),
     ChunkMissingUpdate(String),
     NoSpace,
+    Timeout(String),
+    /// The chunk data directory does not exist or cannot be read, while the metadata store opened fine.
+    DataDirMissing(std::path::PathBuf),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(self, f)
+        match self {
+            Self::DataDirMissing(path) => write!(
+                f,
+                "DataDirMissing({path:?}): the metadata store opened but the chunk data directory is missing or unreadable"
+            ),
+            _ => std::fmt::Debug::fmt(self, f),
+        }
     }
 }
 
@@ -30,5 +39,10 @@ mod tests {
     fn test_error_display() {
         let error = Error::InvalidArg("invalid pos".into());
         assert_eq!(error.to_string(), r#"InvalidArg("invalid pos")"#);
+
+        let error = Error::DataDirMissing("/data/chunks".into());
+        assert!(error
+            .to_string()
+            .starts_with(r#"DataDirMissing("/data/chunks"): the metadata store opened"#));
     }
 }
@@ -1,4 +1,9 @@
 #[derive(Default, Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[repr(C)]
 pub struct Size(pub u64);
 
@@ -64,6 +69,27 @@ impl Size {
     pub fn trailing_zeros(&self) -> u32 {
         self.0.trailing_zeros()
     }
+
+    /// All chunk size buckets, from `CHUNK_SIZE_SMALL` to `CHUNK_SIZE_ULTRA` inclusive.
+    pub fn all_buckets() -> impl Iterator<Item = Size> {
+        std::iter::successors(Some(crate::CHUNK_SIZE_SMALL), Size::next_bucket)
+    }
+
+    pub fn is_valid_bucket(&self) -> bool {
+        self.is_power_of_two()
+            && crate::CHUNK_SIZE_SMALL <= *self
+            && *self <= crate::CHUNK_SIZE_ULTRA
+    }
+
+    /// The next larger bucket, `None` for the largest one or a size that is not a bucket.
+    pub fn next_bucket(&self) -> Option<Size> {
+        (self.is_valid_bucket() && *self < crate::CHUNK_SIZE_ULTRA).then_some(*self * 2)
+    }
+
+    /// The next smaller bucket, `None` for the smallest one or a size that is not a bucket.
+    pub fn prev_bucket(&self) -> Option<Size> {
+        (self.is_valid_bucket() && *self > crate::CHUNK_SIZE_SMALL).then_some(Size(self.0 / 2))
+    }
 }
 
 macro_rules! impl_trait_for_size {
@@ -229,6 +255,41 @@ impl std::fmt::Debug for Size {
 
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn test_size_buckets() {
+        use super::Size;
+        use crate::*;
+
+        let buckets: Vec<Size> = Size::all_buckets().collect();
+        assert_eq!(buckets.len(), CHUNK_SIZE_NUMBER);
+        assert_eq!(buckets.first(), Some(&CHUNK_SIZE_SMALL));
+        assert_eq!(buckets.last(), Some(&CHUNK_SIZE_ULTRA));
+        for (i, bucket) in buckets.iter().enumerate() {
+            assert_eq!(*bucket, CHUNK_SIZE_SMALL * (1u64 << i));
+            assert!(bucket.is_valid_bucket());
+            // The index `Allocators` keeps the bucket at
+            assert_eq!(bucket.trailing_zeros() as usize - CHUNK_SIZE_SHIFT, i);
+        }
+        assert!(buckets.contains(&CHUNK_SIZE_NORMAL));
+        assert!(buckets.contains(&CHUNK_SIZE_LARGE));
+
+        assert_eq!(CHUNK_SIZE_SMALL.prev_bucket(), None);
+        assert_eq!(CHUNK_SIZE_SMALL.next_bucket(), Some(Size::kibibyte(128)));
+        assert_eq!(CHUNK_SIZE_ULTRA.next_bucket(), None);
+        assert_eq!(CHUNK_SIZE_ULTRA.prev_bucket(), Some(Size::mebibyte(32)));
+
+        for size in [
+            Size::zero(),
+            Size::kibibyte(32),
+            Size::mebibyte(5),
+            Size::mebibyte(128),
+        ] {
+            assert!(!size.is_valid_bucket());
+            assert_eq!(size.next_bucket(), None);
+            assert_eq!(size.prev_bucket(), None);
+        }
+    }
+
     #[test]
     fn test_size() {
         use super::Size;